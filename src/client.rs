@@ -14,6 +14,7 @@ use reqwest::blocking::{Client, RequestBuilder, Response};
 use serde::{Deserialize, Serialize};
 
 use crate::models::{Draft, DraftContent, Metric, Note, Pulse};
+use crate::urlencoding;
 
 const DEFAULT_URL: &str = "http://127.0.0.1:7780";
 
@@ -134,6 +135,10 @@ impl Api {
         let rb = http_client()?.delete(format!("{}{path}", base_url()));
         auth(rb)?.send().map_err(Into::into)
     }
+    pub fn post_multipart(path: &str, form: reqwest::blocking::multipart::Form) -> Result<Response> {
+        let rb = http_client()?.post(format!("{}{path}", base_url())).multipart(form);
+        auth(rb)?.send().map_err(Into::into)
+    }
     pub fn get_json<T: for<'de> Deserialize<'de>>(path: &str) -> Result<T> {
         json_or_err(Self::get(path)?)
     }
@@ -165,6 +170,18 @@ pub fn get_note(id: &str) -> Result<Note> {
     Api::get_json(&format!("/api/notes/{id}"))
 }
 
+/// A note's `[[wiki links]]` and what they resolve to.
+#[derive(Debug, Deserialize)]
+pub struct ResolvedLink {
+    pub target: String,
+    pub id: Option<String>,
+    pub title: Option<String>,
+}
+
+pub fn note_links(id: &str) -> Result<Vec<ResolvedLink>> {
+    Api::get_json(&format!("/api/notes/{id}/links"))
+}
+
 pub fn create_note(title: &str, tags: Vec<String>, notebook: &str, body: &str) -> Result<Note> {
     Api::post_json_reply(
         "/api/notes",
@@ -184,6 +201,7 @@ pub fn update_note(
     notebook: Option<String>,
     body: Option<String>,
     related: Option<Vec<String>>,
+    expected_updated: Option<chrono::NaiveDateTime>,
 ) -> Result<Note> {
     let mut payload = serde_json::json!({});
     if let Some(v) = title {
@@ -201,9 +219,37 @@ pub fn update_note(
     if let Some(v) = related {
         payload["related"] = serde_json::Value::Array(v.into_iter().map(serde_json::Value::String).collect());
     }
-    Api::put_json_reply(&format!("/api/notes/{id}"), &payload)
+    if let Some(v) = expected_updated {
+        payload["expected_updated"] = serde_json::json!(v);
+    }
+    let resp = Api::put_json(&format!("/api/notes/{id}"), &payload)?;
+    if resp.status() == reqwest::StatusCode::CONFLICT {
+        #[derive(Deserialize)]
+        struct Reply {
+            current: Note,
+        }
+        let reply: Reply = resp.json()?;
+        return Err(Conflict { current: reply.current }.into());
+    }
+    json_or_err(resp)
 }
 
+/// An `update_note` refused because the note changed on the server after
+/// `expected_updated`. Reach it with `err.downcast_ref::<Conflict>()`.
+#[derive(Debug)]
+pub struct Conflict {
+    /// The server's copy.
+    pub current: Note,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} was changed on the server at {}", self.current.id, self.current.updated)
+    }
+}
+
+impl std::error::Error for Conflict {}
+
 pub fn delete_note(id: &str) -> Result<()> {
     let resp = Api::delete(&format!("/api/notes/{id}"))?;
     if !resp.status().is_success() {
@@ -212,46 +258,170 @@ pub fn delete_note(id: &str) -> Result<()> {
     Ok(())
 }
 
+/// `GET /api/notes/search`: the hits, and the `x-did-you-mean` correction
+/// the server sends when nothing matched as typed.
+#[derive(Debug)]
+pub struct SearchResults {
+    pub hits: Vec<crate::snippet::SearchHit>,
+    pub did_you_mean: Option<String>,
+}
+
 pub fn search_notes(
     q: &str,
     field: &str,
     ignore_case: bool,
     whole_word: bool,
-) -> Result<Vec<Note>> {
+    regex: bool,
+    fuzzy: bool,
+) -> Result<SearchResults> {
     let path = format!(
-        "/api/notes/search?q={}&field={}&ignore_case={}&whole_word={}",
+        "/api/notes/search?q={}&field={}&ignore_case={}&whole_word={}&regex={}&fuzzy={}",
         urlencoding::encode_or_self(q),
         field,
         ignore_case,
-        whole_word
+        whole_word,
+        regex,
+        fuzzy
     );
-    Api::get_json(&path)
+    let resp = Api::get(&path)?;
+    let did_you_mean = resp
+        .headers()
+        .get("x-did-you-mean")
+        .and_then(|v| v.to_str().ok())
+        .map(urlencoding::decode);
+    Ok(SearchResults { hits: json_or_err(resp)?, did_you_mean })
 }
 
-// tiny URL-encoder shim to avoid pulling in a crate for one call.
-mod urlencoding {
-    pub fn encode_or_self(s: &str) -> String {
-        let mut out = String::with_capacity(s.len());
-        for c in s.chars() {
-            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~') {
-                out.push(c);
-            } else {
-                let mut buf = [0u8; 4];
-                for b in c.encode_utf8(&mut buf).as_bytes() {
-                    out.push_str(&format!("%{:02X}", b));
-                }
-            }
-        }
-        out
-    }
+#[derive(Debug, Deserialize)]
+pub struct Suggestion {
+    pub id: String,
+    pub title: String,
+    pub notebook: String,
+}
+
+/// "Did you mean" for a search that found nothing.
+#[derive(Debug, Deserialize)]
+pub struct Suggestions {
+    pub query: Option<String>,
+    pub notes: Vec<Suggestion>,
+}
+
+pub fn suggest(q: &str, field: &str) -> Result<Suggestions> {
+    Api::get_json(&format!("/api/notes/suggest?q={}&field={}", urlencoding::encode_or_self(q), field))
+}
+
+/// One commit in a note's history (`GET /api/notes/:id/history`).
+#[derive(Debug, Deserialize)]
+pub struct Revision {
+    pub hash: String,
+    pub date: chrono::NaiveDateTime,
+    pub subject: String,
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+pub fn note_history(id: &str) -> Result<Vec<Revision>> {
+    Api::get_json(&format!("/api/notes/{id}/history"))
+}
+
+pub fn note_at_rev(id: &str, rev: &str) -> Result<Note> {
+    Api::get_json(&format!("/api/notes/{id}/history/{rev}"))
+}
+
+/// Reply of `POST /api/notes/:id/restore`.
+#[derive(Debug, Deserialize)]
+pub struct RestoreReport {
+    pub note: Note,
+    pub rev: String,
+}
+
+/// Restore a note from git: roll back to `rev`, or (without one) resurrect
+/// a deleted note from its last revision.
+pub fn restore_note(id: &str, rev: Option<&str>) -> Result<RestoreReport> {
+    Api::post_json_reply(
+        &format!("/api/notes/{id}/restore"),
+        &serde_json::json!({ "rev": rev }),
+    )
 }
 
 pub fn list_notebooks() -> Result<Vec<String>> {
-    let notes: Vec<Note> = Api::get_json("/api/notes")?;
-    let mut nbs: Vec<String> = notes.into_iter().map(|n| n.notebook).collect();
-    nbs.sort();
-    nbs.dedup();
-    Ok(nbs)
+    Ok(notebook_counts()?.into_iter().map(|n| n.notebook).collect())
+}
+
+/// One entry of `GET /api/notebooks`.
+#[derive(Debug, Deserialize)]
+pub struct NotebookCount {
+    pub notebook: String,
+    pub count: u32,
+}
+
+/// Reply of the bulk notebook endpoints: the rewritten notes.
+#[derive(Debug, Deserialize)]
+pub struct NotebookEdit {
+    pub notes: Vec<String>,
+}
+
+pub fn notebook_counts() -> Result<Vec<NotebookCount>> {
+    Api::get_json("/api/notebooks")
+}
+
+/// One level of `GET /api/notebooks/tree` or `GET /api/tags/tree`.
+#[derive(Debug, Deserialize)]
+pub struct TreeNode {
+    pub name: String,
+    pub path: String,
+    pub count: u32,
+    pub total: u32,
+    pub children: Vec<TreeNode>,
+}
+
+pub fn notebook_tree() -> Result<Vec<TreeNode>> {
+    Api::get_json("/api/notebooks/tree")
+}
+
+pub fn rename_notebook(from: &str, to: &str) -> Result<NotebookEdit> {
+    Api::post_json_reply("/api/notebooks/rename", &serde_json::json!({ "from": from, "to": to }))
+}
+
+pub fn merge_notebooks(from: &str, into: &str) -> Result<NotebookEdit> {
+    Api::post_json_reply("/api/notebooks/merge", &serde_json::json!({ "from": from, "into": into }))
+}
+
+pub fn move_notes(ids: &[String], notebook: &str) -> Result<NotebookEdit> {
+    Api::post_json_reply("/api/notes/move", &serde_json::json!({ "ids": ids, "notebook": notebook }))
+}
+
+/// One entry of `GET /api/tags`.
+#[derive(Debug, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: u32,
+}
+
+/// Reply of the bulk tag endpoints: the rewritten notes.
+#[derive(Debug, Deserialize)]
+pub struct TagEdit {
+    pub notes: Vec<String>,
+}
+
+pub fn list_tags() -> Result<Vec<TagCount>> {
+    Api::get_json("/api/tags")
+}
+
+pub fn tag_tree() -> Result<Vec<TreeNode>> {
+    Api::get_json("/api/tags/tree")
+}
+
+pub fn rename_tag(from: &str, to: &str) -> Result<TagEdit> {
+    Api::post_json_reply("/api/tags/rename", &serde_json::json!({ "from": from, "to": to }))
+}
+
+pub fn merge_tags(from: &[String], into: &str) -> Result<TagEdit> {
+    Api::post_json_reply("/api/tags/merge", &serde_json::json!({ "from": from, "into": into }))
+}
+
+pub fn delete_tag(tag: &str) -> Result<TagEdit> {
+    Api::post_json_reply("/api/tags/delete", &serde_json::json!({ "tag": tag }))
 }
 
 // ----- drafts ----------------------------------------------------------------
@@ -481,6 +651,128 @@ pub fn update_metric(id: &str, topic: Option<String>) -> Result<Metric> {
     Api::put_json_reply(&format!("/api/metrics/{id}"), &payload)
 }
 
+// ----- resources -----
+
+/// Reply entry of `POST /api/resources`.
+#[derive(Debug, Deserialize)]
+pub struct Uploaded {
+    pub name: String,
+    pub size: u64,
+    pub link: String,
+    pub markdown: String,
+    pub existed: bool,
+}
+
+/// Upload one file into the server's `resources/` directory.
+pub fn upload_resource(path: &std::path::Path) -> Result<Uploaded> {
+    let form = reqwest::blocking::multipart::Form::new()
+        .file("file", path)
+        .map_err(|e| anyhow!("reading {}: {e}", path.display()))?;
+    let mut out: Vec<Uploaded> = json_or_err(Api::post_multipart("/api/resources", form)?)?;
+    out.pop().ok_or_else(|| anyhow!("upload returned nothing"))
+}
+
+/// One file in `resources/`.
+#[derive(Debug, Deserialize)]
+pub struct Resource {
+    pub name: String,
+    pub size: u64,
+}
+
+/// A `resources/` link whose file is missing, with the notes using it.
+#[derive(Debug, Deserialize)]
+pub struct BrokenRef {
+    pub name: String,
+    pub notes: Vec<String>,
+}
+
+/// Reply of `GET /api/resources/audit`.
+#[derive(Debug, Deserialize)]
+pub struct ResourceAudit {
+    pub broken: Vec<BrokenRef>,
+    pub orphans: Vec<Resource>,
+}
+
+/// Reply of `DELETE /api/resources/orphans`.
+#[derive(Debug, Deserialize)]
+pub struct OrphanPurge {
+    pub deleted: Vec<String>,
+}
+
+pub fn audit_resources() -> Result<ResourceAudit> {
+    Api::get_json("/api/resources/audit")
+}
+
+pub fn delete_orphan_resources() -> Result<OrphanPurge> {
+    json_or_err(Api::delete("/api/resources/orphans")?)
+}
+
+// ----- saved searches -----
+
+pub fn list_searches() -> Result<Vec<crate::models::SavedSearch>> {
+    Api::get_json("/api/searches")
+}
+
+pub fn get_search(name: &str) -> Result<crate::models::SavedSearch> {
+    Api::get_json(&format!("/api/searches/{}", urlencoding::encode_or_self(name)))
+}
+
+pub fn save_search(
+    name: &str,
+    query: &str,
+    field: &str,
+    ignore_case: bool,
+    whole_word: bool,
+    regex: bool,
+) -> Result<crate::models::SavedSearch> {
+    Api::put_json_reply(
+        &format!("/api/searches/{}", urlencoding::encode_or_self(name)),
+        &serde_json::json!({
+            "query": query,
+            "field": field,
+            "ignore_case": ignore_case,
+            "whole_word": whole_word,
+            "regex": regex,
+        }),
+    )
+}
+
+pub fn delete_search(name: &str) -> Result<()> {
+    let _: serde_json::Value =
+        json_or_err(Api::delete(&format!("/api/searches/{}", urlencoding::encode_or_self(name)))?)?;
+    Ok(())
+}
+
+// ----- trash -----
+
+/// One trashed item (`GET /api/trash`).
+#[derive(Debug, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub kind: String,
+    pub title: String,
+    pub deleted_at: chrono::NaiveDateTime,
+}
+
+/// Reply of `DELETE /api/trash`.
+#[derive(Debug, Deserialize)]
+pub struct PurgeReport {
+    pub purged: Vec<String>,
+}
+
+pub fn list_trash() -> Result<Vec<TrashEntry>> {
+    Api::get_json("/api/trash")
+}
+
+/// Take an item out of the trash; returns it as restored.
+pub fn restore_from_trash(id: &str) -> Result<crate::yaml::Item> {
+    Api::post_json_reply(&format!("/api/trash/{id}/restore"), &serde_json::json!({}))
+}
+
+pub fn empty_trash() -> Result<PurgeReport> {
+    json_or_err(Api::delete("/api/trash")?)
+}
+
 // ----- admin -----
 
 /// Client-relevant server configuration (`GET /api/config`). The server is
@@ -507,12 +799,30 @@ pub struct ExportReport {
 #[derive(Debug, Deserialize)]
 pub struct ImportReport {
     pub items: usize,
+    #[serde(default)]
+    pub removed: usize,
+    #[serde(default)]
+    pub full: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SyncReport {
     pub changed_files: Vec<String>,
     pub items_loaded: usize,
+    #[serde(default)]
+    pub items_removed: usize,
+    #[serde(default)]
+    pub merged: bool,
+    #[serde(default)]
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// A field both sides of a sync changed; `kept` is `local` or `remote`.
+#[derive(Debug, Deserialize)]
+pub struct SyncConflict {
+    pub file: String,
+    pub field: String,
+    pub kept: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -538,23 +848,63 @@ pub struct BackupReport {
     #[serde(default)]
     pub pushed: bool,
     #[serde(default)]
+    pub remote: String,
+    #[serde(default)]
+    pub branch: String,
+    #[serde(default)]
     pub status: Option<BackupStatus>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpgradeFormatReport {
+    pub format_version: u32,
+    pub dry_run: bool,
+    pub upgraded: Vec<String>,
+    pub failed: Vec<String>,
+    pub committed: bool,
+}
+
 pub fn export() -> Result<ExportReport> {
     Api::post_json_reply("/api/export", &serde_json::json!({}))
 }
 
-pub fn import() -> Result<ImportReport> {
-    Api::post_json_reply("/api/import", &serde_json::json!({}))
+pub fn import(full: bool) -> Result<ImportReport> {
+    Api::post_json_reply("/api/import", &serde_json::json!({ "full": full }))
+}
+
+pub fn backup(dry_run: bool, to: Option<&str>) -> Result<BackupReport> {
+    Api::post_json_reply("/api/backup", &serde_json::json!({ "dry_run": dry_run, "to": to }))
+}
+
+/// A named git remote of the server's repo (`GET /api/remotes`).
+#[derive(Debug, Deserialize)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub url: String,
+    /// The remote `backup` / `sync` use without `--to`.
+    pub default: bool,
+}
+
+pub fn list_remotes() -> Result<Vec<RemoteInfo>> {
+    Api::get_json("/api/remotes")
+}
+
+pub fn add_remote(name: &str, url: &str) -> Result<RemoteInfo> {
+    Api::post_json_reply("/api/remotes", &serde_json::json!({ "name": name, "url": url }))
+}
+
+pub fn remove_remote(name: &str) -> Result<()> {
+    let _: serde_json::Value =
+        json_or_err(Api::delete(&format!("/api/remotes/{}", urlencoding::encode_or_self(name)))?)?;
+    Ok(())
 }
 
-pub fn backup(dry_run: bool) -> Result<BackupReport> {
-    Api::post_json_reply("/api/backup", &serde_json::json!({ "dry_run": dry_run }))
+pub fn sync(ff_only: bool) -> Result<SyncReport> {
+    Api::post_json_reply("/api/sync", &serde_json::json!({ "ff_only": ff_only }))
 }
 
-pub fn sync() -> Result<SyncReport> {
-    Api::post_json_reply("/api/sync", &serde_json::json!({}))
+pub fn upgrade_format(dry_run: bool) -> Result<UpgradeFormatReport> {
+    Api::post_json_reply("/api/upgrade-format", &serde_json::json!({ "dry_run": dry_run }))
 }
 
 #[cfg(test)]
@@ -4,15 +4,19 @@
 //! cold start / sync. The DB is rebuilt from YAML by the server's `import`
 //! command. Schema version lives in the `meta` table.
 
+use std::collections::HashMap;
 use std::path::Path;
 
 use anyhow::{Context, Result};
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{NaiveDate, NaiveDateTime, SubsecRound};
 use rusqlite::{params, Connection, OptionalExtension};
 
+use crate::fuzzy;
 use crate::models::{Draft, DraftContent, Interval, Metric, MetricPoint, Note, Pulse, PulseSlot};
+use crate::query::{Query, Term};
+use crate::wikilink::{self, fold_title};
 
-pub const SCHEMA_VERSION: u32 = 1;
+pub const SCHEMA_VERSION: u32 = 5;
 
 const SCHEMA: &str = r#"
 PRAGMA foreign_keys = ON;
@@ -27,21 +31,36 @@ CREATE TABLE IF NOT EXISTS notes (
     title    TEXT NOT NULL,
     tags     TEXT NOT NULL,           -- JSON array
     notebook TEXT NOT NULL,
-    created  TEXT NOT NULL,           -- RFC3339-ish "YYYY-MM-DDTHH:MM:SS"
+    created  TEXT NOT NULL,           -- RFC3339-ish "YYYY-MM-DDTHH:MM:SS[.f]"
     updated  TEXT NOT NULL,
     related  TEXT NOT NULL DEFAULT '[]',  -- JSON array of note IDs
-    body     TEXT NOT NULL
+    body     TEXT NOT NULL,
+    deleted_at TEXT,                  -- set while in the trash
+    title_folded TEXT NOT NULL DEFAULT ''  -- wikilink::fold_title(title)
 );
 
 CREATE INDEX IF NOT EXISTS idx_notes_updated ON notes(updated);
 CREATE INDEX IF NOT EXISTS idx_notes_created ON notes(created);
 CREATE INDEX IF NOT EXISTS idx_notes_notebook ON notes(notebook);
+CREATE INDEX IF NOT EXISTS idx_notes_title_folded ON notes(title_folded);
+
+-- Full-text index over live notes, keyed by `notes.rowid`. Kept in sync by
+-- `upsert_note` / `delete_note` (not triggers) so every write path that goes
+-- through this module stays indexed; `tags` holds the "; "-joined list.
+CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+    title, tags, notebook, body,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- The index's words and how many notes have each, for `did_you_mean`.
+CREATE VIRTUAL TABLE IF NOT EXISTS notes_vocab USING fts5vocab(notes_fts, row);
 
 CREATE TABLE IF NOT EXISTS pulses (
     id       TEXT PRIMARY KEY,
     topic    TEXT NOT NULL,
     interval TEXT NOT NULL,           -- "daily" | "weekly" | "monthly" | "yearly"
-    created  TEXT NOT NULL
+    created  TEXT NOT NULL,
+    deleted_at TEXT                   -- set while in the trash
 );
 
 CREATE TABLE IF NOT EXISTS pulse_slots (
@@ -55,7 +74,8 @@ CREATE TABLE IF NOT EXISTS pulse_slots (
 CREATE TABLE IF NOT EXISTS metrics (
     id      TEXT PRIMARY KEY,
     topic   TEXT NOT NULL,
-    created TEXT NOT NULL
+    created TEXT NOT NULL,
+    deleted_at TEXT                   -- set while in the trash
 );
 
 CREATE TABLE IF NOT EXISTS metric_points (
@@ -77,29 +97,40 @@ CREATE TABLE IF NOT EXISTS drafts (
 );
 "#;
 
+/// A schema step. `MIGRATIONS[i]` upgrades a DB at version `i + 1` to
+/// `i + 2`; steps run inside the caller's transaction.
+type Migration = fn(&Connection) -> Result<()>;
+
+/// Ordered schema steps, oldest first. Append a step (and bump
+/// `SCHEMA_VERSION`) whenever `SCHEMA` changes shape; `SCHEMA` itself always
+/// describes the latest version so fresh DBs skip the chain entirely.
+const MIGRATIONS: &[Migration] = &[
+    migrate_v1_to_v2, // notes_fts full-text index
+    migrate_v2_to_v3, // deleted_at (trash) columns
+    migrate_v3_to_v4, // notes.title_folded
+    migrate_v4_to_v5, // notes_vocab; trashed notes leave notes_fts
+];
+
 pub fn open(path: &Path) -> Result<Connection> {
     let conn = Connection::open(path).with_context(|| format!("opening {}", path.display()))?;
     conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")?;
-    conn.execute_batch(SCHEMA)?;
-    let current: Option<u32> = conn
-        .query_row(
-            "SELECT value FROM meta WHERE key = 'schema_version'",
-            [],
-            |r| r.get::<_, String>(0),
-        )
-        .optional()?
-        .and_then(|s| s.parse().ok());
-    match current {
-        Some(v) if v == SCHEMA_VERSION => {}
+    match stored_schema_version(&conn)? {
         None => {
+            conn.execute_batch(SCHEMA)?;
             conn.execute(
                 "INSERT INTO meta (key, value) VALUES ('schema_version', ?1)",
                 params![SCHEMA_VERSION.to_string()],
             )?;
         }
+        Some(v) if v == SCHEMA_VERSION => conn.execute_batch(SCHEMA)?,
+        Some(v) if v > 0 && v < SCHEMA_VERSION => {
+            backup_before_migration(&conn, path, v)?;
+            migrate(&conn, v)?;
+            conn.execute_batch(SCHEMA)?;
+        }
         Some(v) => {
             anyhow::bail!(
-                "DB schema version {} is newer/different than this build supports ({})",
+                "DB schema version {} is newer than this build supports ({}); upgrade ron",
                 v,
                 SCHEMA_VERSION
             );
@@ -108,8 +139,153 @@ pub fn open(path: &Path) -> Result<Connection> {
     Ok(conn)
 }
 
+/// The `meta.schema_version` of an existing DB, or `None` for a fresh file.
+fn stored_schema_version(conn: &Connection) -> Result<Option<u32>> {
+    let has_meta: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'meta')",
+        [],
+        |r| r.get(0),
+    )?;
+    if !has_meta {
+        return Ok(None);
+    }
+    let v = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'schema_version'",
+            [],
+            |r| r.get::<_, String>(0),
+        )
+        .optional()?;
+    match v {
+        None => Ok(None),
+        Some(s) => Ok(Some(
+            s.parse().with_context(|| format!("bad schema_version {s:?} in meta"))?,
+        )),
+    }
+}
+
+/// A `meta` value by key (`schema_version`, `yaml_head`, ...).
+pub fn get_meta(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row("SELECT value FROM meta WHERE key = ?1", params![key], |r| r.get(0))
+        .optional()?)
+}
+
+/// Set a `meta` value.
+pub fn set_meta(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+/// Path of the pre-migration snapshot for a DB at schema version `from`,
+/// e.g. `db.sqlite3.v1.bak` next to the DB.
+pub fn migration_backup_path(path: &Path, from: u32) -> std::path::PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{from}.bak"));
+    path.with_file_name(name)
+}
+
+/// Snapshot the DB before migrating it. `VACUUM INTO` writes a consistent
+/// copy even with a WAL in flight; an older snapshot of the same version is
+/// replaced.
+fn backup_before_migration(conn: &Connection, path: &Path, from: u32) -> Result<()> {
+    let backup = migration_backup_path(path, from);
+    if backup.exists() {
+        std::fs::remove_file(&backup).with_context(|| format!("removing {}", backup.display()))?;
+    }
+    conn.execute("VACUUM INTO ?1", params![backup.to_string_lossy()])
+        .with_context(|| format!("backing up DB to {}", backup.display()))?;
+    eprintln!(
+        "migrating DB schema v{from} -> v{SCHEMA_VERSION} (backup: {})",
+        backup.display()
+    );
+    Ok(())
+}
+
+/// Run every step from `from` up to `SCHEMA_VERSION` in one transaction, so
+/// a failing step leaves the DB at its old version (and the backup unused).
+fn migrate(conn: &Connection, from: u32) -> Result<()> {
+    debug_assert_eq!(MIGRATIONS.len() as u32 + 1, SCHEMA_VERSION);
+    let tx = conn.unchecked_transaction()?;
+    for (i, step) in MIGRATIONS.iter().enumerate().skip(from as usize - 1) {
+        let v = i as u32 + 1;
+        step(&tx).with_context(|| format!("migrating DB schema v{} -> v{}", v, v + 1))?;
+    }
+    tx.execute(
+        "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+        params![SCHEMA_VERSION.to_string()],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Creates the index empty: `index_note` reads the v3 `deleted_at`, so
+/// the rows come from the v4 -> v5 reindex, which every chain from here runs.
+fn migrate_v1_to_v2(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+            title, tags, notebook, body,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );",
+    )?;
+    Ok(())
+}
+
+fn migrate_v2_to_v3(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE notes ADD COLUMN deleted_at TEXT;
+         ALTER TABLE pulses ADD COLUMN deleted_at TEXT;
+         ALTER TABLE metrics ADD COLUMN deleted_at TEXT;",
+    )?;
+    Ok(())
+}
+
+/// SQLite's `lower()` and `NOCASE` fold ASCII only, so the Unicode-folded
+/// title is computed here and stored.
+fn migrate_v3_to_v4(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE notes ADD COLUMN title_folded TEXT NOT NULL DEFAULT '';")?;
+    let titles: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, title FROM notes")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    for (id, title) in titles {
+        conn.execute(
+            "UPDATE notes SET title_folded = ?1 WHERE id = ?2",
+            params![fold_title(&title), id],
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_v4_to_v5(conn: &Connection) -> Result<()> {
+    conn.execute_batch("CREATE VIRTUAL TABLE IF NOT EXISTS notes_vocab USING fts5vocab(notes_fts, row);")?;
+    reindex_notes(conn)
+}
+
+/// Rebuild the full-text index from scratch. Callers wrap this in a
+/// transaction.
+fn reindex_notes(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM notes_fts", [])?;
+    let ids: Vec<String> = {
+        let mut stmt = conn.prepare("SELECT id FROM notes")?;
+        let rows = stmt.query_map([], |r| r.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    for id in ids {
+        index_note(conn, &id)?;
+    }
+    Ok(())
+}
+
+/// Full precision, so an `updated` read back is the one written (the
+/// ETag compares it exactly); whole seconds print without a fraction.
 fn ts_to_str(t: NaiveDateTime) -> String {
-    t.format("%Y-%m-%dT%H:%M:%S").to_string()
+    t.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
 }
 
 fn ts_from_str(s: &str) -> Result<NaiveDateTime> {
@@ -142,12 +318,13 @@ pub fn upsert_note(conn: &Connection, note: &Note) -> Result<()> {
     let tags = serde_json::to_string(&note.tags)?;
     let related = serde_json::to_string(&note.related)?;
     conn.execute(
-        "INSERT INTO notes (id, title, tags, notebook, created, updated, related, body)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "INSERT INTO notes (id, title, tags, notebook, created, updated, related, body, deleted_at, title_folded)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
          ON CONFLICT(id) DO UPDATE SET
             title=excluded.title, tags=excluded.tags, notebook=excluded.notebook,
             created=excluded.created, updated=excluded.updated,
-            related=excluded.related, body=excluded.body",
+            related=excluded.related, body=excluded.body, deleted_at=excluded.deleted_at,
+            title_folded=excluded.title_folded",
         params![
             note.id,
             note.title,
@@ -157,14 +334,55 @@ pub fn upsert_note(conn: &Connection, note: &Note) -> Result<()> {
             ts_to_str(note.updated),
             related,
             note.body,
+            note.deleted_at.map(ts_to_str),
+            fold_title(&note.title),
         ],
     )?;
+    index_note(conn, &note.id)?;
+    Ok(())
+}
+
+/// Refresh the full-text row for one note from its `notes` row. Trashed
+/// notes have none, so the index's vocabulary is the live notes' words.
+fn index_note(conn: &Connection, id: &str) -> Result<()> {
+    let (rowid, title, tags_json, notebook, body, trashed): (i64, String, String, String, String, bool) = conn
+        .query_row(
+            "SELECT rowid, title, tags, notebook, body, deleted_at IS NOT NULL FROM notes WHERE id = ?1",
+            params![id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)),
+        )?;
+    let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+    conn.execute("DELETE FROM notes_fts WHERE rowid = ?1", params![rowid])?;
+    if trashed {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO notes_fts (rowid, title, tags, notebook, body) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![rowid, title, tags.join("; "), notebook, body],
+    )?;
     Ok(())
 }
 
+/// A live note by id; trashed notes read as absent.
 pub fn get_note(conn: &Connection, id: &str) -> Result<Option<Note>> {
     let row = conn
-        .query_row("SELECT * FROM notes WHERE id = ?1", params![id], note_from_row)
+        .query_row(
+            "SELECT * FROM notes WHERE id = ?1 AND deleted_at IS NULL",
+            params![id],
+            note_from_row,
+        )
+        .optional()?;
+    Ok(row)
+}
+
+/// A note by id, but only if it is in the trash.
+pub fn get_trashed_note(conn: &Connection, id: &str) -> Result<Option<Note>> {
+    let row = conn
+        .query_row(
+            "SELECT * FROM notes WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+            note_from_row,
+        )
         .optional()?;
     Ok(row)
 }
@@ -174,6 +392,7 @@ fn note_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Note> {
     let related_json: String = row.get("related")?;
     let created: String = row.get("created")?;
     let updated: String = row.get("updated")?;
+    let deleted_at: Option<String> = row.get("deleted_at")?;
     Ok(Note {
         id: row.get("id")?,
         title: row.get("title")?,
@@ -183,18 +402,25 @@ fn note_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Note> {
         updated: ts_from_str(&updated).unwrap_or_else(|_| chrono::Local::now().naive_local()),
         related: serde_json::from_str(&related_json).unwrap_or_default(),
         body: row.get("body")?,
+        deleted_at: deleted_at.and_then(|s| ts_from_str(&s).ok()),
     })
 }
 
+/// Remove a note row for good (a trash purge). Moving a note to the trash
+/// is an `upsert_note` with `deleted_at` set.
 pub fn delete_note(conn: &Connection, id: &str) -> Result<bool> {
+    conn.execute(
+        "DELETE FROM notes_fts WHERE rowid = (SELECT rowid FROM notes WHERE id = ?1)",
+        params![id],
+    )?;
     let n = conn.execute("DELETE FROM notes WHERE id = ?1", params![id])?;
     Ok(n > 0)
 }
 
 pub fn list_notes(conn: &Connection, limit: Option<u32>) -> Result<Vec<Note>> {
     let sql = match limit {
-        Some(n) => format!("SELECT * FROM notes ORDER BY updated DESC LIMIT {n}"),
-        None => "SELECT * FROM notes ORDER BY updated DESC".to_string(),
+        Some(n) => format!("SELECT * FROM notes WHERE deleted_at IS NULL ORDER BY updated DESC LIMIT {n}"),
+        None => "SELECT * FROM notes WHERE deleted_at IS NULL ORDER BY updated DESC".to_string(),
     };
     let mut stmt = conn.prepare(&sql)?;
     let rows = stmt.query_map([], note_from_row)?;
@@ -205,6 +431,233 @@ pub fn list_notes(conn: &Connection, limit: Option<u32>) -> Result<Vec<Note>> {
     Ok(out)
 }
 
+/// Live notes.
+pub fn count_notes(conn: &Connection) -> Result<usize> {
+    let n: i64 = conn.query_row("SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL", [], |r| r.get(0))?;
+    Ok(n as usize)
+}
+
+/// Live notes in `order` (`Relevance` has no query to rank by, so it lists
+/// as `Updated`), starting after `after`, at most `limit` of them. Ties on
+/// the sort key go by ID, so a cursor never skips or repeats a note.
+/// Titles sort by `title_folded`, the same folding [`sorted`] uses.
+pub fn page_notes(conn: &Connection, order: NoteOrder, after: Option<&Cursor>, limit: Option<u32>) -> Result<Vec<Note>> {
+    let (key, dir, cmp) = match order {
+        NoteOrder::Created => ("created", "DESC", "<"),
+        NoteOrder::Title => ("title_folded", "ASC", ">"),
+        NoteOrder::Updated | NoteOrder::Relevance => ("updated", "DESC", "<"),
+    };
+    let mut sql = "SELECT * FROM notes WHERE deleted_at IS NULL".to_string();
+    if after.is_some() {
+        sql.push_str(&format!(" AND ({key} {cmp} ?1 OR ({key} = ?1 AND id > ?2))"));
+    }
+    sql.push_str(&format!(" ORDER BY {key} {dir}, id ASC"));
+    if let Some(n) = limit {
+        sql.push_str(&format!(" LIMIT {n}"));
+    }
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = match after {
+        Some(c) => stmt.query_map(params![c.sort_key(order), c.id], note_from_row)?,
+        None => stmt.query_map([], note_from_row)?,
+    };
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+/// Where a page of notes ended: the sort key and ID of its last note,
+/// written `<key>,<id>` (`2026-08-06T14:32:00,note-…` for `Updated` and
+/// `Created`, the title for `Title`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub key: String,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn of(n: &Note, order: NoteOrder) -> Self {
+        let key = match order {
+            NoteOrder::Created => ts_to_str(n.created),
+            NoteOrder::Title => n.title.clone(),
+            NoteOrder::Updated | NoteOrder::Relevance => ts_to_str(n.updated),
+        };
+        Self { key, id: n.id.clone() }
+    }
+
+    /// The notes of an in-memory result list (a search, in `order`) that
+    /// sort after this cursor, found by key and ID the way [`page_notes`]
+    /// seeks, so the page goes on even if the cursor's note is gone. A
+    /// relevance rank isn't a key the cursor holds: there it goes by the
+    /// note's position, and starts over if the note left the results.
+    pub fn skip(&self, notes: Vec<Note>, order: NoteOrder) -> Vec<Note> {
+        if order == NoteOrder::Relevance {
+            return match notes.iter().position(|n| n.id == self.id) {
+                Some(i) => notes.into_iter().skip(i + 1).collect(),
+                None => notes,
+            };
+        }
+        let key = self.sort_key(order);
+        notes
+            .into_iter()
+            .filter(|n| {
+                let nk = Cursor::of(n, order).sort_key(order);
+                let past = if order == NoteOrder::Title { nk > key } else { nk < key };
+                past || (nk == key && n.id > self.id)
+            })
+            .collect()
+    }
+
+    /// The key as the notes sort by it: titles folded.
+    fn sort_key(&self, order: NoteOrder) -> String {
+        match order {
+            NoteOrder::Title => fold_title(&self.key),
+            _ => self.key.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.key, self.id)
+    }
+}
+
+impl std::str::FromStr for Cursor {
+    type Err = anyhow::Error;
+
+    /// IDs have no commas; the key (a title) may.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.rsplit_once(',') {
+            Some((key, id)) if !id.is_empty() => Ok(Self { key: key.into(), id: id.into() }),
+            _ => anyhow::bail!("bad cursor {s:?}: expected <key>,<id>"),
+        }
+    }
+}
+
+impl NoteOrder {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NoteOrder::Updated => "updated",
+            NoteOrder::Created => "created",
+            NoteOrder::Title => "title",
+            NoteOrder::Relevance => "relevance",
+        }
+    }
+}
+
+/// Notes in the trash, most recently trashed first.
+pub fn list_trashed_notes(conn: &Connection) -> Result<Vec<Note>> {
+    let mut stmt = conn.prepare("SELECT * FROM notes WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC")?;
+    let rows = stmt.query_map([], note_from_row)?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+/// Every notebook with live notes and how many, sorted by name.
+pub fn notebook_counts(conn: &Connection) -> Result<Vec<(String, u32)>> {
+    let mut stmt = conn.prepare(
+        "SELECT notebook, COUNT(*) FROM notes WHERE deleted_at IS NULL
+         GROUP BY notebook ORDER BY notebook",
+    )?;
+    let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+/// Notes in `notebook` or one of its sub-notebooks (`notebook/…`), trashed
+/// ones included.
+pub fn notes_in_notebook(conn: &Connection, notebook: &str) -> Result<Vec<Note>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM notes
+         WHERE notebook = ?1 OR substr(notebook, 1, length(?1) + 1) = ?1 || '/'
+         ORDER BY updated DESC",
+    )?;
+    let rows = stmt.query_map(params![notebook], note_from_row)?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+/// Every tag on a live note with the number of notes carrying it, sorted
+/// by tag.
+pub fn tag_counts(conn: &Connection) -> Result<Vec<(String, u32)>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.value, COUNT(DISTINCT notes.id) FROM notes, json_each(notes.tags) AS t
+         WHERE notes.deleted_at IS NULL GROUP BY t.value ORDER BY t.value",
+    )?;
+    let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+/// Notes carrying `tag` or one of its descendants (`tag/…`), trashed ones
+/// included (so a bulk tag edit also covers notes that may be restored
+/// later).
+pub fn notes_with_tag(conn: &Connection, tag: &str) -> Result<Vec<Note>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM notes WHERE EXISTS (SELECT 1 FROM json_each(notes.tags)
+             WHERE value = ?1 OR substr(value, 1, length(?1) + 1) = ?1 || '/')
+         ORDER BY updated DESC",
+    )?;
+    let rows = stmt.query_map(params![tag], note_from_row)?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
+/// The most recently updated live note titled `title` (compared with
+/// [`fold_title`]), for `[[Title]]` links.
+pub fn find_note_by_title(conn: &Connection, title: &str) -> Result<Option<Note>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM notes WHERE deleted_at IS NULL AND title_folded = ?1
+         ORDER BY updated DESC LIMIT 1",
+    )?;
+    let mut rows = stmt.query_map(params![fold_title(title)], note_from_row)?;
+    Ok(rows.next().transpose()?)
+}
+
+/// Live notes other than `id` that may link to it: `id` appears in their
+/// `related` list or anywhere in their body, or their body has a wiki link
+/// whose target is `title` (compared with [`fold_title`]). A cheap
+/// prefilter — callers check for whole-ID mentions.
+pub fn notes_linking_to(conn: &Connection, id: &str, title: &str) -> Result<Vec<Note>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM notes WHERE deleted_at IS NULL AND id != ?1
+           AND (instr(related, ?2) > 0 OR instr(body, ?1) > 0 OR instr(body, '[[') > 0)
+         ORDER BY updated DESC",
+    )?;
+    let rows = stmt.query_map(params![id, format!("\"{id}\"")], note_from_row)?;
+    // SQLite can't fold non-ASCII case, so the title check runs here on the
+    // parsed links (`[[ Title ]]`, `[[Title|label]]` included).
+    let title = fold_title(title);
+    let mut out = Vec::new();
+    for r in rows {
+        let n = r?;
+        if n.related.iter().any(|r| r == id)
+            || n.body.contains(id)
+            || wikilink::parse(&n.body).iter().any(|l| fold_title(&l.target) == title)
+        {
+            out.push(n);
+        }
+    }
+    Ok(out)
+}
+
 /// Text matching options for note search.
 #[derive(Clone, Copy, Debug)]
 pub struct NoteMatch {
@@ -216,6 +669,12 @@ pub struct NoteMatch {
     pub to: Option<NaiveDateTime>,
     /// Result ordering. `None` means newest-updated first.
     pub order_by: Option<NoteOrder>,
+    /// The pattern is a regular expression matched against the field text
+    /// instead of a query; `whole_word` anchors it at word boundaries.
+    pub regex: bool,
+    /// When nothing matches, fall back to typo-tolerant matching of title
+    /// and tag words ([`search_fuzzy`]).
+    pub fuzzy: bool,
 }
 
 impl Default for NoteMatch {
@@ -226,12 +685,23 @@ impl Default for NoteMatch {
             from: None,
             to: None,
             order_by: None,
+            regex: false,
+            fuzzy: false,
         }
     }
 }
 
+impl NoteMatch {
+    /// Whether `updated` falls within `from..=to`, compared to the second
+    /// like the bounds are given.
+    fn in_range(&self, updated: NaiveDateTime) -> bool {
+        let t = updated.trunc_subsecs(0);
+        self.from.is_none_or(|f| t >= f.trunc_subsecs(0)) && self.to.is_none_or(|to| t <= to.trunc_subsecs(0))
+    }
+}
+
 /// Search field.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum NoteField {
     Title,
     Tags,
@@ -239,82 +709,523 @@ pub enum NoteField {
     Content, // title + tags + notebook + body
 }
 
+impl std::str::FromStr for NoteField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "title" => NoteField::Title,
+            "tags" => NoteField::Tags,
+            "notebook" => NoteField::Notebook,
+            "content" => NoteField::Content,
+            _ => anyhow::bail!("unknown field {s:?}: expected content, title, tags or notebook"),
+        })
+    }
+}
+
 /// Sort key for search results.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum NoteOrder {
-    Updated, // newest first (desc)
-    Created, // newest first (desc)
-    Title,   // alphabetical, case-insensitive (asc)
+    Updated,   // newest first (desc)
+    Created,   // newest first (desc)
+    Title,     // alphabetical, case-insensitive (asc)
+    Relevance, // best bm25 score first, newest-updated breaks ties
+}
+
+/// A term's FTS5 MATCH expression, scoped to its field. The text is always
+/// emitted as a quoted string so user punctuation can never be read as FTS
+/// syntax. Bare words match as token prefixes unless `whole_word` is set; a
+/// trailing `*` always asks for a prefix match.
+fn fts_term(t: &Term, field: NoteField, whole_word: bool) -> String {
+    let star = if term_is_prefix(t, whole_word) { "*" } else { "" };
+    let body = format!("\"{}\"{star}", t.text.replace('"', "\"\""));
+    match t.field.unwrap_or(field) {
+        NoteField::Title => format!("title : {body}"),
+        NoteField::Tags => format!("tags : {body}"),
+        NoteField::Notebook => format!("notebook : {body}"),
+        NoteField::Content => body,
+    }
+}
+
+pub(crate) fn term_is_prefix(t: &Term, whole_word: bool) -> bool {
+    t.star || (!t.phrase && !whole_word)
 }
 
-/// In-memory search since SQLite FTS would be its own migration. For a
-/// single-user dataset this is plenty.
+/// Verbatim (case-sensitive) check of a term against `target`. FTS5 folds
+/// case, so case-sensitive searches re-check the candidates here.
+fn term_matches_exact(t: &Term, whole_word: bool, target: &str) -> bool {
+    if t.phrase || term_is_prefix(t, whole_word) {
+        target.contains(&t.text)
+    } else {
+        target
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|w| w == t.text)
+    }
+}
+
+/// The checks FTS can't do for a term the index matched: notebook / tag
+/// path queries, and case-sensitive matching.
+fn term_holds(n: &Note, t: &Term, field: NoteField, opts: &NoteMatch) -> bool {
+    let field = t.field.unwrap_or(field);
+    if let Some((query, prefix)) = path_query(t, field, opts.whole_word) {
+        let hit = match field {
+            NoteField::Notebook => path_matches(&n.notebook, query, prefix, opts.ignore_case),
+            _ => n.tags.iter().any(|tag| path_matches(tag, query, prefix, opts.ignore_case)),
+        };
+        if !hit {
+            return false;
+        }
+    }
+    if !opts.ignore_case {
+        return term_matches_exact(t, opts.whole_word, &field_text(n, field));
+    }
+    true
+}
+
+/// The text a search in `field` looks at.
+pub fn field_text(n: &Note, field: NoteField) -> String {
+    match field {
+        NoteField::Title => n.title.clone(),
+        NoteField::Tags => n.tags.join("; "),
+        NoteField::Notebook => n.notebook.clone(),
+        NoteField::Content => format!("{}\n{}\n{}\n{}", n.title, n.tags.join("; "), n.notebook, n.body),
+    }
+}
+
+/// IDs of the live notes an FTS5 expression matches, with their bm25 score
+/// (title hits weigh most).
+fn fts_hits(conn: &Connection, expr: &str) -> Result<HashMap<String, f64>> {
+    let mut stmt = conn.prepare(
+        "SELECT n.id, bm25(notes_fts, 10.0, 5.0, 5.0, 1.0) FROM notes_fts
+         JOIN notes n ON n.rowid = notes_fts.rowid
+         WHERE notes_fts MATCH ?1 AND n.deleted_at IS NULL",
+    )?;
+    let rows = stmt.query_map(params![expr], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    let mut out = HashMap::new();
+    for r in rows {
+        let (id, rank) = r?;
+        out.insert(id, rank);
+    }
+    Ok(out)
+}
+
+/// Notebooks and tags are slash-separated paths (`work/clients/acme`). A
+/// notebook- or tag-scoped bare word is a path query: returns `(path, last
+/// segment is a prefix)`.
+fn path_query(t: &Term, field: NoteField, whole_word: bool) -> Option<(&str, bool)> {
+    if !matches!(field, NoteField::Notebook | NoteField::Tags) || t.phrase {
+        return None;
+    }
+    Some((t.text.trim_matches('/'), term_is_prefix(t, whole_word)))
+}
+
+/// Whether `value` lies at or below `query` in the path hierarchy: `work`
+/// matches `work` and `work/clients/acme`, not `homework` or
+/// `clients/work`. With `prefix`, the query's last segment only has to
+/// start the corresponding segment (`work/cl` matches `work/clients`).
+pub fn path_matches(value: &str, query: &str, prefix: bool, ignore_case: bool) -> bool {
+    let fold = |s: &str| if ignore_case { s.to_lowercase() } else { s.to_string() };
+    let (value, query) = (fold(value), fold(query));
+    let vs: Vec<&str> = value.split('/').collect();
+    let qs: Vec<&str> = query.split('/').collect();
+    if vs.len() < qs.len() {
+        return false;
+    }
+    let last = qs.len() - 1;
+    vs[..last] == qs[..last]
+        && if prefix {
+            vs[last].starts_with(qs[last])
+        } else {
+            vs[last] == qs[last]
+        }
+}
+
+/// Full-text search over the `notes_fts` index with the query language of
+/// [`crate::query`]; terms without a scope of their own search `field`. A
+/// note matches when ALL terms are present (AND semantics), so "linux
+/// python" finds notes containing both words rather than the literal
+/// phrase; use quotes for a phrase. Results are ranked by bm25 when
+/// `order_by` is `Relevance` (title hits weigh most).
 pub fn search_notes(
     conn: &Connection,
     field: NoteField,
     pattern: &str,
     opts: NoteMatch,
 ) -> Result<Vec<Note>> {
-    let all = list_notes(conn, None)?;
-    let norm = |s: String| -> String {
-        if opts.ignore_case {
-            s.to_lowercase()
-        } else {
-            s
-        }
-    };
-    // Split the query into whitespace-delimited tokens; a note matches only
-    // when ALL tokens are present (AND semantics), so "linux python" finds
-    // notes containing both terms rather than the literal phrase.
-    let ptns: Vec<String> = pattern
-        .split_whitespace()
-        .map(|p| norm(p.to_string()))
+    if opts.regex {
+        let re = crate::query::build_regex(pattern, opts.ignore_case, opts.whole_word)?;
+        return search_regex(conn, field, &re, opts);
+    }
+    let query = crate::query::parse(pattern, chrono::Local::now().naive_local())?;
+    let out = search_query(conn, field, &query, opts)?;
+    if out.is_empty() && opts.fuzzy {
+        return search_fuzzy(conn, field, &query, opts);
+    }
+    Ok(out)
+}
+
+/// Regex mode of [`search_notes`]: every live note whose `field` text `re`
+/// matches. The index can't help, so this scans; `Relevance` puts notes
+/// with more matches first.
+pub fn search_regex(conn: &Connection, field: NoteField, re: &regex::Regex, opts: NoteMatch) -> Result<Vec<Note>> {
+    let mut rank: HashMap<String, f64> = HashMap::new();
+    let out: Vec<Note> = list_notes(conn, None)?
+        .into_iter()
+        .filter(|n| opts.in_range(n.updated))
+        .filter(|n| {
+            let hits = re.find_iter(&field_text(n, field)).count();
+            rank.insert(n.id.clone(), -(hits as f64));
+            hits > 0
+        })
         .collect();
-    let matches = |target: &str| -> bool {
-        if ptns.is_empty() {
-            return false;
+    Ok(sorted(out, opts.order_by.unwrap_or(NoteOrder::Updated), &rank))
+}
+
+/// [`search_notes`] for an already parsed query. The text side compiles to
+/// one FTS5 expression (see [`plan`]); the metadata filters and the checks
+/// FTS can't do run over the rows it returns. Queries that nest a filter or
+/// such a check under `OR`/`NOT` beside text go through [`search_scan`].
+pub fn search_query(
+    conn: &Connection,
+    field: NoteField,
+    query: &Query,
+    opts: NoteMatch,
+) -> Result<Vec<Note>> {
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(plan) = plan(query, field, &opts) else {
+        return search_scan(conn, field, query, opts);
+    };
+    let (mut sql, mut args) = match plan.fts {
+        Fts::Match(expr) => (
+            "SELECT n.*, bm25(notes_fts, 10.0, 5.0, 5.0, 1.0) AS rank FROM notes_fts
+             JOIN notes n ON n.rowid = notes_fts.rowid
+             WHERE notes_fts MATCH ?1 AND n.deleted_at IS NULL"
+                .to_string(),
+            vec![expr],
+        ),
+        Fts::Except(expr) => (
+            "SELECT n.*, NULL AS rank FROM notes n
+             WHERE n.deleted_at IS NULL
+               AND n.rowid NOT IN (SELECT rowid FROM notes_fts WHERE notes_fts MATCH ?1)"
+                .to_string(),
+            vec![expr],
+        ),
+        Fts::All => ("SELECT n.*, NULL AS rank FROM notes n WHERE n.deleted_at IS NULL".to_string(), vec![]),
+    };
+    if let Some(f) = opts.from {
+        args.push(ts_to_str(f.trunc_subsecs(0)));
+        sql.push_str(&format!(" AND substr(n.updated, 1, 19) >= ?{}", args.len()));
+    }
+    if let Some(t) = opts.to {
+        args.push(ts_to_str(t.trunc_subsecs(0)));
+        sql.push_str(&format!(" AND substr(n.updated, 1, 19) <= ?{}", args.len()));
+    }
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(args.iter()), |row| {
+        Ok((note_from_row(row)?, row.get::<_, Option<f64>>("rank")?))
+    })?;
+    let mut rank = HashMap::new();
+    let mut out = Vec::new();
+    for r in rows {
+        let (n, score) = r?;
+        let holds = plan.filters.iter().all(|f| f.matches(&n, &|_| false))
+            && plan.checked.iter().all(|t| term_holds(&n, t, field, &opts));
+        if holds {
+            if let Some(score) = score {
+                rank.insert(n.id.clone(), score);
+            }
+            out.push(n);
         }
-        let t = norm(target.to_string());
-        ptns.iter().all(|p| {
-            if opts.whole_word {
-                t.split_whitespace().any(|w| w == p)
-            } else {
-                t.contains(p)
+    }
+    Ok(sorted(out, opts.order_by.unwrap_or(NoteOrder::Updated), &rank))
+}
+
+/// The rows an FTS5 expression selects among the live notes.
+#[derive(Clone, Debug, PartialEq)]
+enum Fts {
+    All,
+    Match(String),
+    /// Every row but those the expression matches. FTS5's `NOT` is binary,
+    /// so a lone negation can only be run as `NOT IN (… MATCH …)`.
+    Except(String),
+}
+
+/// How [`search_query`] runs a query: the top-level conjunction split into
+/// what FTS5 decides and what is checked on the rows it returns.
+#[derive(Debug)]
+struct Plan<'a> {
+    fts: Fts,
+    /// Parts without any term (`has:tags`, `-created:>…`).
+    filters: Vec<&'a Query>,
+    /// Top-level terms FTS matches only loosely: notebook/tag paths, and any
+    /// term of a case-sensitive search. Their FTS form still narrows the
+    /// rows; [`term_holds`] then decides.
+    checked: Vec<&'a Term>,
+}
+
+/// Compile `query` into a [`Plan`], or `None` when a filter or a checked
+/// term sits under `OR`/`NOT` beside text, where no single expression can
+/// stand in for it.
+fn plan<'a>(query: &'a Query, field: NoteField, opts: &NoteMatch) -> Option<Plan<'a>> {
+    let needs_check = |t: &Term| {
+        !opts.ignore_case || path_query(t, t.field.unwrap_or(field), opts.whole_word).is_some()
+    };
+    let mut parts = Vec::new();
+    let mut filters = Vec::new();
+    let mut checked = Vec::new();
+    for q in conjuncts(query) {
+        let terms = q.terms();
+        match q {
+            Query::Term(t) => {
+                parts.push(Fts::Match(fts_term(t, field, opts.whole_word)));
+                if needs_check(t) {
+                    checked.push(t);
+                }
             }
-        })
+            _ if terms.is_empty() => filters.push(q),
+            _ if terms.into_iter().any(needs_check) => return None,
+            _ => parts.push(to_fts(q, field, opts.whole_word)?),
+        }
+    }
+    Some(Plan { fts: fts_and(parts), filters, checked })
+}
+
+/// `q` split at its (nested) top-level `AND`s.
+fn conjuncts(q: &Query) -> Vec<&Query> {
+    match q {
+        Query::And(qs) => qs.iter().flat_map(conjuncts).collect(),
+        q => vec![q],
+    }
+}
+
+/// A filter-free subtree as an FTS5 expression; `None` for the corner
+/// cases with no expression (an empty `OR`, a negated empty group).
+fn to_fts(q: &Query, field: NoteField, whole_word: bool) -> Option<Fts> {
+    let all = |qs: &[Query]| qs.iter().map(|q| to_fts(q, field, whole_word)).collect::<Option<Vec<_>>>();
+    match q {
+        Query::Term(t) => Some(Fts::Match(fts_term(t, field, whole_word))),
+        Query::Filter(_) => None,
+        Query::Not(q) => match to_fts(q, field, whole_word)? {
+            Fts::All => None,
+            Fts::Match(e) => Some(Fts::Except(e)),
+            Fts::Except(e) => Some(Fts::Match(e)),
+        },
+        Query::And(qs) => Some(fts_and(all(qs)?)),
+        Query::Or(qs) => fts_or(all(qs)?),
+    }
+}
+
+/// `A AND B AND NOT C AND NOT D` is `(A AND B) NOT (C OR D)`.
+fn fts_and(parts: Vec<Fts>) -> Fts {
+    let (pos, neg) = split_fts(parts);
+    match (pos.is_empty(), neg.is_empty()) {
+        (true, true) => Fts::All,
+        (false, true) => Fts::Match(fts_group(&pos, "AND")),
+        (true, false) => Fts::Except(fts_group(&neg, "OR")),
+        (false, false) => Fts::Match(format!("{} NOT {}", fts_group(&pos, "AND"), fts_group(&neg, "OR"))),
+    }
+}
+
+/// `A OR B OR NOT C OR NOT D` is everything except `(C AND D) NOT (A OR B)`.
+fn fts_or(parts: Vec<Fts>) -> Option<Fts> {
+    if parts.contains(&Fts::All) {
+        return Some(Fts::All);
+    }
+    let (pos, neg) = split_fts(parts);
+    Some(match (pos.is_empty(), neg.is_empty()) {
+        (true, true) => return None,
+        (false, true) => Fts::Match(fts_group(&pos, "OR")),
+        (true, false) => Fts::Except(fts_group(&neg, "AND")),
+        (false, false) => Fts::Except(format!("{} NOT {}", fts_group(&neg, "AND"), fts_group(&pos, "OR"))),
+    })
+}
+
+/// Matched and excepted expressions; `All` parts are dropped.
+fn split_fts(parts: Vec<Fts>) -> (Vec<String>, Vec<String>) {
+    let (mut pos, mut neg) = (Vec::new(), Vec::new());
+    for p in parts {
+        match p {
+            Fts::All => {}
+            Fts::Match(e) => pos.push(e),
+            Fts::Except(e) => neg.push(e),
+        }
+    }
+    (pos, neg)
+}
+
+fn fts_group(exprs: &[String], op: &str) -> String {
+    let inner: Vec<String> = exprs.iter().map(|e| format!("({e})")).collect();
+    format!("({})", inner.join(&format!(" {op} ")))
+}
+
+/// [`search_query`] for the queries [`plan`] can't compile: each term is
+/// looked up in the index once, then the whole query is evaluated over the
+/// live notes.
+fn search_scan(conn: &Connection, field: NoteField, query: &Query, opts: NoteMatch) -> Result<Vec<Note>> {
+    let mut hits: HashMap<&Term, HashMap<String, f64>> = HashMap::new();
+    for t in query.terms() {
+        if !hits.contains_key(t) {
+            hits.insert(t, fts_hits(conn, &fts_term(t, field, opts.whole_word))?);
+        }
+    }
+    let order = opts.order_by.unwrap_or(NoteOrder::Updated);
+    let positive = query.positive_terms();
+    let rank = if order == NoteOrder::Relevance && !positive.is_empty() {
+        let expr = positive
+            .iter()
+            .map(|t| format!("({})", fts_term(t, field, opts.whole_word)))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        fts_hits(conn, &expr)?
+    } else {
+        HashMap::new()
     };
-    let mut out = Vec::new();
-    for n in all {
-        let hit = match field {
-            NoteField::Title => matches(&n.title),
-            NoteField::Tags => matches(&n.tags.join("; ")),
-            NoteField::Notebook => matches(&n.notebook),
-            NoteField::Content => {
-                let combined = format!("{}\n{}\n{}\n{}", n.title, n.tags.join("; "), n.notebook, n.body);
-                matches(&combined)
+
+    let out: Vec<Note> = list_notes(conn, None)?
+        .into_iter()
+        .filter(|n| opts.in_range(n.updated))
+        .filter(|n| {
+            query.matches(n, &|t| hits[t].contains_key(&n.id) && term_holds(n, t, field, &opts))
+        })
+        .collect();
+    Ok(sorted(out, order, &rank))
+}
+
+/// Typo-tolerant fallback of [`search_query`]: a term matches when each of
+/// its words is within a few edits of a title or tag word (a notebook word
+/// for notebook-scoped terms), ignoring case. The boolean structure and
+/// the filters apply as usual; `Relevance` puts fewer typos first.
+pub fn search_fuzzy(conn: &Connection, field: NoteField, query: &Query, opts: NoteMatch) -> Result<Vec<Note>> {
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut rank: HashMap<String, f64> = HashMap::new();
+    let out: Vec<Note> = list_notes(conn, None)?
+        .into_iter()
+        .filter(|n| opts.in_range(n.updated))
+        .filter(|n| query.matches(n, &|t| fuzzy_typos(n, t, field, opts.whole_word).is_some()))
+        .inspect(|n| {
+            let typos: usize =
+                query.positive_terms().iter().filter_map(|t| fuzzy_typos(n, t, field, opts.whole_word)).sum();
+            rank.insert(n.id.clone(), typos as f64);
+        })
+        .collect();
+    Ok(sorted(out, opts.order_by.unwrap_or(NoteOrder::Updated), &rank))
+}
+
+/// Typos it takes for `t` to match `n` fuzzily, if it can.
+fn fuzzy_typos(n: &Note, t: &Term, field: NoteField, whole_word: bool) -> Option<usize> {
+    let scope = t.field.unwrap_or(field);
+    let mut vocab: Vec<String> = Vec::new();
+    if matches!(scope, NoteField::Content | NoteField::Title) {
+        vocab.extend(fuzzy::words(&n.title));
+    }
+    if matches!(scope, NoteField::Content | NoteField::Tags) {
+        vocab.extend(n.tags.iter().flat_map(|tag| fuzzy::words(tag)));
+    }
+    if scope == NoteField::Notebook {
+        vocab.extend(fuzzy::words(&n.notebook));
+    }
+    let prefix = term_is_prefix(t, whole_word);
+    fuzzy::words(&t.text)
+        .map(|w| {
+            vocab
+                .iter()
+                .filter_map(|v| if prefix && v.starts_with(&w) { Some(0) } else { fuzzy::typos(&w, v, prefix) })
+                .min()
+        })
+        .sum()
+}
+
+/// `query` with each word of its positive terms that no live note contains
+/// replaced by the closest word that some note does; `None` when there is
+/// nothing to correct. Words are looked up in `notes_vocab`, the full-text
+/// index's own vocabulary, so a word counts as known exactly when searching
+/// for it finds something.
+pub fn did_you_mean(conn: &Connection, query: &Query) -> Result<Option<Query>> {
+    let mut vocab: Option<Vec<(String, usize)>> = None;
+    let mut fixes: HashMap<String, String> = HashMap::new();
+    for t in query.positive_terms() {
+        for w in fuzzy::words(&t.text) {
+            if fixes.contains_key(&w) || vocab_has(conn, &w, !t.phrase)? {
+                continue;
             }
-        };
-        if hit {
-            let in_range = opts
-                .from
-                .map_or(true, |f| n.updated >= f)
-                && opts.to.map_or(true, |t| n.updated <= t);
-            if in_range {
-                out.push(n);
+            if vocab.is_none() {
+                let mut stmt = conn.prepare("SELECT term, doc FROM notes_vocab")?;
+                let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)? as usize)))?;
+                vocab = Some(rows.collect::<rusqlite::Result<_>>()?);
+            }
+            let known = vocab.iter().flatten().map(|(k, n)| (k.as_str(), *n));
+            if let Some(c) = fuzzy::closest(&w, known) {
+                fixes.insert(w, c.to_string());
             }
         }
     }
-    match opts.order_by.unwrap_or(NoteOrder::Updated) {
-        NoteOrder::Updated => out.sort_by(|a, b| b.updated.cmp(&a.updated).then(a.id.cmp(&b.id))),
-        NoteOrder::Created => out.sort_by(|a, b| b.created.cmp(&a.created).then(a.id.cmp(&b.id))),
+    if fixes.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(query.map_terms(&mut |t, negated| {
+        let mut t = t.clone();
+        if !negated {
+            t.text = respell(&t.text, &fixes);
+        }
+        t
+    })))
+}
+
+/// Whether the index has the word `w`, or with `prefix` any word starting
+/// with it.
+fn vocab_has(conn: &Connection, w: &str, prefix: bool) -> Result<bool> {
+    let sql = if prefix {
+        "SELECT EXISTS (SELECT 1 FROM notes_vocab WHERE term >= ?1 AND substr(term, 1, length(?1)) = ?1)"
+    } else {
+        "SELECT EXISTS (SELECT 1 FROM notes_vocab WHERE term = ?1)"
+    };
+    Ok(conn.query_row(sql, params![w], |r| r.get(0))?)
+}
+
+/// `text` with the words in `fixes` (keyed lowercase, as
+/// [`fuzzy::words`] gives them) swapped for their fix; the rest as typed.
+fn respell(text: &str, fixes: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word = String::new();
+    let flush = |word: &mut String, out: &mut String| {
+        match fixes.get(&word.to_lowercase()) {
+            Some(fix) => out.push_str(fix),
+            None => out.push_str(word),
+        }
+        word.clear();
+    };
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+/// Sort search results; `rank` (lower is better) drives `Relevance`, with
+/// unranked notes last.
+fn sorted(mut out: Vec<Note>, order: NoteOrder, rank: &HashMap<String, f64>) -> Vec<Note> {
+    let newest = |a: &Note, b: &Note| b.updated.cmp(&a.updated).then_with(|| a.id.cmp(&b.id));
+    match order {
+        NoteOrder::Updated => out.sort_by(newest),
+        NoteOrder::Created => out.sort_by(|a, b| b.created.cmp(&a.created).then_with(|| a.id.cmp(&b.id))),
         NoteOrder::Title => out.sort_by(|a, b| {
-            a.title
-                .to_lowercase()
-                .cmp(&b.title.to_lowercase())
-                .then_with(|| a.id.cmp(&b.id))
+            fold_title(&a.title).cmp(&fold_title(&b.title)).then_with(|| a.id.cmp(&b.id))
+        }),
+        NoteOrder::Relevance => out.sort_by(|a, b| {
+            let r = |n: &Note| rank.get(&n.id).copied().unwrap_or(f64::INFINITY);
+            r(a).total_cmp(&r(b)).then_with(|| newest(a, b))
         }),
     }
-    Ok(out)
+    out
 }
 
 /// Parse a user-supplied date or datetime for range filtering. A bare date
@@ -342,11 +1253,18 @@ pub fn parse_when(s: &str, end_of_day: bool) -> Option<NaiveDateTime> {
 
 pub fn upsert_pulse(conn: &Connection, pulse: &Pulse) -> Result<()> {
     conn.execute(
-        "INSERT INTO pulses (id, topic, interval, created)
-         VALUES (?1, ?2, ?3, ?4)
+        "INSERT INTO pulses (id, topic, interval, created, deleted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
          ON CONFLICT(id) DO UPDATE SET
-            topic=excluded.topic, interval=excluded.interval, created=excluded.created",
-        params![pulse.id, pulse.topic, interval_to_str(pulse.interval), ts_to_str(pulse.created)],
+            topic=excluded.topic, interval=excluded.interval, created=excluded.created,
+            deleted_at=excluded.deleted_at",
+        params![
+            pulse.id,
+            pulse.topic,
+            interval_to_str(pulse.interval),
+            ts_to_str(pulse.created),
+            pulse.deleted_at.map(ts_to_str),
+        ],
     )?;
     conn.execute(
         "DELETE FROM pulse_slots WHERE pulse_id = ?1",
@@ -362,7 +1280,17 @@ pub fn upsert_pulse(conn: &Connection, pulse: &Pulse) -> Result<()> {
     Ok(())
 }
 
+/// A live pulse by id; trashed pulses read as absent.
 pub fn get_pulse(conn: &Connection, id: &str) -> Result<Option<Pulse>> {
+    Ok(read_pulse(conn, id)?.filter(|p| p.deleted_at.is_none()))
+}
+
+/// A pulse by id, but only if it is in the trash.
+pub fn get_trashed_pulse(conn: &Connection, id: &str) -> Result<Option<Pulse>> {
+    Ok(read_pulse(conn, id)?.filter(|p| p.deleted_at.is_some()))
+}
+
+fn read_pulse(conn: &Connection, id: &str) -> Result<Option<Pulse>> {
     let pulse = conn
         .query_row("SELECT * FROM pulses WHERE id = ?1", params![id], |row| {
             let interval_str: String = row.get("interval")?;
@@ -377,12 +1305,14 @@ pub fn get_pulse(conn: &Connection, id: &str) -> Result<Option<Pulse>> {
                 }
             };
             let created_str: String = row.get("created")?;
+            let deleted_at: Option<String> = row.get("deleted_at")?;
             Ok(Pulse {
                 id: row.get("id")?,
                 topic: row.get("topic")?,
                 interval,
                 created: ts_from_str(&created_str).unwrap_or_else(|_| chrono::Local::now().naive_local()),
                 slots: Vec::new(),
+                deleted_at: deleted_at.and_then(|s| ts_from_str(&s).ok()),
             })
         })
         .optional()?;
@@ -401,11 +1331,20 @@ pub fn get_pulse(conn: &Connection, id: &str) -> Result<Option<Pulse>> {
 }
 
 pub fn list_pulses(conn: &Connection) -> Result<Vec<Pulse>> {
-    let mut stmt = conn.prepare("SELECT id FROM pulses ORDER BY created")?;
+    pulses_where(conn, "deleted_at IS NULL ORDER BY created")
+}
+
+/// Pulses in the trash, most recently trashed first.
+pub fn list_trashed_pulses(conn: &Connection) -> Result<Vec<Pulse>> {
+    pulses_where(conn, "deleted_at IS NOT NULL ORDER BY deleted_at DESC")
+}
+
+fn pulses_where(conn: &Connection, clause: &str) -> Result<Vec<Pulse>> {
+    let mut stmt = conn.prepare(&format!("SELECT id FROM pulses WHERE {clause}"))?;
     let ids: Vec<String> = stmt.query_map([], |r| r.get::<_, String>(0))?.filter_map(Result::ok).collect();
     let mut out = Vec::new();
     for id in ids {
-        if let Some(p) = get_pulse(conn, &id)? {
+        if let Some(p) = read_pulse(conn, &id)? {
             out.push(p);
         }
     }
@@ -421,10 +1360,11 @@ pub fn delete_pulse(conn: &Connection, id: &str) -> Result<bool> {
 
 pub fn upsert_metric(conn: &Connection, metric: &Metric) -> Result<()> {
     conn.execute(
-        "INSERT INTO metrics (id, topic, created)
-         VALUES (?1, ?2, ?3)
-         ON CONFLICT(id) DO UPDATE SET topic=excluded.topic, created=excluded.created",
-        params![metric.id, metric.topic, ts_to_str(metric.created)],
+        "INSERT INTO metrics (id, topic, created, deleted_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(id) DO UPDATE SET
+            topic=excluded.topic, created=excluded.created, deleted_at=excluded.deleted_at",
+        params![metric.id, metric.topic, ts_to_str(metric.created), metric.deleted_at.map(ts_to_str)],
     )?;
     conn.execute(
         "DELETE FROM metric_points WHERE metric_id = ?1",
@@ -440,15 +1380,27 @@ pub fn upsert_metric(conn: &Connection, metric: &Metric) -> Result<()> {
     Ok(())
 }
 
+/// A live metric by id; trashed metrics read as absent.
 pub fn get_metric(conn: &Connection, id: &str) -> Result<Option<Metric>> {
+    Ok(read_metric(conn, id)?.filter(|m| m.deleted_at.is_none()))
+}
+
+/// A metric by id, but only if it is in the trash.
+pub fn get_trashed_metric(conn: &Connection, id: &str) -> Result<Option<Metric>> {
+    Ok(read_metric(conn, id)?.filter(|m| m.deleted_at.is_some()))
+}
+
+fn read_metric(conn: &Connection, id: &str) -> Result<Option<Metric>> {
     let metric = conn
         .query_row("SELECT * FROM metrics WHERE id = ?1", params![id], |row| {
             let created_str: String = row.get("created")?;
+            let deleted_at: Option<String> = row.get("deleted_at")?;
             Ok(Metric {
                 id: row.get("id")?,
                 topic: row.get("topic")?,
                 created: ts_from_str(&created_str).unwrap_or_else(|_| chrono::Local::now().naive_local()),
                 points: Vec::new(),
+                deleted_at: deleted_at.and_then(|s| ts_from_str(&s).ok()),
             })
         })
         .optional()?;
@@ -468,11 +1420,20 @@ pub fn get_metric(conn: &Connection, id: &str) -> Result<Option<Metric>> {
 }
 
 pub fn list_metrics(conn: &Connection) -> Result<Vec<Metric>> {
-    let mut stmt = conn.prepare("SELECT id FROM metrics ORDER BY created")?;
+    metrics_where(conn, "deleted_at IS NULL ORDER BY created")
+}
+
+/// Metrics in the trash, most recently trashed first.
+pub fn list_trashed_metrics(conn: &Connection) -> Result<Vec<Metric>> {
+    metrics_where(conn, "deleted_at IS NOT NULL ORDER BY deleted_at DESC")
+}
+
+fn metrics_where(conn: &Connection, clause: &str) -> Result<Vec<Metric>> {
+    let mut stmt = conn.prepare(&format!("SELECT id FROM metrics WHERE {clause}"))?;
     let ids: Vec<String> = stmt.query_map([], |r| r.get::<_, String>(0))?.filter_map(Result::ok).collect();
     let mut out = Vec::new();
     for id in ids {
-        if let Some(m) = get_metric(conn, &id)? {
+        if let Some(m) = read_metric(conn, &id)? {
             out.push(m);
         }
     }
@@ -740,6 +1701,352 @@ mod tests {
         assert!(search_notes(&conn, NoteField::Content, "linux nomatch", NoteMatch::default()).unwrap().is_empty());
     }
 
+    #[test]
+    fn search_notes_prefix_and_phrase() {
+        let conn = conn();
+        let mk = |id: &str, title: &str, body: &str| {
+            Note::new(id.into(), title.into(), vec![], "nb".into(), now(), now(), body.into())
+        };
+        upsert_note(&conn, &mk("n1", "PowerShell profile", "set the prompt color")).unwrap();
+        upsert_note(&conn, &mk("n2", "prompt engineering", "color the set")).unwrap();
+        let ids = |v: Vec<Note>| {
+            let mut ids: Vec<String> = v.into_iter().map(|n| n.id).collect();
+            ids.sort();
+            ids
+        };
+
+        // bare words are prefixes unless whole-word is asked for
+        assert_eq!(ids(search_notes(&conn, NoteField::Title, "power", NoteMatch::default()).unwrap()), vec!["n1"]);
+        let whole = NoteMatch { whole_word: true, ..Default::default() };
+        assert!(search_notes(&conn, NoteField::Title, "power", whole).unwrap().is_empty());
+        assert_eq!(ids(search_notes(&conn, NoteField::Title, "power*", whole).unwrap()), vec!["n1"]);
+
+        // a quoted phrase needs the words adjacent and in order
+        assert_eq!(ids(search_notes(&conn, NoteField::Content, "set prompt color", NoteMatch::default()).unwrap()), vec!["n1", "n2"]);
+        assert_eq!(ids(search_notes(&conn, NoteField::Content, "\"the prompt color\"", NoteMatch::default()).unwrap()), vec!["n1"]);
+
+        // FTS syntax in user input is treated as text
+        assert!(search_notes(&conn, NoteField::Content, "NEAR( \" -", NoteMatch::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_notes_ranks_by_relevance_and_respects_case() {
+        let conn = conn();
+        let mk = |id: &str, title: &str, body: &str| {
+            Note::new(id.into(), title.into(), vec![], "nb".into(), now(), now(), body.into())
+        };
+        upsert_note(&conn, &mk("n1", "misc", "somewhere in the body: Rust")).unwrap();
+        upsert_note(&conn, &mk("n2", "Rust ownership", "borrowing")).unwrap();
+        let rel = NoteMatch { order_by: Some(NoteOrder::Relevance), ..Default::default() };
+        let hits = search_notes(&conn, NoteField::Content, "rust", rel).unwrap();
+        assert_eq!(hits.iter().map(|n| n.id.as_str()).collect::<Vec<_>>(), vec!["n2", "n1"]);
+
+        let exact = NoteMatch { ignore_case: false, ..Default::default() };
+        assert_eq!(search_notes(&conn, NoteField::Content, "Rust", exact).unwrap().len(), 2);
+        assert!(search_notes(&conn, NoteField::Content, "rust", exact).unwrap().is_empty());
+    }
+
+    #[test]
+    fn notebook_and_tag_scopes_match_whole_subtrees() {
+        let conn = conn();
+        let mk = |id: &str, nb: &str, tag: &str| {
+            Note::new(id.into(), id.into(), vec![tag.into()], nb.into(), now(), now(), "".into())
+        };
+        upsert_note(&conn, &mk("n1", "work", "lang/rust")).unwrap();
+        upsert_note(&conn, &mk("n2", "work/clients/acme", "lang")).unwrap();
+        upsert_note(&conn, &mk("n3", "homework", "rust")).unwrap();
+        upsert_note(&conn, &mk("n4", "clients/work", "language")).unwrap();
+        let ids = |field, q: &str, opts: NoteMatch| {
+            let mut ids: Vec<String> = search_notes(&conn, field, q, opts).unwrap().into_iter().map(|n| n.id).collect();
+            ids.sort();
+            ids
+        };
+        let whole = NoteMatch { whole_word: true, ..Default::default() };
+
+        assert_eq!(ids(NoteField::Notebook, "work", whole), ["n1", "n2"]);
+        assert_eq!(ids(NoteField::Notebook, "Work/Clients", NoteMatch::default()), ["n2"]);
+        assert_eq!(ids(NoteField::Notebook, "work/cl", NoteMatch::default()), ["n2"]);
+        assert_eq!(ids(NoteField::Tags, "lang", whole), ["n1", "n2"]);
+        assert_eq!(ids(NoteField::Tags, "lang", NoteMatch::default()), ["n1", "n2", "n4"]);
+
+        assert!(path_matches("a/b/c", "a/b", false, false));
+        assert!(!path_matches("a/bc", "a/b", false, false));
+        assert!(!path_matches("A/b", "a", false, false));
+    }
+
+    #[test]
+    fn search_query_language() {
+        let conn = conn();
+        let at = |s: &str| -> NaiveDateTime { s.parse().unwrap() };
+        let mk = |id: &str, title: &str, nb: &str, created: &str, body: &str| {
+            Note::new(id.into(), title.into(), vec![], nb.into(), at(created), at(created), body.into())
+        };
+        upsert_note(&conn, &mk("n1", "rust notes", "work", "2026-01-05T10:00:00", "see ![x](resources/x.png)")).unwrap();
+        upsert_note(&conn, &mk("n2", "go notes", "home", "2025-12-20T10:00:00", "draft")).unwrap();
+        let mut n3 = mk("n3", "python", "work", "2026-02-01T10:00:00", "rust bindings");
+        n3.related = vec!["n1".into()];
+        upsert_note(&conn, &n3).unwrap();
+        let ids = |q: &str| {
+            let mut ids: Vec<String> =
+                search_notes(&conn, NoteField::Content, q, NoteMatch::default()).unwrap().into_iter().map(|n| n.id).collect();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(ids("rust OR go"), ["n1", "n2", "n3"]);
+        assert_eq!(ids("notes -draft"), ["n1"]);
+        assert_eq!(ids("NOT rust"), ["n2"]);
+        assert_eq!(ids("t:(rust OR python)"), ["n1", "n3"]);
+        assert_eq!(ids("(rust OR go) n:work"), ["n1", "n3"]);
+        assert_eq!(ids("created:>2026-01-05"), ["n3"]);
+        assert_eq!(ids("created:>=2026-01-05 rust"), ["n1", "n3"]);
+        assert_eq!(ids("updated:2025-12-20"), ["n2"]);
+        assert_eq!(ids("has:attachment"), ["n1"]);
+        assert_eq!(ids("related:n1 OR has:attachment"), ["n1", "n3"]);
+        assert!(search_notes(&conn, NoteField::Content, "has:nothing", NoteMatch::default()).is_err());
+
+        // Case-sensitive matching applies per term, negated ones included.
+        let exact = NoteMatch { ignore_case: false, ..Default::default() };
+        let hits = search_notes(&conn, NoteField::Content, "notes -Draft", exact).unwrap();
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[test]
+    fn search_query_compiles_text_to_one_fts_expression() {
+        let conn = conn();
+        let mk = |id: &str, title: &str, tags: &[&str], nb: &str, body: &str| {
+            let tags = tags.iter().map(|t| t.to_string()).collect();
+            Note::new(id.into(), title.into(), tags, nb.into(), now(), now(), body.into())
+        };
+        upsert_note(&conn, &mk("n1", "Rust notes", &["lang/rust"], "work", "see ![x](resources/x.png)")).unwrap();
+        upsert_note(&conn, &mk("n2", "go notes", &[], "home", "draft about Rust")).unwrap();
+        upsert_note(&conn, &mk("n3", "python", &["lang"], "work/py", "rust bindings, draft")).unwrap();
+        upsert_note(&conn, &mk("n4", "misc", &[], "homework", "nothing")).unwrap();
+        let now = chrono::Local::now().naive_local();
+        let fts = |q: &str, opts: NoteMatch| {
+            plan(&crate::query::parse(q, now).unwrap(), NoteField::Content, &opts).map(|p| p.fts)
+        };
+        let loose = NoteMatch::default();
+        assert!(matches!(fts("notes -draft", loose), Some(Fts::Match(e)) if e.contains(" NOT ")));
+        assert!(matches!(fts("NOT rust", loose), Some(Fts::Except(_))));
+        assert!(matches!(fts("rust OR -go", loose), Some(Fts::Except(_))));
+        assert_eq!(fts("related:n1 OR has:attachment", loose), Some(Fts::All));
+        assert_eq!(fts("rust OR has:tags", loose), None);
+        assert_eq!(fts("go OR -n:work", loose), None);
+
+        // Whatever the plan, the answer is the one a term-by-term scan gives.
+        let exact = NoteMatch { ignore_case: false, ..loose };
+        for q in [
+            "rust", "notes -draft", "NOT rust", "rust OR -go", "(rust OR go) -(draft notes)",
+            "t:(rust OR python) -n:home", "n:work rust", "g:lang -g:lang/rust", "rust has:attachment",
+            "-draft -has:tags", "rust OR has:tags", "go OR -n:work", "Rust -draft", "-Rust",
+        ] {
+            for opts in [loose, exact] {
+                let query = crate::query::parse(q, now).unwrap();
+                let ids = |notes: Vec<Note>| {
+                    let mut ids: Vec<String> = notes.into_iter().map(|n| n.id).collect();
+                    ids.sort();
+                    ids
+                };
+                assert_eq!(
+                    ids(search_query(&conn, NoteField::Content, &query, opts).unwrap()),
+                    ids(search_scan(&conn, NoteField::Content, &query, opts).unwrap()),
+                    "{q} (ignore_case: {})",
+                    opts.ignore_case
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn regex_mode_matches_field_text() {
+        let conn = conn();
+        let mk = |id: &str, title: &str, body: &str| {
+            Note::new(id.into(), title.into(), vec![], "nb".into(), now(), now(), body.into())
+        };
+        upsert_note(&conn, &mk("n1", "error 404", "see E-1234 and E-99")).unwrap();
+        upsert_note(&conn, &mk("n2", "Errors", "E-7")).unwrap();
+        let ids = |field, q: &str, opts: NoteMatch| {
+            search_notes(&conn, field, q, opts).unwrap().into_iter().map(|n| n.id).collect::<Vec<_>>()
+        };
+        let re = NoteMatch { regex: true, order_by: Some(NoteOrder::Relevance), ..Default::default() };
+
+        assert_eq!(ids(NoteField::Content, r"E-\d+", re), ["n1", "n2"]);
+        assert_eq!(ids(NoteField::Title, r"^error \d{3}$", re), ["n1"]);
+        assert_eq!(ids(NoteField::Title, "error", NoteMatch { whole_word: true, ..re }), ["n1"]);
+        assert_eq!(ids(NoteField::Title, "^E", NoteMatch { ignore_case: false, ..re }), ["n2"]);
+        assert!(search_notes(&conn, NoteField::Content, "(", re).is_err());
+    }
+
+    #[test]
+    fn cursor_pages_cover_every_note_once() {
+        let conn = conn();
+        let at = |h: u32| NaiveDate::from_ymd_opt(2026, 8, 6).unwrap().and_hms_opt(h, 0, 0).unwrap();
+        // n2 and n3 share `updated`; titles collide but for case.
+        for (id, title, h) in [("n1", "beta", 1), ("n2", "Alpha", 2), ("n3", "alpha", 2), ("n4", "a, b", 3)] {
+            upsert_note(&conn, &Note::new(id.into(), title.into(), vec![], "nb".into(), at(5 - h), at(h), "".into())).unwrap();
+        }
+        let mut trashed = Note::new("n5".into(), "zed".into(), vec![], "nb".into(), at(9), at(9), "".into());
+        trashed.deleted_at = Some(at(9));
+        upsert_note(&conn, &trashed).unwrap();
+        assert_eq!(count_notes(&conn).unwrap(), 4);
+
+        let walk = |order: NoteOrder| {
+            let mut seen = Vec::new();
+            let mut after: Option<Cursor> = None;
+            loop {
+                let page = page_notes(&conn, order, after.as_ref(), Some(1)).unwrap();
+                let Some(last) = page.last() else { break };
+                // Round-trips through its string form, as in a URL.
+                after = Some(Cursor::of(last, order).to_string().parse().unwrap());
+                seen.extend(page.into_iter().map(|n| n.id));
+            }
+            seen
+        };
+        assert_eq!(walk(NoteOrder::Updated), ["n4", "n2", "n3", "n1"]);
+        assert_eq!(walk(NoteOrder::Created), ["n1", "n2", "n3", "n4"]);
+        assert_eq!(walk(NoteOrder::Title), ["n4", "n2", "n3", "n1"]);
+        assert_eq!(page_notes(&conn, NoteOrder::Updated, None, None).unwrap().len(), 4);
+
+        let c: Cursor = "a, b,n4".parse().unwrap();
+        assert_eq!((c.key.as_str(), c.id.as_str()), ("a, b", "n4"));
+        assert!("nocomma".parse::<Cursor>().is_err());
+        let listed = page_notes(&conn, NoteOrder::Updated, None, None).unwrap();
+        assert_eq!(Cursor::of(&listed[0], NoteOrder::Updated).skip(listed.clone(), NoteOrder::Updated).len(), 3);
+        // A cursor whose note is gone still seeks by its key.
+        let gone = Cursor { key: ts_to_str(at(2)), id: "n0".into() };
+        let ids = |notes: Vec<Note>| notes.into_iter().map(|n| n.id).collect::<Vec<_>>();
+        assert_eq!(ids(gone.skip(listed.clone(), NoteOrder::Updated)), ["n2", "n3", "n1"]);
+        assert_eq!(ids(gone.skip(listed, NoteOrder::Relevance)).len(), 4);
+    }
+
+    #[test]
+    fn title_order_folds_like_title_lookups() {
+        let conn = conn();
+        // ASCII-only folding would put `Ωmega` (U+03A9) before `αlpha` (U+03B1).
+        for (id, title) in [("n1", "Ωmega"), ("n2", "αlpha"), ("n3", "Beta")] {
+            upsert_note(&conn, &Note::new(id.into(), title.into(), vec![], "nb".into(), now(), now(), "".into())).unwrap();
+        }
+        let ids = |notes: Vec<Note>| notes.into_iter().map(|n| n.id).collect::<Vec<_>>();
+        let paged = page_notes(&conn, NoteOrder::Title, None, None).unwrap();
+        assert_eq!(ids(paged.clone()), ["n3", "n2", "n1"]);
+        let sorted = sorted(paged.clone(), NoteOrder::Title, &HashMap::new());
+        assert_eq!(ids(sorted.clone()), ["n3", "n2", "n1"]);
+
+        let after = Cursor { key: "ΑLPHA".into(), id: "n0".into() };
+        assert_eq!(ids(page_notes(&conn, NoteOrder::Title, Some(&after), None).unwrap()), ["n2", "n1"]);
+        assert_eq!(ids(after.skip(sorted, NoteOrder::Title)), ["n2", "n1"]);
+    }
+
+    #[test]
+    fn fuzzy_fallback_and_did_you_mean() {
+        let conn = conn();
+        let mk = |id: &str, title: &str, tags: &[&str], body: &str| {
+            let tags = tags.iter().map(|t| t.to_string()).collect();
+            Note::new(id.into(), title.into(), tags, "nb".into(), now(), now(), body.into())
+        };
+        upsert_note(&conn, &mk("n1", "PowerShell prompt", &["windows"], "colour settings")).unwrap();
+        upsert_note(&conn, &mk("n2", "Rust ownership", &["rust"], "borrowing")).unwrap();
+        let ids = |q: &str, opts: NoteMatch| {
+            search_notes(&conn, NoteField::Content, q, opts).unwrap().into_iter().map(|n| n.id).collect::<Vec<_>>()
+        };
+        let fuzzy = NoteMatch { fuzzy: true, ..Default::default() };
+
+        assert!(ids("powrshell", NoteMatch::default()).is_empty());
+        assert_eq!(ids("powrshell", fuzzy), ["n1"]);
+        assert_eq!(ids("pwoer", fuzzy), ["n1"]);
+        assert_eq!(ids("windwos OR rsut", fuzzy), ["n1", "n2"]);
+        assert_eq!(ids("windwos -prompt", fuzzy), Vec::<String>::new());
+        // Body words aren't matched fuzzily; exact hits don't fall back.
+        assert!(ids("borowing", fuzzy).is_empty());
+        assert_eq!(ids("rust", fuzzy), ["n2"]);
+
+        let now = chrono::Local::now().naive_local();
+        let mean = |q: &str| {
+            did_you_mean(&conn, &crate::query::parse(q, now).unwrap()).unwrap().map(|q| q.to_string())
+        };
+        assert_eq!(mean("Powrshell t:promt -drafts").as_deref(), Some("powershell t:prompt -drafts"));
+        assert_eq!(mean("\"rust ownrship\" OR coluor*").as_deref(), Some("\"rust ownership\" OR colour*"));
+        // Only what a search would find counts: trashed notes' words don't.
+        let mut gone = mk("n3", "Kubernetes", &[], "");
+        gone.deleted_at = Some(now);
+        upsert_note(&conn, &gone).unwrap();
+        assert_eq!(mean("kubernets"), None);
+        assert_eq!(mean("borowing").as_deref(), Some("borrowing"));
+        assert_eq!(mean("power rust"), None);
+        assert_eq!(mean("zzzzzz"), None);
+
+        // Words too short for typos still match exactly, whole-word too.
+        upsert_note(&conn, &mk("n4", "Go modules", &[], "")).unwrap();
+        assert_eq!(ids("go modlues", NoteMatch { whole_word: true, ..fuzzy }), ["n4"]);
+    }
+
+    #[test]
+    fn fts_index_follows_writes() {
+        let conn = conn();
+        let mut n = Note::new("n1".into(), "alpha".into(), vec![], "nb".into(), now(), now(), "".into());
+        upsert_note(&conn, &n).unwrap();
+        n.title = "beta".into();
+        upsert_note(&conn, &n).unwrap();
+        assert!(search_notes(&conn, NoteField::Title, "alpha", NoteMatch::default()).unwrap().is_empty());
+        assert_eq!(search_notes(&conn, NoteField::Title, "beta", NoteMatch::default()).unwrap().len(), 1);
+        assert!(delete_note(&conn, "n1").unwrap());
+        assert!(search_notes(&conn, NoteField::Title, "beta", NoteMatch::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn trashed_items_are_hidden_from_live_reads() {
+        let conn = conn();
+        let mut n = Note::new("n1".into(), "alpha".into(), vec![], "nb".into(), now(), now(), "".into());
+        n.deleted_at = Some(now());
+        upsert_note(&conn, &n).unwrap();
+        assert!(get_note(&conn, "n1").unwrap().is_none());
+        assert!(list_notes(&conn, None).unwrap().is_empty());
+        assert!(search_notes(&conn, NoteField::Title, "alpha", NoteMatch::default()).unwrap().is_empty());
+        assert_eq!(get_trashed_note(&conn, "n1").unwrap().unwrap().deleted_at, Some(now()));
+        assert_eq!(list_trashed_notes(&conn).unwrap().len(), 1);
+
+        let mut p = Pulse::new("p1".into(), "jog".into(), Interval::Daily, now());
+        p.deleted_at = Some(now());
+        upsert_pulse(&conn, &p).unwrap();
+        assert!(get_pulse(&conn, "p1").unwrap().is_none());
+        assert!(list_pulses(&conn).unwrap().is_empty());
+        assert_eq!(list_trashed_pulses(&conn).unwrap()[0].id, "p1");
+
+        let mut m = Metric::new("m1".into(), "weight".into(), now());
+        m.deleted_at = Some(now());
+        upsert_metric(&conn, &m).unwrap();
+        assert!(get_metric(&conn, "m1").unwrap().is_none());
+        assert!(get_trashed_metric(&conn, "m1").unwrap().is_some());
+
+        // Restoring is an upsert with the mark cleared.
+        n.deleted_at = None;
+        upsert_note(&conn, &n).unwrap();
+        assert!(get_note(&conn, "n1").unwrap().is_some());
+        assert!(get_trashed_note(&conn, "n1").unwrap().is_none());
+        assert_eq!(search_notes(&conn, NoteField::Title, "alpha", NoteMatch::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn title_lookups_fold_non_ascii_case() {
+        let conn = conn();
+        let target = Note::new("n1".into(), "Ärger im Büro".into(), vec![], "nb".into(), now(), now(), "".into());
+        let linker = Note::new("n2".into(), "l".into(), vec![], "nb".into(), now(), now(), "see [[ärger IM büro]]".into());
+        let other = Note::new("n3".into(), "o".into(), vec![], "nb".into(), now(), now(), "[[elsewhere]]".into());
+        let spaced =
+            Note::new("n4".into(), "s".into(), vec![], "nb".into(), now(), now(), "[[ Ärger im Büro ]]".into());
+        let labelled =
+            Note::new("n5".into(), "b".into(), vec![], "nb".into(), now(), now(), "[[  ärger im büro|the row]]".into());
+        for n in [&target, &linker, &other, &spaced, &labelled] {
+            upsert_note(&conn, n).unwrap();
+        }
+        assert_eq!(find_note_by_title(&conn, " ÄRGER IM BÜRO ").unwrap().unwrap().id, "n1");
+        let mut ids: Vec<String> =
+            notes_linking_to(&conn, "n1", &target.title).unwrap().into_iter().map(|n| n.id).collect();
+        ids.sort();
+        assert_eq!(ids, ["n2", "n4", "n5"]);
+    }
+
     #[test]
     fn parse_when_handles_date_and_datetime() {
         assert_eq!(parse_when("2026-08-06", false), Some("2026-08-06T00:00:00".parse().unwrap()));
@@ -765,6 +2072,75 @@ mod tests {
         assert_eq!(v, SCHEMA_VERSION.to_string());
     }
 
+    /// Build a DB the way a v1 build left it: v1 tables, no full-text index,
+    /// no trash columns.
+    fn v1_db() -> std::path::PathBuf {
+        let path = NamedTempFile::new().unwrap().into_temp_path().keep().unwrap();
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+             INSERT INTO meta VALUES ('schema_version', '1');
+             CREATE TABLE notes (id TEXT PRIMARY KEY, title TEXT NOT NULL, tags TEXT NOT NULL,
+                 notebook TEXT NOT NULL, created TEXT NOT NULL, updated TEXT NOT NULL,
+                 related TEXT NOT NULL DEFAULT '[]', body TEXT NOT NULL);
+             INSERT INTO notes VALUES ('n1', 'Old note', '[\"legacy\"]', 'nb',
+                 '2026-01-01T00:00:00', '2026-01-01T00:00:00', '[]', 'kept across upgrades');
+             CREATE TABLE pulses (id TEXT PRIMARY KEY, topic TEXT NOT NULL, interval TEXT NOT NULL,
+                 created TEXT NOT NULL);
+             CREATE TABLE metrics (id TEXT PRIMARY KEY, topic TEXT NOT NULL, created TEXT NOT NULL);",
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn open_migrates_older_schema_with_backup() {
+        let path = v1_db();
+        let conn = open(&path).unwrap();
+        let v: String = conn
+            .query_row("SELECT value FROM meta WHERE key = 'schema_version'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(v, SCHEMA_VERSION.to_string());
+        // data survives and the v2 full-text index covers it
+        assert_eq!(get_note(&conn, "n1").unwrap().unwrap().tags, vec!["legacy".to_string()]);
+        let hits = search_notes(&conn, NoteField::Content, "upgrades", NoteMatch::default()).unwrap();
+        assert_eq!(hits.len(), 1);
+        // v4 folded the existing title
+        assert_eq!(find_note_by_title(&conn, "OLD NOTE").unwrap().unwrap().id, "n1");
+        // v5 added the vocabulary over the rebuilt index
+        let words: i64 = conn.query_row("SELECT count(*) FROM notes_vocab", [], |r| r.get(0)).unwrap();
+        assert!(words > 0);
+
+        // the pre-migration snapshot is still a v1 DB
+        let backup = migration_backup_path(&path, 1);
+        let old = Connection::open(&backup).unwrap();
+        let v: String = old
+            .query_row("SELECT value FROM meta WHERE key = 'schema_version'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(v, "1");
+        std::fs::remove_file(backup).unwrap();
+
+        // reopening at the current version does not migrate again
+        drop(conn);
+        open(&path).unwrap();
+        assert!(!migration_backup_path(&path, 1).exists());
+    }
+
+    #[test]
+    fn open_rejects_newer_schema() {
+        let path = NamedTempFile::new().unwrap().into_temp_path().keep().unwrap();
+        drop(open(&path).unwrap());
+        let conn = Connection::open(&path).unwrap();
+        conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+            params![(SCHEMA_VERSION + 1).to_string()],
+        )
+        .unwrap();
+        drop(conn);
+        let err = open(&path).unwrap_err().to_string();
+        assert!(err.contains("newer"), "{err}");
+    }
+
     #[test]
     fn draft_crud_consume_and_watermark() {
         use crate::models::DraftContent;
@@ -0,0 +1,104 @@
+//! Typo-tolerant word matching, for when a search finds nothing:
+//! `db::search_fuzzy` matches query words against title / tag words within
+//! a few edits, and `db::did_you_mean` swaps unknown words for the closest
+//! known one.
+
+/// Edits (insert, delete, substitute, swap two neighbours) between `a` and
+/// `b`, by character.
+pub fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Rows i-2, i-1 and i of the DP table.
+    let mut prev2 = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        cur[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                cur[j] = cur[j].min(prev2[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+/// Typos forgiven in a word this long: none up to 2 characters, one up to
+/// 5, then two.
+pub fn max_typos(word: &str) -> usize {
+    match word.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// Lowercased words of `text`, split like the full-text index splits them.
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase)
+}
+
+/// How far `word` (lowercase) is from `candidate` (lowercase), if within
+/// its [`max_typos`]. With `prefix`, `candidate` may also run on past the
+/// typo'd word (`powr` is one edit from `powershell`). An exact match is
+/// always `Some(0)`, however short the word.
+pub fn typos(word: &str, candidate: &str, prefix: bool) -> Option<usize> {
+    if word == candidate {
+        return Some(0);
+    }
+    let max = max_typos(word);
+    let n = word.chars().count();
+    if max == 0 || candidate.chars().count() + max < n {
+        return None;
+    }
+    let mut d = distance(word, candidate);
+    if prefix && n > 3 {
+        for len in n.saturating_sub(max)..=n + max {
+            let head: String = candidate.chars().take(len).collect();
+            d = d.min(distance(word, &head));
+        }
+    }
+    (d <= max).then_some(d)
+}
+
+/// The candidate closest to `word`, if any is within its [`max_typos`]:
+/// fewest typos, then the most frequent, then alphabetical.
+pub fn closest<'a>(word: &str, candidates: impl IntoIterator<Item = (&'a str, usize)>) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .filter_map(|(c, freq)| typos(word, c, false).map(|d| (d, std::cmp::Reverse(freq), c)))
+        .min()
+        .map(|(_, _, c)| c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_and_thresholds() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("powershell", "powershell"), 0);
+        assert_eq!(distance("pwoershell", "powershell"), 1);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("héllo", "hello"), 1);
+
+        assert_eq!(typos("powrshell", "powershell", false), Some(1));
+        assert_eq!(typos("pwr", "power", false), None);
+        assert_eq!(typos("powr", "powershell", true), Some(1));
+        assert_eq!(typos("powr", "powershell", false), None);
+        assert_eq!(typos("go", "do", false), None);
+        assert_eq!(typos("go", "go", false), Some(0));
+        assert_eq!(typos("rust", "rest", false), Some(1));
+
+        let vocab = [("rest", 1), ("rust", 5), ("crust", 9)];
+        assert_eq!(closest("rsut", vocab), Some("rust"));
+        assert_eq!(closest("rusk", vocab), Some("rust"));
+        assert_eq!(closest("zzzz", vocab), None);
+        assert_eq!(words("PowerShell: set-prompt").collect::<Vec<_>>(), vec!["powershell", "set", "prompt"]);
+    }
+}
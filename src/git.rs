@@ -16,13 +16,48 @@ const DEFAULT_BRANCH: &str = "master";
 /// Cap on how many commits `BackupStatus` lists per direction.
 const MAX_STATUS_LOG: usize = 20;
 
-/// Ensure `repo` is a git repo. Idempotent.
+/// `.gitattributes` lines routing item YAML through the `ron` merge driver.
+const MERGE_ATTRIBUTES: [&str; 3] = [
+    "notes/*.yaml merge=ron",
+    "pulses/*.yaml merge=ron",
+    "metrics/*.yaml merge=ron",
+];
+
+/// Command git runs for files marked `merge=ron` (see `merge::merge_driver`):
+/// the running `ron` binary by its absolute path, shell-quoted, so git finds
+/// it whatever the `PATH` of whoever merges. Anything else linking this
+/// library (a test harness) has no `merge-driver` subcommand, so it falls
+/// back to `ron` on the `PATH`.
+fn merge_driver() -> String {
+    let exe = std::env::current_exe()
+        .ok()
+        .filter(|p| p.file_stem().is_some_and(|s| s == "ron"));
+    let program = match exe {
+        Some(p) => shell_quote(&p.to_string_lossy()),
+        None => "ron".to_string(),
+    };
+    format!("{program} merge-driver %O %A %B %P")
+}
+
+/// `s` as one single-quoted `sh` word.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Ensure `repo` is a git repo with the `ron` merge driver registered.
+/// Idempotent.
 pub fn ensure_repo(repo: &Path) -> Result<()> {
+    ensure_repo_on(repo, DEFAULT_BRANCH)
+}
+
+/// [`ensure_repo`], creating a new repo on `branch` (the configured backup
+/// branch) rather than the default.
+pub fn ensure_repo_on(repo: &Path, branch: &str) -> Result<()> {
     if !repo.join(".git").exists() {
         let out = Command::new("git")
             .arg("-C")
             .arg(repo)
-            .args(["init", "-b", DEFAULT_BRANCH])
+            .args(["init", "-b", branch])
             .output()
             .context("git init")?;
         if !out.status.success() {
@@ -32,6 +67,48 @@ pub fn ensure_repo(repo: &Path) -> Result<()> {
             );
         }
     }
+    register_merge_driver(repo)
+}
+
+/// Point `merge=ron` at `ron merge-driver` in the repo config and mark the
+/// item directories with it in `.gitattributes`, adding whichever lines are
+/// missing. The server's `.gitignore` lists `.gitattributes`, so it stays
+/// out of commits (export's `git add -A` included). Rerun on every start,
+/// so the driver follows the binary if it moves.
+fn register_merge_driver(repo: &Path) -> Result<()> {
+    for (key, value) in [
+        ("merge.ron.name", "ron item-aware YAML merge".to_string()),
+        ("merge.ron.driver", merge_driver()),
+    ] {
+        let out = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["config", key, &value])
+            .output()
+            .context("git config")?;
+        if !out.status.success() {
+            anyhow::bail!(
+                "git config {key} failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+        }
+    }
+    let path = repo.join(".gitattributes");
+    let mut text = std::fs::read_to_string(&path).unwrap_or_default();
+    let missing: Vec<&str> = MERGE_ATTRIBUTES
+        .into_iter()
+        .filter(|line| !text.lines().any(|l| l.trim() == *line))
+        .collect();
+    if !missing.is_empty() {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        for line in missing {
+            text.push_str(line);
+            text.push('\n');
+        }
+        std::fs::write(&path, text).with_context(|| format!("writing {}", path.display()))?;
+    }
     Ok(())
 }
 
@@ -50,6 +127,26 @@ pub fn remote_url(repo: &Path, name: &str) -> Result<Option<String>> {
     ))
 }
 
+/// Remove a configured remote. Returns false when it didn't exist.
+pub fn remove_remote(repo: &Path, name: &str) -> Result<bool> {
+    if remote_url(repo, name)?.is_none() {
+        return Ok(false);
+    }
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["remote", "remove", name])
+        .output()
+        .context("git remote remove")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "git remote remove failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(true)
+}
+
 /// Configure a remote URL. Idempotent.
 pub fn set_remote(repo: &Path, name: &str, url: &str) -> Result<()> {
     let current = remote_url(repo, name)?;
@@ -99,8 +196,10 @@ pub fn add_and_commit(repo: &Path, paths: &[&str], message: &str) -> Result<bool
     if !out.status.success() {
         let stderr = String::from_utf8_lossy(&out.stderr);
         let stdout = String::from_utf8_lossy(&out.stdout);
-        // `nothing to commit, working tree clean` is the common benign case.
-        if stderr.contains("nothing to commit") || stdout.contains("nothing to commit") {
+        // `nothing to commit, working tree clean` is the common benign case;
+        // untracked files (`.gitignore`, `.gitattributes`) turn it into
+        // `nothing added to commit`.
+        if nothing_to_commit(&stdout) || nothing_to_commit(&stderr) {
             return Ok(false);
         }
         anyhow::bail!("git commit failed: {}", stderr.trim());
@@ -108,6 +207,11 @@ pub fn add_and_commit(repo: &Path, paths: &[&str], message: &str) -> Result<bool
     Ok(true)
 }
 
+/// Whether `git commit` output says there was nothing staged.
+fn nothing_to_commit(output: &str) -> bool {
+    output.contains("nothing to commit") || output.contains("nothing added to commit")
+}
+
 /// Remove a path (file) from the index and commit its deletion.
 pub fn remove_and_commit(repo: &Path, paths: &[&str], message: &str) -> Result<bool> {
     let mut rm = Command::new("git");
@@ -154,7 +258,7 @@ pub fn add_all_and_commit(repo: &Path, _paths: &[&str], message: &str) -> Result
     if !out.status.success() {
         let stderr = String::from_utf8_lossy(&out.stderr);
         let stdout = String::from_utf8_lossy(&out.stdout);
-        if stderr.contains("nothing to commit") || stdout.contains("nothing to commit") {
+        if nothing_to_commit(&stdout) || nothing_to_commit(&stderr) {
             return Ok(false);
         }
         anyhow::bail!("git commit failed: {}", stderr.trim());
@@ -178,29 +282,213 @@ pub fn push(repo: &Path, remote: &str, branch: &str) -> Result<()> {
     Ok(())
 }
 
-/// Pull and return the list of YAML files that changed.
-pub fn pull(repo: &Path, remote: &str, branch: &str) -> Result<Vec<PathBuf>> {
-    let before = head_files(repo)?;
+/// Fetch `<branch>` from `<remote>`, updating `<remote>/<branch>`. Unlike
+/// the status check's fetch, an unreachable remote is an error here.
+pub fn fetch_branch(repo: &Path, remote: &str, branch: &str) -> Result<()> {
     let out = Command::new("git")
         .arg("-C")
         .arg(repo)
-        .args(["pull", "--ff-only", remote, branch])
+        .args(["fetch", remote, branch])
         .output()
-        .with_context(|| format!("git pull {remote} {branch}"))?;
+        .with_context(|| format!("git fetch {remote} {branch}"))?;
     if !out.status.success() {
         anyhow::bail!(
-            "git pull failed: {}",
+            "git fetch failed: {}",
             String::from_utf8_lossy(&out.stderr).trim()
         );
     }
-    let after = head_files(repo)?;
-    let mut diff: Vec<PathBuf> = after
-        .iter()
-        .filter(|p| !before.contains(p))
-        .cloned()
+    Ok(())
+}
+
+/// Full commit hash `rev` resolves to, or `None` (unborn branch, missing
+/// ref).
+pub fn resolve(repo: &Path, rev: &str) -> Option<String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{rev}^{{commit}}"))
+        .output()
+        .ok()?;
+    out.status.success()
+        .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// Best common ancestor of two commits, or `None` for unrelated histories.
+pub fn merge_base(repo: &Path, a: &str, b: &str) -> Option<String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["merge-base", a, b])
+        .output()
+        .ok()?;
+    out.status.success()
+        .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// Repo-relative paths that differ between commits `from` and `to` (a
+/// rename shows up as a delete plus an add).
+pub fn changed_paths(repo: &Path, from: &str, to: &str) -> Result<Vec<String>> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["diff", "--name-only", "--no-renames", from, to, "--"])
+        .output()
+        .context("git diff")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Repo-relative paths that differ between HEAD and the working tree:
+/// modified, deleted and untracked (not ignored) files.
+pub fn worktree_changes(repo: &Path) -> Result<Vec<String>> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["status", "--porcelain", "-z", "--untracked-files=all", "--no-renames"])
+        .output()
+        .context("git status")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "git status failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    // `XY <path>` entries, NUL-separated.
+    Ok(String::from_utf8_lossy(&out.stdout)
+        .split('\0')
+        .filter_map(|entry| entry.get(3..))
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Fast-forward the checked-out branch (unborn or behind) to `rev`.
+pub fn fast_forward(repo: &Path, rev: &str) -> Result<()> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["merge", "--ff-only", rev])
+        .output()
+        .with_context(|| format!("git merge --ff-only {rev}"))?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "git merge --ff-only failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Start merging `rev` into the checked-out branch without committing.
+/// Paths changed on one side only are merged by git; paths it can't merge
+/// are left conflicted for the caller to resolve, stage and
+/// [`commit_merge`] (or [`abort_merge`]).
+pub fn begin_merge(repo: &Path, rev: &str) -> Result<()> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["merge", "--no-ff", "--no-commit", "-X", "no-renames", rev])
+        .output()
+        .with_context(|| format!("git merge {rev}"))?;
+    // A conflicted merge exits non-zero but is still in progress; anything
+    // else (dirty tree in the way, bad rev) never got that far.
+    if !out.status.success() && !rev_exists(repo, "MERGE_HEAD") {
+        anyhow::bail!(
+            "git merge failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Paths still conflicted in an in-progress merge.
+pub fn unmerged_paths(repo: &Path) -> Result<Vec<String>> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["diff", "--name-only", "--diff-filter=U"])
+        .output()
+        .context("git diff --diff-filter=U")?;
+    // Each path is listed once per unmerged stage.
+    let mut paths: Vec<String> = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(str::to_string)
         .collect();
-    diff.extend(before.iter().filter(|p| !after.contains(p)).cloned());
-    Ok(diff)
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Put `rel_path` in the working tree back to its contents at `rev`.
+pub fn checkout_file(repo: &Path, rev: &str, rel_path: &str) -> Result<()> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["checkout", rev, "--", rel_path])
+        .output()
+        .context("git checkout")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "git checkout failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Stage `paths` as they are in the working tree, deletions included.
+pub fn stage(repo: &Path, paths: &[String]) -> Result<()> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["add", "-A", "--"])
+        .args(paths)
+        .output()
+        .context("git add")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "git add failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Commit the in-progress merge with `message`.
+pub fn commit_merge(repo: &Path, message: &str) -> Result<()> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["commit", "--no-edit", "-m", message])
+        .output()
+        .context("git commit")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "git commit failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Throw away an in-progress merge, restoring the pre-merge tree.
+pub fn abort_merge(repo: &Path) {
+    let _ = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["merge", "--abort"])
+        .output();
 }
 
 /// List all files in HEAD (only tracked paths).
@@ -220,6 +508,135 @@ pub fn head_files(repo: &Path) -> Result<Vec<PathBuf>> {
         .collect())
 }
 
+/// One commit in a file's history (`git log --follow -- <path>`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Revision {
+    /// Full commit hash.
+    pub hash: String,
+    /// Commit time, in local time.
+    pub date: chrono::NaiveDateTime,
+    pub subject: String,
+    /// The file's repo-relative path as of this commit (differs from today's
+    /// for commits before a rename, e.g. the flat -> per-type layout move).
+    pub path: String,
+    /// Whether this commit deleted the file.
+    pub deleted: bool,
+}
+
+/// Whether `rev` looks like a (possibly abbreviated) commit hash. History
+/// endpoints only accept hashes, so user input never reaches git as an
+/// option or an arbitrary revspec.
+pub fn is_commit_hash(rev: &str) -> bool {
+    (4..=40).contains(&rev.len()) && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Commits that touched `rel_path`, newest first, following renames.
+/// Empty when the file was never committed.
+pub fn file_log(repo: &Path, rel_path: &str) -> Result<Vec<Revision>> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args([
+            "log",
+            "--follow",
+            "--name-status",
+            "--date=format-local:%Y-%m-%dT%H:%M:%S",
+            "--format=%x1e%H%x09%cd%x09%s",
+            "--",
+            rel_path,
+        ])
+        .output()
+        .context("git log")?;
+    if !out.status.success() {
+        // No commits yet.
+        return Ok(Vec::new());
+    }
+    let text = String::from_utf8_lossy(&out.stdout);
+    let mut revs = Vec::new();
+    for entry in text.split('\x1e').filter(|e| !e.trim().is_empty()) {
+        let mut lines = entry.lines();
+        let header = lines.next().unwrap_or_default();
+        let mut parts = header.splitn(3, '\t');
+        let (Some(hash), Some(date), subject) = (parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+        let Ok(date) = chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S") else {
+            continue;
+        };
+        // `--name-status` line: "M\tpath", "D\tpath" or "R100\told\tnew".
+        let status = lines.find(|l| !l.trim().is_empty()).unwrap_or_default();
+        let cols: Vec<&str> = status.split('\t').collect();
+        revs.push(Revision {
+            hash: hash.to_string(),
+            date,
+            subject: subject.unwrap_or_default().to_string(),
+            path: cols.last().filter(|_| cols.len() > 1).unwrap_or(&rel_path).to_string(),
+            deleted: cols.first() == Some(&"D"),
+        });
+    }
+    Ok(revs)
+}
+
+/// Contents of `rel_path` at commit `rev`, or `None` when the file doesn't
+/// exist there.
+pub fn show_file(repo: &Path, rev: &str, rel_path: &str) -> Result<Option<String>> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .arg("show")
+        .arg(format!("{rev}:{rel_path}"))
+        .output()
+        .context("git show")?;
+    if !out.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&out.stdout).into_owned()))
+}
+
+/// Three-way merge of two edits of `base` (`git merge-file --diff3`).
+/// Returns the merged text and how many conflicts it has; each conflict is
+/// left in the text between markers named after `labels` (ours, base,
+/// theirs).
+pub fn merge_text(base: &str, ours: &str, theirs: &str, labels: [&str; 3]) -> Result<(String, usize)> {
+    let dir = tempfile::tempdir().context("create temp dir")?;
+    let write = |name: &str, text: &str| -> Result<PathBuf> {
+        let p = dir.path().join(name);
+        std::fs::write(&p, text)?;
+        Ok(p)
+    };
+    let (o, b, t) = (write("ours", ours)?, write("base", base)?, write("theirs", theirs)?);
+    let out = Command::new("git")
+        .args(["merge-file", "-p", "--diff3"])
+        .args(["-L", labels[0], "-L", labels[1], "-L", labels[2]])
+        .args([&o, &b, &t])
+        .output()
+        .context("git merge-file")?;
+    // The exit code is the number of conflicts (capped at 127); a failure
+    // is negative, which shows up as >127 or a signal.
+    match out.status.code() {
+        Some(n @ 0..=127) => Ok((String::from_utf8_lossy(&out.stdout).into_owned(), n as usize)),
+        _ => anyhow::bail!("git merge-file failed: {}", String::from_utf8_lossy(&out.stderr).trim()),
+    }
+}
+
+/// Unified diff that commit `rev` made to `rel_path` (against its parent;
+/// the whole file as added for a root commit).
+pub fn file_diff(repo: &Path, rev: &str, rel_path: &str) -> Result<String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["show", "--format=", "--no-color", "--no-ext-diff", rev, "--", rel_path])
+        .output()
+        .context("git show")?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "git show failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
 /// One line of `git log --oneline`: short hash + subject.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommitLine {
@@ -338,6 +755,17 @@ fn log_oneline(repo: &Path, revspec: &str, max: usize) -> Vec<CommitLine> {
     }
 }
 
+/// True when `rel_path` is in the index (committed or staged).
+pub fn is_tracked(repo: &Path, rel_path: &str) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["ls-files", "--error-unmatch", "--", rel_path])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
 /// True when tracked files have uncommitted changes (modified/staged/
 /// deleted). Untracked files are ignored on purpose: the server leaves its
 /// auto-created `.gitignore` and hand-dropped `resources/` untracked, which
@@ -419,6 +847,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn merge_text_combines_edits_and_marks_conflicts() {
+        let base = "a\nb\nc\n";
+        let (clean, n) = merge_text(base, "A\nb\nc\n", "a\nb\nC\n", ["yours", "base", "server"]).unwrap();
+        assert_eq!((clean.as_str(), n), ("A\nb\nC\n", 0));
+        let (marked, n) = merge_text(base, "a\nX\nc\n", "a\nY\nc\n", ["yours", "base", "server"]).unwrap();
+        assert_eq!(n, 1);
+        assert!(marked.contains("<<<<<<< yours\nX\n||||||| base\nb\n=======\nY\n>>>>>>> server\n"));
+    }
+
     #[test]
     fn ensure_repo_creates_and_is_idempotent() {
         let dir = tempdir().unwrap();
@@ -427,6 +865,18 @@ mod tests {
         ensure_repo(&repo).unwrap();
         ensure_repo(&repo).unwrap();
         assert!(repo.join(".git").exists());
+        let attrs = std::fs::read_to_string(repo.join(".gitattributes")).unwrap();
+        assert_eq!(attrs.lines().count(), MERGE_ATTRIBUTES.len());
+        let git = |args: &[&str]| {
+            let out = Command::new("git").arg("-C").arg(&repo).args(args).output().unwrap();
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        assert_eq!(git(&["config", "merge.ron.driver"]), merge_driver());
+        // The test harness isn't `ron`; the real binary is quoted by path.
+        assert_eq!(merge_driver(), "ron merge-driver %O %A %B %P");
+        assert_eq!(shell_quote("/opt/my tools/it's/ron"), r"'/opt/my tools/it'\''s/ron'");
+        assert_eq!(git(&["check-attr", "merge", "pulses/pulse-x.yaml"]), "pulses/pulse-x.yaml: merge: ron");
+        assert_eq!(git(&["check-attr", "merge", "searches/s.yaml"]), "searches/s.yaml: merge: unspecified");
     }
 
     #[test]
@@ -447,6 +897,9 @@ mod tests {
             remote_url(repo, "origin").unwrap().as_deref(),
             Some("https://example.com/r2.git")
         );
+        assert!(remove_remote(repo, "origin").unwrap());
+        assert!(!remove_remote(repo, "origin").unwrap());
+        assert_eq!(remote_url(repo, "origin").unwrap(), None);
     }
 
     #[test]
@@ -491,6 +944,42 @@ mod tests {
         assert!(names.contains(&"q.yaml".into()));
     }
 
+    #[test]
+    fn file_log_show_and_diff_follow_a_file() {
+        let dir = tempdir().unwrap();
+        let repo = dir.path();
+        ensure_repo(repo).unwrap();
+        config(repo);
+        std::fs::write(repo.join("n.yaml"), "title: one\n").unwrap();
+        add_and_commit(repo, &["n.yaml"], "first").unwrap();
+        std::fs::create_dir_all(repo.join("notes")).unwrap();
+        std::fs::rename(repo.join("n.yaml"), repo.join("notes/n.yaml")).unwrap();
+        add_all_and_commit(repo, &[], "move").unwrap();
+        std::fs::write(repo.join("notes/n.yaml"), "title: two\n").unwrap();
+        add_and_commit(repo, &["notes/n.yaml"], "second").unwrap();
+        std::fs::remove_file(repo.join("notes/n.yaml")).unwrap();
+        remove_and_commit(repo, &["notes/n.yaml"], "gone").unwrap();
+
+        let log = file_log(repo, "notes/n.yaml").unwrap();
+        let subjects: Vec<&str> = log.iter().map(|r| r.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["gone", "second", "move", "first"]);
+        assert!(log[0].deleted);
+        assert!(!log[1].deleted);
+        assert_eq!(log[3].path, "n.yaml");
+        assert_eq!(log[1].path, "notes/n.yaml");
+
+        assert_eq!(show_file(repo, &log[1].hash, &log[1].path).unwrap().as_deref(), Some("title: two\n"));
+        assert_eq!(show_file(repo, &log[3].hash, &log[3].path).unwrap().as_deref(), Some("title: one\n"));
+        assert!(show_file(repo, &log[0].hash, &log[0].path).unwrap().is_none());
+        let diff = file_diff(repo, &log[1].hash, &log[1].path).unwrap();
+        assert!(diff.contains("-title: one") && diff.contains("+title: two"), "{diff}");
+
+        assert!(file_log(repo, "notes/never.yaml").unwrap().is_empty());
+        assert!(is_commit_hash(&log[0].hash[..7]));
+        assert!(!is_commit_hash("--output=x"));
+        assert!(!is_commit_hash("HEAD"));
+    }
+
     /// Bare repo to use as `origin`, plus a working clone wired to it.
     fn origin_and_clone(dir: &std::path::Path) -> (std::path::PathBuf, std::path::PathBuf) {
         let origin = dir.join("origin.git");
@@ -45,6 +45,42 @@ pub fn new_id_at(kind: Kind, now: &chrono::DateTime<chrono::Local>) -> String {
     format!("{}-{}-{}-{:03x}", kind.prefix(), date, time, rand3)
 }
 
+/// True for a well-formed note ID: `note-<8 digits>-<4 digits>-<3 hex>`.
+pub fn is_note_id(s: &str) -> bool {
+    let Some(rest) = s.strip_prefix("note-") else {
+        return false;
+    };
+    let parts: Vec<&str> = rest.split('-').collect();
+    matches!(parts.as_slice(), [date, time, rand]
+        if date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit())
+            && time.len() == 4 && time.bytes().all(|b| b.is_ascii_digit())
+            && rand.len() == 3 && rand.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Note IDs mentioned in free text (e.g. a note body), in order of first
+/// appearance. Only whole IDs count: `xnote-…` or `…-a8f0` don't.
+pub fn note_ids_in(text: &str) -> Vec<String> {
+    const LEN: usize = "note-20260806-1432-a8f".len();
+    let word = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+    let mut out: Vec<String> = Vec::new();
+    for (at, _) in text.match_indices("note-") {
+        if text[..at].chars().next_back().is_some_and(word) {
+            continue;
+        }
+        let Some(candidate) = text.get(at..at + LEN) else {
+            continue;
+        };
+        let after = text[at + LEN..].chars().next();
+        if is_note_id(candidate)
+            && !after.is_some_and(|c| c.is_alphanumeric() || c == '_')
+            && !out.iter().any(|id| id == candidate)
+        {
+            out.push(candidate.to_string());
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,4 +105,17 @@ mod tests {
         assert_eq!(Kind::Pulse.prefix(), "pulse");
         assert_eq!(Kind::Metric.prefix(), "metric");
     }
+
+    #[test]
+    fn finds_whole_note_ids_in_text() {
+        let id = new_id(Kind::Note);
+        assert!(is_note_id(&id));
+        assert!(!is_note_id("pulse-20260806-1432-a8f"));
+        assert!(!is_note_id("note-2026080-1432-a8f"));
+        let text = "see note-20260806-1432-a8f, and [x](/view/note-20260101-0000-000).\n\
+                    not xnote-20260806-1432-a8f nor note-20260806-1432-a8f0; \
+                    again note-20260806-1432-a8f";
+        assert_eq!(note_ids_in(text), ["note-20260806-1432-a8f", "note-20260101-0000-000"]);
+        assert!(note_ids_in("note-é").is_empty());
+    }
 }
@@ -9,14 +9,21 @@
 pub mod client;
 pub mod db;
 pub mod editor;
+pub mod fuzzy;
 pub mod git;
 pub mod id;
+pub mod links;
+pub mod merge;
 pub mod migrate;
 pub mod models;
 pub mod paths;
+pub mod query;
 pub mod server;
+pub mod snippet;
 pub mod token;
+pub mod urlencoding;
 pub mod viewer;
+pub mod wikilink;
 pub mod yaml;
 
 pub use models::{Metric, Note, Pulse};
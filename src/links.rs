@@ -0,0 +1,150 @@
+//! Attachment references in note bodies: `resources/<name>` links, as the
+//! resources audit (`server::resources`) and the `has:attachment` search
+//! filter see them. Wiki links have their own module, [`crate::wikilink`].
+
+use pulldown_cmark::{Event, Parser, Tag};
+
+/// Flat file names only: no path separators, no `..`, no dotfiles.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains('/')
+        && !name.contains('\\')
+        && !name.contains("..")
+        && !name.starts_with('.')
+}
+
+/// Resource names referenced from a note body, percent-decoded (so
+/// `My%20File.pdf` names `My File.pdf`):
+///
+/// - markdown link and image destinations, parsed the way the viewer renders
+///   them (`<my file.pdf>` targets included);
+/// - quoted `src`/`href`-style attribute values in raw HTML;
+/// - bare `resources/<name>` mentions in the text, up to the end of the URL.
+///
+/// A target counts when it is `resources/<name>`, `./resources/<name>` or
+/// `/resources/<name>`; absolute URLs (`https://host/resources/x`) don't.
+pub fn referenced_names(body: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut add = |name: Option<String>| {
+        if let Some(name) = name {
+            if !out.contains(&name) {
+                out.push(name);
+            }
+        }
+    };
+    // pulldown-cmark splits a text run into several events; join them back
+    // so a mention is scanned whole.
+    let mut text = String::new();
+    for event in Parser::new_ext(body, crate::viewer::render::options()) {
+        match event {
+            Event::Text(t) | Event::Code(t) => {
+                text.push_str(&t);
+                continue;
+            }
+            Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) => {
+                add(resource_name(&dest_url));
+            }
+            Event::Html(html) | Event::InlineHtml(html) => {
+                for value in quoted_values(&html) {
+                    add(resource_name(value));
+                }
+            }
+            _ => {}
+        }
+        for name in bare_mentions(&text) {
+            add(Some(name));
+        }
+        text.clear();
+    }
+    for name in bare_mentions(&text) {
+        add(Some(name));
+    }
+    out
+}
+
+/// The resource a link target points at, if any: the percent-decoded name
+/// after `resources/`, `./resources/` or `/resources/`, without any query
+/// or fragment.
+fn resource_name(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let path = path
+        .strip_prefix("./")
+        .or_else(|| path.strip_prefix('/'))
+        .unwrap_or(path);
+    let name = crate::urlencoding::decode(path.strip_prefix("resources/")?);
+    valid_name(&name).then_some(name)
+}
+
+/// `resources/<name>` mentions in plain text. The prefix must start a word
+/// (or follow a lone `/` or `./`), and the name runs to the next space,
+/// bracket or quote, minus trailing punctuation.
+fn bare_mentions(text: &str) -> Vec<String> {
+    let prefix = "resources/";
+    let mut out = Vec::new();
+    for (at, _) in text.match_indices(prefix) {
+        let before = &text[..at];
+        let boundary = match before.chars().next_back() {
+            None => true,
+            Some('/') => !before[..before.len() - 1]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '-' || c == '_'),
+            Some(c) => !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.'),
+        };
+        if !boundary {
+            continue;
+        }
+        let rest = &text[at + prefix.len()..];
+        let end = rest
+            .find(|c: char| c.is_whitespace() || "()[]<>\"'`?#|".contains(c))
+            .unwrap_or(rest.len());
+        let name = crate::urlencoding::decode(rest[..end].trim_end_matches(['.', ',', ';', ':', '!']));
+        if valid_name(&name) {
+            out.push(name);
+        }
+    }
+    out
+}
+
+/// Every `"…"` / `'…'` attribute value in a chunk of raw HTML.
+fn quoted_values(html: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut rest = html;
+    while let Some(eq) = rest.find('=') {
+        rest = rest[eq + 1..].trim_start();
+        let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        let Some(len) = rest[1..].find(quote) else {
+            break;
+        };
+        out.push(&rest[1..1 + len]);
+        rest = &rest[len + 2..];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn referenced_names_finds_relative_and_rooted_urls_only() {
+        let body = "![a](resources/a.png) [b](./resources/b.pdf \"t\")\n\
+                    <img src=\"/resources/c.jpg\"> see resources/d.txt.\n\
+                    ![a again](resources/a.png) https://x.org/resources/e.png myresources/f";
+        assert_eq!(referenced_names(body), ["a.png", "b.pdf", "c.jpg", "d.txt"]);
+        assert!(referenced_names("resources/ and resources/../db").is_empty());
+    }
+
+    #[test]
+    fn referenced_names_decodes_and_accepts_spaced_targets() {
+        let body = "[spec](resources/My%20File.pdf) ![pic](<resources/my photo.jpg>)\n\
+                    [t](./resources/caf%C3%A9.txt?dl=1) <a href='/resources/old notes.md'>x</a>\n\
+                    `resources/a%2Fb` resources/100%.txt";
+        assert_eq!(
+            referenced_names(body),
+            ["My File.pdf", "my photo.jpg", "café.txt", "old notes.md", "100%.txt"]
+        );
+    }
+}
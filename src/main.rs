@@ -5,10 +5,15 @@
 //!   - `migrate <src> <dst>`              1.x -> 2.x YAML migration (P1)
 //!   - `token grant|list|revoke`          bearer-token management
 //!   - `viewer-key`                       print the viewer passphrase
-//!   - Notes:   add / edit / delete / view / list / search / relate
+//!   - Notes:   add / edit / delete / view / list / search / relate / attach
 //!   - Drafts:  draft edit|list|clear     note-edit recovery cache
 //!   - Pulses:  padd / pcheck / puncheck / plist / pedit / pdel
 //!   - Metrics: madd / mlog / mstats / mlist / medit / mdel
+//!   - Trash:   trash list|restore|empty
+//!   - Tags:    tags / tag-rename / tag-merge / tag-delete
+//!   - Notebooks: notebook list|rename|merge, move
+//!   - Searches: searches list|delete    saved searches (`search --save`)
+//!   - Resources: resources check       attachment audit
 
 use std::path::PathBuf;
 
@@ -20,6 +25,7 @@ fn main() -> Result<()> {
     match matches {
         Some(("serve", _)) => run_serve(),
         Some(("migrate", sub)) => run_migrate(sub),
+        Some(("merge-driver", sub)) => run_merge_driver(sub),
         Some(("token", sub)) => run_token(sub),
         Some(("add", _)) => notes_cmd::add(),
         Some(("edit", sub)) => notes_cmd::edit(index_or_id(sub)),
@@ -31,6 +37,13 @@ fn main() -> Result<()> {
             _ => unreachable!("subcommand_required prevents None"),
         },
         Some(("view", sub)) => notes_cmd::view(index_or_id(sub)),
+        Some(("restore", sub)) => notes_cmd::restore(
+            sub.get_one::<String>("id").unwrap(),
+            sub.get_one::<String>("rev").map(String::as_str),
+        ),
+        Some(("history", sub)) => {
+            notes_cmd::history(index_or_id(sub), sub.get_one::<String>("rev").cloned())
+        }
         Some(("list", sub)) => {
             let n: u32 = sub.get_one::<String>("number").map(|s| s.parse().unwrap_or(5)).unwrap_or(5);
             notes_cmd::list(Some(n))
@@ -43,9 +56,54 @@ fn main() -> Result<()> {
             let field = sub.get_one::<String>("field").cloned().unwrap_or_else(|| "content".into());
             let ignore_case = !*sub.get_one::<bool>("case").unwrap_or(&false);
             let whole_word = *sub.get_one::<bool>("whole").unwrap_or(&false);
-            notes_cmd::search(&ptns, &field, ignore_case, whole_word)
+            notes_cmd::search(
+                &ptns,
+                &field,
+                ignore_case,
+                whole_word,
+                sub.get_flag("regex"),
+                sub.get_flag("fuzzy"),
+                sub.get_one::<String>("save").map(String::as_str),
+            )
         }
+        Some(("searches", sub)) => match sub.subcommand() {
+            Some(("list", _)) => searches_cmd::list(),
+            Some(("delete", m)) => searches_cmd::delete(m.get_one::<String>("name").unwrap()),
+            _ => unreachable!("subcommand_required prevents None"),
+        },
         Some(("list-notebook", _)) => notes_cmd::list_notebooks(),
+        Some(("notebook", sub)) => match sub.subcommand() {
+            Some(("list", m)) => notebooks_cmd::list(m.get_flag("tree")),
+            Some(("rename", m)) => notebooks_cmd::rename(
+                m.get_one::<String>("old").unwrap(),
+                m.get_one::<String>("new").unwrap(),
+            ),
+            Some(("merge", m)) => notebooks_cmd::merge(
+                m.get_one::<String>("from").unwrap(),
+                m.get_one::<String>("into").unwrap(),
+            ),
+            _ => unreachable!("subcommand_required prevents None"),
+        },
+        Some(("move", sub)) => {
+            let targets: Vec<String> = sub
+                .get_many::<String>("notes")
+                .map(|v| v.cloned().collect())
+                .unwrap_or_default();
+            notes_cmd::move_notes(&targets, sub.get_one::<String>("to").unwrap())
+        }
+        Some(("tags", m)) => tags_cmd::list(m.get_flag("tree")),
+        Some(("tag-rename", sub)) => tags_cmd::rename(
+            sub.get_one::<String>("old").unwrap(),
+            sub.get_one::<String>("new").unwrap(),
+        ),
+        Some(("tag-merge", sub)) => {
+            let from: Vec<String> = sub
+                .get_many::<String>("from")
+                .map(|v| v.cloned().collect())
+                .unwrap_or_default();
+            tags_cmd::merge(from, sub.get_one::<String>("into").unwrap())
+        }
+        Some(("tag-delete", sub)) => tags_cmd::delete(sub.get_one::<String>("tag").unwrap()),
         Some(("relate", sub)) => {
             let id = sub.get_one::<String>("id").unwrap().clone();
             let related: Vec<String> = sub
@@ -54,6 +112,10 @@ fn main() -> Result<()> {
                 .unwrap_or_default();
             notes_cmd::relate(&id, related)
         }
+        Some(("attach", sub)) => notes_cmd::attach(
+            sub.get_one::<String>("target").unwrap().clone(),
+            std::path::Path::new(sub.get_one::<String>("file").unwrap()),
+        ),
         Some(("padd", sub)) => pulses_cmd::add(sub),
         Some(("pcheck", sub)) => pulses_cmd::set_check(sub, true),
         Some(("puncheck", sub)) => pulses_cmd::set_check(sub, false),
@@ -66,12 +128,37 @@ fn main() -> Result<()> {
         Some(("mlist", _)) => metrics_cmd::list(),
         Some(("mdel", sub)) => metrics_cmd::delete(sub),
         Some(("medit", sub)) => metrics_cmd::edit(sub),
+        Some(("trash", sub)) => match sub.subcommand() {
+            Some(("list", _)) => trash_cmd::list(),
+            Some(("restore", m)) => trash_cmd::restore(m.get_one::<String>("id").unwrap()),
+            Some(("empty", _)) => trash_cmd::empty(),
+            _ => unreachable!("subcommand_required prevents None"),
+        },
+        Some(("resources", sub)) => match sub.subcommand() {
+            Some(("check", m)) => {
+                resources_cmd::check(*m.get_one::<bool>("delete-orphans").unwrap_or(&false))
+            }
+            _ => unreachable!("subcommand_required prevents None"),
+        },
         Some(("export", _)) => admin_cmd::export(),
-        Some(("import", _)) => admin_cmd::import(),
-        Some(("backup", sub)) => {
-            admin_cmd::backup(*sub.get_one::<bool>("dry-run").unwrap_or(&false))
+        Some(("import", sub)) => admin_cmd::import(sub.get_flag("full")),
+        Some(("backup", sub)) => admin_cmd::backup(
+            *sub.get_one::<bool>("dry-run").unwrap_or(&false),
+            sub.get_one::<String>("to").map(String::as_str),
+        ),
+        Some(("remote", sub)) => match sub.subcommand() {
+            Some(("add", m)) => admin_cmd::remote_add(
+                m.get_one::<String>("name").unwrap(),
+                m.get_one::<String>("url").unwrap(),
+            ),
+            Some(("list", _)) => admin_cmd::remote_list(),
+            Some(("remove", m)) => admin_cmd::remote_remove(m.get_one::<String>("name").unwrap()),
+            _ => unreachable!("subcommand_required prevents None"),
+        },
+        Some(("sync", sub)) => admin_cmd::sync(sub.get_flag("ff-only")),
+        Some(("upgrade-format", sub)) => {
+            admin_cmd::upgrade_format(*sub.get_one::<bool>("dry-run").unwrap_or(&false))
         }
-        Some(("sync", _)) => admin_cmd::sync(),
         Some(("viewer-key", _)) => admin_cmd::viewer_key(),
         _ => unreachable!("subcommand_required prevents None"),
     }
@@ -106,6 +193,15 @@ fn parse_args() -> clap::ArgMatches {
                         .help("keep original Created on every mismatch (non-interactive)"),
                 ),
         )
+        .subcommand(
+            Command::new("merge-driver")
+                .about("git merge driver for item YAML (registered in the repo as `merge=ron`)")
+                .hide(true)
+                .arg(Arg::new("base").required(true).help("%O: common ancestor"))
+                .arg(Arg::new("current").required(true).help("%A: local version; overwritten with the result"))
+                .arg(Arg::new("other").required(true).help("%B: remote version"))
+                .arg(Arg::new("path").help("%P: path in the repo")),
+        )
         .subcommand(
             Command::new("token")
                 .about("manage bearer tokens")
@@ -133,7 +229,7 @@ fn parse_args() -> clap::ArgMatches {
         .subcommand(
             Command::new("delete")
                 .visible_alias("del")
-                .about("delete a note by ID or index")
+                .about("move a note to the trash by ID or index")
                 .arg(Arg::new("target").default_value("1")),
         )
         .subcommand(
@@ -142,6 +238,26 @@ fn parse_args() -> clap::ArgMatches {
                 .about("view a note by ID or index (cat to stdout)")
                 .arg(Arg::new("target").default_value("1")),
         )
+        .subcommand(
+            Command::new("history")
+                .about("list a note's revisions (git log), or show one with --rev")
+                .arg(Arg::new("target").default_value("1").help("note ID or index"))
+                .arg(
+                    Arg::new("rev")
+                        .long("rev")
+                        .help("commit hash from the history list; print the note as of it"),
+                ),
+        )
+        .subcommand(
+            Command::new("restore")
+                .about("restore a deleted note, or roll a note back to --rev (a new commit)")
+                .arg(Arg::new("id").required(true).help("note ID"))
+                .arg(
+                    Arg::new("rev")
+                        .long("rev")
+                        .help("commit hash from `ron history`; default: last version of a deleted note"),
+                ),
+        )
         .subcommand(
             Command::new("list")
                 .visible_alias("l")
@@ -151,7 +267,7 @@ fn parse_args() -> clap::ArgMatches {
         .subcommand(
             Command::new("search")
                 .visible_alias("s")
-                .about("search notes; supports `t:`/`g:`/`n:`/`a:` scope prefixes per pattern (defaults to a/content)")
+                .about("search notes: words (AND), OR, -word, \"phrases\", (groups), t:/g:/n:/a: scopes, created:/updated:/has:/related: filters, @saved")
                 .arg(
                     Arg::new("patterns")
                         .action(ArgAction::Append)
@@ -177,6 +293,37 @@ fn parse_args() -> clap::ArgMatches {
                         .short('w')
                         .action(ArgAction::SetTrue)
                         .help("match whole words only"),
+                )
+                .arg(
+                    Arg::new("fuzzy")
+                        .long("fuzzy")
+                        .short('z')
+                        .action(ArgAction::SetTrue)
+                        .help("if nothing matches, show notes whose titles / tags match with typos"),
+                )
+                .arg(
+                    Arg::new("regex")
+                        .long("regex")
+                        .short('r')
+                        .action(ArgAction::SetTrue)
+                        .help("treat the patterns (joined by spaces) as one regular expression"),
+                )
+                .arg(
+                    Arg::new("save")
+                        .long("save")
+                        .value_name("NAME")
+                        .help("also save the query as NAME; rerun it with `ron search @NAME`"),
+                ),
+        )
+        .subcommand(
+            Command::new("searches")
+                .about("saved searches (`ron search --save`, `@name`)")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("saved searches with their queries"))
+                .subcommand(
+                    Command::new("delete")
+                        .about("forget a saved search")
+                        .arg(Arg::new("name").required(true)),
                 ),
         )
         .subcommand(
@@ -184,12 +331,76 @@ fn parse_args() -> clap::ArgMatches {
                 .visible_alias("lnb")
                 .about("list notebooks"),
         )
+        .subcommand(
+            Command::new("notebook")
+                .about("reorganize notebooks; each change is one commit")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("list").about("notebooks with their note counts").arg(
+                        Arg::new("tree")
+                            .long("tree")
+                            .action(ArgAction::SetTrue)
+                            .help("nest slash-separated names, counting each level's subtree"),
+                    ),
+                )
+                .subcommand(
+                    Command::new("rename")
+                        .about("rename a notebook and its sub-notebooks (the new name must be unused)")
+                        .arg(Arg::new("old").required(true))
+                        .arg(Arg::new("new").required(true)),
+                )
+                .subcommand(
+                    Command::new("merge")
+                        .about("move every note of one notebook into another")
+                        .arg(Arg::new("from").required(true))
+                        .arg(Arg::new("into").required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("move")
+                .about("move notes to another notebook; one commit")
+                .arg(Arg::new("notes").required(true).num_args(1..).help("note IDs or indices"))
+                .arg(Arg::new("to").long("to").required(true).help("target notebook")),
+        )
+        .subcommand(
+            Command::new("tags")
+                .about("list tags with their note counts")
+                .arg(
+                    Arg::new("tree")
+                        .long("tree")
+                        .action(ArgAction::SetTrue)
+                        .help("nest slash-separated names, counting each level's subtree"),
+                ),
+        )
+        .subcommand(
+            Command::new("tag-rename")
+                .about("rename a tag on every note; one commit")
+                .arg(Arg::new("old").required(true))
+                .arg(Arg::new("new").required(true)),
+        )
+        .subcommand(
+            Command::new("tag-merge")
+                .about("replace several tags with one on every note; one commit")
+                .arg(Arg::new("from").required(true).num_args(1..).help("tag(s) to fold in"))
+                .arg(Arg::new("into").long("into").required(true).help("tag to keep")),
+        )
+        .subcommand(
+            Command::new("tag-delete")
+                .about("remove a tag from every note; one commit")
+                .arg(Arg::new("tag").required(true)),
+        )
         .subcommand(
             Command::new("relate")
                 .about("add related note IDs to a note")
                 .arg(Arg::new("id").required(true).help("note ID"))
                 .arg(Arg::new("to").required(true).num_args(1..).help("note ID(s) to relate")),
         )
+        .subcommand(
+            Command::new("attach")
+                .about("upload a file to resources/ and link it at the end of a note")
+                .arg(Arg::new("target").required(true).help("note ID or index"))
+                .arg(Arg::new("file").required(true).help("local file to upload")),
+        )
         .subcommand(
             Command::new("draft")
                 .about("manage note drafts (recovery cache for interrupted create/edit)")
@@ -248,7 +459,7 @@ fn parse_args() -> clap::ArgMatches {
                 ),
         )
         .subcommand(
-            Command::new("pdel").about("delete a pulse").arg(Arg::new("id").required(true)),
+            Command::new("pdel").about("move a pulse to the trash").arg(Arg::new("id").required(true)),
         )
         // ---- metrics ----
         .subcommand(
@@ -272,7 +483,7 @@ fn parse_args() -> clap::ArgMatches {
         )
         .subcommand(Command::new("mlist").about("list metrics"))
         .subcommand(
-            Command::new("mdel").about("delete a metric").arg(Arg::new("id").required(true)),
+            Command::new("mdel").about("move a metric to the trash").arg(Arg::new("id").required(true)),
         )
         .subcommand(
             Command::new("medit")
@@ -280,27 +491,102 @@ fn parse_args() -> clap::ArgMatches {
                 .arg(Arg::new("id").required(true))
                 .arg(Arg::new("topic").long("topic").short('t')),
         )
+        // ---- trash ----
+        .subcommand(
+            Command::new("trash")
+                .about("deleted notes/pulses/metrics, kept until purged")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("list trashed items, newest first"))
+                .subcommand(
+                    Command::new("restore")
+                        .about("take an item out of the trash")
+                        .arg(Arg::new("id").required(true)),
+                )
+                .subcommand(Command::new("empty").about("purge everything in the trash for good; one commit")),
+        )
+        // ---- resources ----
+        .subcommand(
+            Command::new("resources")
+                .about("note attachments under resources/")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("check")
+                        .about("list broken resources/ links and files no note references")
+                        .arg(
+                            Arg::new("delete-orphans")
+                                .long("delete-orphans")
+                                .action(ArgAction::SetTrue)
+                                .help("delete the unreferenced files; one commit"),
+                        ),
+                ),
+        )
         // ---- admin ----
         .subcommand(
             Command::new("export")
                 .about("rewrite all YAML files from the DB; git add+commit"),
         )
         .subcommand(
-            Command::new("import").about("reload the DB from the YAML files on disk"),
+            Command::new("import")
+                .about("reload the DB from the YAML files changed on disk since the last load")
+                .arg(
+                    Arg::new("full")
+                        .long("full")
+                        .action(ArgAction::SetTrue)
+                        .help("drop every row and reload the whole repo"),
+                ),
         )
         .subcommand(
             Command::new("backup")
-                .about("git push origin master (the repo must have a remote)")
+                .about("git push to the configured remote and branch (origin master by default)")
                 .arg(
                     Arg::new("dry-run")
                         .long("dry-run")
                         .action(ArgAction::SetTrue)
-                        .help("show ahead/behind vs origin and hints; don't push"),
+                        .help("show ahead/behind vs the remote and hints; don't push"),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .value_name("NAME")
+                        .help("push to this backup target (see `ron remote list`) instead"),
+                ),
+        )
+        .subcommand(
+            Command::new("remote")
+                .about("manage the git remotes the server backs up to")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("add a remote, or point an existing one at a new URL")
+                        .arg(Arg::new("name").required(true))
+                        .arg(Arg::new("url").required(true)),
+                )
+                .subcommand(Command::new("list").about("show remotes; `*` marks the backup/sync default"))
+                .subcommand(
+                    Command::new("remove")
+                        .about("remove a remote")
+                        .arg(Arg::new("name").required(true)),
                 ),
         )
         .subcommand(
             Command::new("sync")
-                .about("git pull --ff-only origin master, then reload DB from YAML"),
+                .about("pull the configured remote and branch, merging diverged notes/pulses/metrics item by item, then reload their DB rows")
+                .arg(
+                    Arg::new("ff-only")
+                        .long("ff-only")
+                        .action(ArgAction::SetTrue)
+                        .help("fail instead of merging when local and remote have diverged"),
+                ),
+        )
+        .subcommand(
+            Command::new("upgrade-format")
+                .about("rewrite YAML files from older format versions at the current one; one commit")
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(ArgAction::SetTrue)
+                        .help("list the outdated files; don't rewrite"),
+                ),
         )
         .subcommand(
             Command::new("viewer-key")
@@ -361,6 +647,26 @@ fn run_migrate(sub: &clap::ArgMatches) -> Result<()> {
     Ok(())
 }
 
+/// Run as git's `merge.ron.driver`: merge in place, report the fields that
+/// were settled by picking a side, and exit non-zero (leaving the local file)
+/// when a whole file couldn't be merged so git marks it conflicted.
+fn run_merge_driver(sub: &clap::ArgMatches) -> Result<()> {
+    let arg = |name: &str| PathBuf::from(sub.get_one::<String>(name).unwrap());
+    let current = arg("current");
+    let path = sub
+        .get_one::<String>("path")
+        .cloned()
+        .unwrap_or_else(|| current.to_string_lossy().into_owned());
+    let merged = ron::merge::merge_driver(&arg("base"), &current, &arg("other"), &path)?;
+    for c in &merged.conflicts {
+        eprintln!("ron merge-driver: {} {}: kept {}", c.file, c.field, c.kept);
+    }
+    if merged.conflicts.iter().any(|c| c.field == "file") {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
 /// Ask the user whether to rewrite a note's `Created` to the date found in its
 /// title. Keys: y=yes, n=no, a=yes-for-all, s=skip-all, q=abort. On EOF /
 /// non-interactive stdin the safe default is "keep".
@@ -396,7 +702,7 @@ fn run_token(sub: &clap::ArgMatches) -> Result<()> {
                 "/api/tokens",
                 &serde_json::json!({ "label": label }),
             )
-            .and_then(|r| json_or_err::<serde_json::Value>(r))
+            .and_then(json_or_err::<serde_json::Value>)
             .context("token grant failed; is the server running?")?;
             let stored = StoredToken {
                 id: resp["id"].as_str().context("missing id")?.to_string(),
@@ -411,7 +717,7 @@ fn run_token(sub: &clap::ArgMatches) -> Result<()> {
         }
         Some(("list", _)) => {
             let resp: serde_json::Value =
-                client::Api::get_no_auth("/api/tokens").and_then(|r| json_or_err::<serde_json::Value>(r))?;
+                client::Api::get_no_auth("/api/tokens").and_then(json_or_err::<serde_json::Value>)?;
             for t in resp.as_array().unwrap_or(&vec![]) {
                 println!("{}\t{}\t{}", t["id"], t["label"], t["created"]);
             }
@@ -453,6 +759,8 @@ mod notes_cmd {
     use ron::client;
     use ron::models::{DraftContent, Note};
     use ron::editor::EditOutcome;
+    use ron::snippet::SearchHit;
+    use std::io::IsTerminal;
 
     pub fn add() -> Result<()> {
         // The server is the authority for the default notebook; the local
@@ -498,14 +806,7 @@ mod notes_cmd {
                 });
             }
         };
-        let mut initial = format!(
-            "Title: {}\nTags: {}\nNotebook: {}\nRelated: {}\n\n------\n\n{}",
-            note.title,
-            note.tags.join("; "),
-            note.notebook,
-            note.related.join("; "),
-            note.body,
-        );
+        let mut initial = note_buffer(&note);
         let mut from_draft = false;
         if let Some(d) = resolve_draft(&key).filter(|d| d.updated > note.updated) {
             eprintln!(
@@ -516,32 +817,145 @@ mod notes_cmd {
             from_draft = true;
         }
         let outcome = ron::editor::edit(&initial)?;
-        let id = note.id.clone();
-        let related = note.related.clone();
-        finish_edit_session(&key, &outcome, &initial, from_draft, move |parsed| {
-            let updated = client::update_note(
-                &id,
-                Some(parsed.title),
-                Some(parsed.tags),
-                Some(parsed.notebook),
-                Some(parsed.body),
-                Some(related),
+        finish_edit_session(&key, &outcome, &initial, from_draft, move |parsed| save_edit(note, parsed))
+    }
+
+    /// The editor buffer for an existing note.
+    fn note_buffer(note: &Note) -> String {
+        format!(
+            "Title: {}\nTags: {}\nNotebook: {}\nRelated: {}\n\n------\n\n{}",
+            note.title,
+            note.tags.join("; "),
+            note.notebook,
+            note.related.join("; "),
+            note.body,
+        )
+    }
+
+    fn parsed_buffer(p: &ParsedNote) -> String {
+        format!(
+            "Title: {}\nTags: {}\nNotebook: {}\nRelated: {}\n\n------\n\n{}",
+            p.title,
+            p.tags.join("; "),
+            p.notebook,
+            p.related.join("; "),
+            p.body,
+        )
+    }
+
+    /// Save an edit made on `base`. If the note changed on the server in the
+    /// meantime, both edits are merged line by line and the result opened
+    /// in $EDITOR — overlapping changes between conflict markers — to be
+    /// saved over the newer copy. Quitting with a non-zero exit or leaving
+    /// markers in keeps the edit as a draft instead. The buffer's Related
+    /// line is for reference only (`ron relate` changes it), so the server's
+    /// list is kept.
+    fn save_edit(mut base: Note, mut edit: ParsedNote) -> Result<String> {
+        loop {
+            let sent = client::update_note(
+                &base.id,
+                Some(edit.title.clone()),
+                Some(edit.tags.clone()),
+                Some(edit.notebook.clone()),
+                Some(edit.body.clone()),
+                Some(base.related.clone()),
+                Some(base.updated),
+            );
+            let current = match sent {
+                Ok(updated) => return Ok(format!("updated {}", updated.id)),
+                Err(e) => match e.downcast::<client::Conflict>() {
+                    Ok(c) => c.current,
+                    Err(e) => return Err(e),
+                },
+            };
+            let (merged, conflicts) = ron::git::merge_text(
+                &note_buffer(&base),
+                &parsed_buffer(&edit),
+                &note_buffer(&current),
+                ["your edit", "as you opened it", "server"],
             )?;
-            Ok(format!("updated {}", updated.id))
-        })
+            eprintln!(
+                "{} was changed on the server at {} while you edited it; opening the merge ({conflicts} conflict(s))",
+                current.id,
+                current.updated.format("%Y-%m-%d %H:%M:%S"),
+            );
+            let outcome = ron::editor::edit(&merged)?;
+            if matches!(outcome, EditOutcome::ExitedNonzero(_)) {
+                return Err(anyhow!("merge abandoned"));
+            }
+            if has_conflict_markers(outcome.text()) {
+                return Err(anyhow!("the merge still has conflict markers"));
+            }
+            edit = parse_editor_buffer(outcome.text())?;
+            base = current;
+        }
+    }
+
+    /// Whether `text` still holds a whole conflict block: a `<<<<<<< ` line,
+    /// then `=======`, then `>>>>>>> `. A lone `=======` (a setext heading
+    /// underline) or one marker-like line isn't one.
+    fn has_conflict_markers(text: &str) -> bool {
+        let mut seen = 0;
+        for l in text.lines() {
+            match seen {
+                0 if l.starts_with("<<<<<<< ") => seen = 1,
+                1 if l.trim_end() == "=======" => seen = 2,
+                2 if l.starts_with(">>>>>>> ") => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Save a change to `note` with `send`, which gets the copy to change
+    /// and passes its `updated` as the precondition. When someone else saved
+    /// in between, the change is made again on the server's copy: `relate`
+    /// and `attach` only add to a note, so there is nothing to merge.
+    fn update_retrying(mut note: Note, send: impl Fn(&Note) -> Result<Note>) -> Result<Note> {
+        const ATTEMPTS: usize = 5;
+        for _ in 0..ATTEMPTS {
+            match send(&note) {
+                Ok(updated) => return Ok(updated),
+                Err(e) => match e.downcast::<client::Conflict>() {
+                    Ok(c) => note = c.current,
+                    Err(e) => return Err(e),
+                },
+            }
+        }
+        Err(anyhow!("{} kept changing on the server; gave up after {ATTEMPTS} tries", note.id))
     }
 
     pub fn delete(target: String) -> Result<()> {
         let id = resolve_target(&target)?;
         client::delete_note(&id)?;
-        println!("deleted {id}");
+        println!("moved {id} to the trash (`ron trash restore {id}` to undo)");
         Ok(())
     }
 
     pub fn view(target: String) -> Result<()> {
         let id = resolve_target(&target)?;
-        let note = client::get_note(&id)?;
-        let text = format!(
+        let mut note = client::get_note(&id)?;
+        if !ron::wikilink::parse(&note.body).is_empty() {
+            note.body = resolve_wiki_text(&note.body, &client::note_links(&id)?);
+        }
+        run_cli_viewer(&view_text(&note))
+    }
+
+    /// `[[target]]` links rewritten as `[Title](note-id)` for the terminal;
+    /// unresolved ones stay as written.
+    pub(crate) fn resolve_wiki_text(body: &str, links: &[client::ResolvedLink]) -> String {
+        ron::wikilink::rewrite(body, |link| {
+            let resolved = links.iter().find(|l| l.target == link.target);
+            match resolved.and_then(|l| Some((l.id.as_deref()?, l.title.as_deref()?))) {
+                Some((id, title)) => format!("[{}]({id})", link.label.as_deref().unwrap_or(title)),
+                None => body[link.span.clone()].to_string(),
+            }
+        })
+    }
+
+    /// Header block + body, as `view` prints a note.
+    fn view_text(note: &Note) -> String {
+        format!(
             "Title: {}\nTags: {}\nNotebook: {}\nRelated: {}\nCreated: {}\nUpdated: {}\nID: {}\n\n{}",
             note.title,
             note.tags.join("; "),
@@ -551,8 +965,41 @@ mod notes_cmd {
             note.updated.format("%F %T"),
             note.id,
             note.body,
+        )
+    }
+
+    pub fn restore(id: &str, rev: Option<&str>) -> Result<()> {
+        let id = id.strip_prefix("note:").unwrap_or(id);
+        let r = client::restore_note(id, rev)?;
+        println!(
+            "restored {} ({}) from {}",
+            r.note.id,
+            r.note.title,
+            &r.rev[..r.rev.len().min(8)]
         );
-        run_cli_viewer(&text)
+        Ok(())
+    }
+
+    /// Without `rev`: the note's commits, newest first. With `rev`: the note
+    /// as of that commit, through the CLI viewer like `view`.
+    pub fn history(target: String, rev: Option<String>) -> Result<()> {
+        let id = resolve_target(&target)?;
+        if let Some(rev) = rev {
+            let note = client::note_at_rev(&id, &rev)?;
+            return run_cli_viewer(&view_text(&note));
+        }
+        let revs = client::note_history(&id)?;
+        println!("{:<8}  {:<19}  subject", "rev", "date");
+        for r in &revs {
+            let mark = if r.deleted { "  (deleted)" } else { "" };
+            println!(
+                "{:<8}  {:<19}  {}{mark}",
+                &r.hash[..r.hash.len().min(8)],
+                r.date.format("%F %T").to_string(),
+                r.subject,
+            );
+        }
+        Ok(())
     }
 
     /// Pipe `text` through the configured `cli_viewer` command (default
@@ -600,23 +1047,65 @@ mod notes_cmd {
         Ok(())
     }
 
-    pub fn search(patterns: &[String], field: &str, ignore_case: bool, whole_word: bool) -> Result<()> {
-        // Each pattern may carry a scope prefix (t:/g:/n:/a:). When any do,
-        // run a separate search per pattern with its own field and intersect.
-        let mut results: Option<Vec<Note>> = None;
+    pub fn search(
+        patterns: &[String],
+        field: &str,
+        mut ignore_case: bool,
+        mut whole_word: bool,
+        mut regex: bool,
+        fuzzy: bool,
+        save: Option<&str>,
+    ) -> Result<()> {
+        // The server parses the query language; the patterns are AND-ed.
+        // `@name` stands for a saved search, scoped to its own field and
+        // bringing its case / whole-word options along. A regex search
+        // can't be combined with anything, so it only runs on its own.
+        let mut parts = Vec::new();
+        let mut field = field.to_string();
+        let mut saved_regex = None;
         for p in patterns {
-            let (fld, q) = split_scope(p);
-            let f = if fld == "default" { field } else { fld };
-            let hits = client::search_notes(q, f, ignore_case, whole_word)?;
-            results = Some(match results {
-                None => hits,
-                Some(prev) => prev
-                    .into_iter()
-                    .filter(|n| hits.iter().any(|h| h.id == n.id))
-                    .collect(),
-            });
+            match p.strip_prefix('@') {
+                Some(name) if !name.is_empty() => {
+                    let saved = client::get_search(name).with_context(|| format!("saved search @{name}"))?;
+                    ignore_case &= saved.ignore_case;
+                    whole_word |= saved.whole_word;
+                    if saved.regex {
+                        saved_regex = Some(saved);
+                    } else {
+                        parts.push(format!("{}:({})", saved.field, saved.query));
+                    }
+                }
+                _ if regex => parts.push(p.clone()),
+                _ => parts.push(query_arg(p)),
+            }
+        }
+        if let Some(saved) = &saved_regex {
+            if patterns.len() > 1 || regex {
+                return Err(anyhow!("a regex saved search runs on its own; try `ron search --regex` with the pattern"));
+            }
+            parts.push(saved.query.clone());
+            field = saved.field.clone();
+            regex = true;
+        }
+        let q = parts.join(" ");
+        if let Some(name) = save {
+            client::save_search(name, &q, &field, ignore_case, whole_word, regex)?;
+            eprintln!("saved as @{name}");
+        }
+        let found = client::search_notes(&q, &field, ignore_case, whole_word, regex, fuzzy)?;
+        print_hit_table(&found.hits, std::io::stdout().is_terminal());
+        if let Some(better) = &found.did_you_mean {
+            println!("did you mean: {better}");
+        }
+        if found.hits.is_empty() && !regex {
+            let s = client::suggest(&q, &field)?;
+            if !s.notes.is_empty() {
+                println!("close matches (`ron search --fuzzy` lists them):");
+                for n in &s.notes {
+                    println!("  {:<24}  {}  ({})", n.id, n.title, n.notebook);
+                }
+            }
         }
-        print_note_table(&results.unwrap_or_default());
         Ok(())
     }
 
@@ -627,19 +1116,57 @@ mod notes_cmd {
         Ok(())
     }
 
+    pub fn move_notes(targets: &[String], notebook: &str) -> Result<()> {
+        let ids = targets.iter().map(|t| resolve_target(t)).collect::<Result<Vec<_>>>()?;
+        let r = client::move_notes(&ids, notebook)?;
+        println!("moved {} note(s) to {notebook}", r.notes.len());
+        Ok(())
+    }
+
     pub fn relate(id: &str, to: Vec<String>) -> Result<()> {
         let note = client::get_note(id)?;
-        let mut related = note.related.clone();
-        for t in &to {
-            if !related.contains(t) {
-                related.push(t.clone());
+        let updated = update_retrying(note, |note| {
+            let mut related = note.related.clone();
+            for t in &to {
+                if !related.contains(t) {
+                    related.push(t.clone());
+                }
             }
-        }
-        let updated = client::update_note(id, None, None, None, None, Some(related))?;
+            client::update_note(id, None, None, None, None, Some(related), Some(note.updated))
+        })?;
         println!("related on {}: {:?}", updated.id, updated.related);
         Ok(())
     }
 
+    /// Upload `file` to the server's `resources/` and append its markdown
+    /// link to the note body.
+    pub fn attach(target: String, file: &std::path::Path) -> Result<()> {
+        let id = resolve_target(&target)?;
+        let note = client::get_note(&id)?;
+        let up = client::upload_resource(file)?;
+        update_retrying(note, |note| {
+            let body = append_link(&note.body, &up.markdown);
+            client::update_note(&id, None, None, None, Some(body), None, Some(note.updated))
+        })?;
+        println!(
+            "attached {} to {id} as {}{}",
+            file.display(),
+            up.link,
+            if up.existed { " (already stored)" } else { "" }
+        );
+        Ok(())
+    }
+
+    /// `body` with `link` on its own paragraph at the end.
+    pub(crate) fn append_link(body: &str, link: &str) -> String {
+        let body = body.trim_end();
+        if body.is_empty() {
+            format!("{link}\n")
+        } else {
+            format!("{body}\n\n{link}\n")
+        }
+    }
+
     // ---- helpers ----
 
     struct ParsedNote {
@@ -678,7 +1205,7 @@ mod notes_cmd {
             }
             body_lines.push(line);
         }
-        if body_lines.first().map_or(false, |l| l.is_empty()) {
+        if body_lines.first().is_some_and(|l| l.is_empty()) {
             body_lines.remove(0);
         }
         Ok(ParsedNote {
@@ -723,7 +1250,7 @@ mod notes_cmd {
         // Watermark: a local copy at or below the consumed timestamp was
         // already saved as a note (possibly from another machine) — drop it.
         if let Some(wm) = info.consumed_updated {
-            if local.as_ref().map_or(false, |l| l.saved_at <= wm) {
+            if local.as_ref().is_some_and(|l| l.saved_at <= wm) {
                 let _ = client::drop_local_draft(&local_path, key);
                 local = None;
             }
@@ -733,7 +1260,7 @@ mod notes_cmd {
         // goes up so other devices can see it.
         let mut server_draft = info.draft;
         if let Some(l) = &local {
-            let newer = server_draft.as_ref().map_or(true, |d| l.saved_at > d.updated);
+            let newer = server_draft.as_ref().is_none_or(|d| l.saved_at > d.updated);
             if newer {
                 if let Ok(parsed) = parse_editor_buffer(&l.content) {
                     let content = DraftContent {
@@ -901,16 +1428,20 @@ mod notes_cmd {
         line.strip_prefix(prefix).unwrap_or(line)
     }
 
-    fn split_scope(pattern: &str) -> (&'static str, &str) {
-        let Some(rest) = pattern.split_once(':') else {
-            return ("default", pattern);
-        };
-        match rest.0 {
-            "t" => ("title", rest.1),
-            "g" => ("tags", rest.1),
-            "n" => ("notebook", rest.1),
-            "a" => ("content", rest.1),
-            _ => ("default", pattern),
+    /// One shell argument as a query operand: an argument with spaces is
+    /// grouped, keeping a leading scope on the whole group (`'t:foo bar'`
+    /// -> `t:(foo bar)`), so each argument still narrows the result.
+    fn query_arg(pattern: &str) -> String {
+        if !pattern.trim().contains(char::is_whitespace) {
+            return pattern.to_string();
+        }
+        match pattern.split_once(':') {
+            Some((scope, rest))
+                if matches!(scope, "t" | "title" | "g" | "tag" | "tags" | "n" | "notebook" | "a" | "content") =>
+            {
+                format!("{scope}:({rest})")
+            }
+            _ => format!("({pattern})"),
         }
     }
 
@@ -918,14 +1449,16 @@ mod notes_cmd {
     /// last `list`/`search` result. P3 only resolves indices via a fresh
     /// `list_notes(50)` call; the old pickle cache is gone. A draft key
     /// (`note:<id>`, as printed by `ron draft list`) is accepted as its ID.
+    /// Anything else is taken as a note title (ignoring case), with
+    /// "did you mean" suggestions when no note has it.
     fn resolve_target(target: &str) -> Result<String> {
         let target = target.strip_prefix("note:").unwrap_or(target);
         if target.contains('-') {
             return Ok(target.to_string());
         }
-        let idx: usize = target
-            .parse()
-            .map_err(|_| anyhow!("invalid target {target:?}; pass a note ID or 1-based index"))?;
+        let Ok(idx) = target.parse::<usize>() else {
+            return resolve_title(target);
+        };
         if idx == 0 {
             return Err(anyhow!("indices are 1-based"));
         }
@@ -936,25 +1469,102 @@ mod notes_cmd {
             .ok_or_else(|| anyhow!("no note at index {idx}"))
     }
 
+    fn resolve_title(title: &str) -> Result<String> {
+        let phrase = format!("\"{}\"", title.replace('"', " "));
+        let exact: Vec<Note> = client::search_notes(&phrase, "title", true, false, false, false)?
+            .hits
+            .into_iter()
+            .map(|h| h.note)
+            .filter(|n| n.title.trim().to_lowercase() == title.trim().to_lowercase())
+            .collect();
+        match exact.as_slice() {
+            [n] => Ok(n.id.clone()),
+            [] => Err(anyhow!(no_title_message(title, &client::suggest(title, "title")?))),
+            many => Err(anyhow!(
+                "{} notes are titled {title:?}; pass one of their IDs: {}",
+                many.len(),
+                many.iter().map(|n| n.id.as_str()).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+
+    fn no_title_message(title: &str, s: &client::Suggestions) -> String {
+        let mut msg = format!("no note with ID, index or title {title:?}");
+        if !s.notes.is_empty() {
+            msg.push_str("; did you mean:");
+            for n in &s.notes {
+                msg.push_str(&format!("\n  {}  {}", n.id, n.title));
+            }
+        }
+        msg
+    }
+
     pub fn print_note_table(notes: &[Note]) {
-        if notes.is_empty() {
+        let hits: Vec<SearchHit> = notes.iter().cloned().map(SearchHit::from).collect();
+        print_hit_table(&hits, false)
+    }
+
+    /// The note table for search results: each row followed by its body
+    /// snippets, and with `color` the hits in bold red.
+    pub fn print_hit_table(hits: &[SearchHit], color: bool) {
+        if hits.is_empty() {
             println!("(no notes)");
             return;
         }
         // Columns: idx, updated, ID, notebook, title, tags
-        println!("{:>2}  {:<10}  {:<24}  {:<12}  {:<30}  {}", "#", "updated", "id", "notebook", "title", "tags");
-        for (i, n) in notes.iter().enumerate() {
-            let tags = n.tags.join(";");
+        println!("{:>2}  {:<10}  {:<24}  {:<12}  {:<30}  tags", "#", "updated", "id", "notebook", "title");
+        for (i, hit) in hits.iter().enumerate() {
+            let n = &hit.note;
+            let marks = |part: &str| match (color, hit.snippet(part)) {
+                (true, Some(s)) => s.matches.as_slice(),
+                _ => &[],
+            };
+            // The tags snippet joins them with "; ", the table with ";".
+            let mut tag_marks = Vec::new();
+            let mut at = 0;
+            for (t, own) in n.tags.iter().zip(hit.tag_marks()) {
+                tag_marks.extend(own.into_iter().filter(|_| color).map(|(s, e)| (at + s, at + e)));
+                at += t.len() + 1;
+            }
             println!(
-                "{:>2}  {:<10}  {:<24}  {:<12}  {:<30}  {}",
+                "{:>2}  {:<10}  {:<24}  {}  {}  {}",
                 i + 1,
                 n.updated.format("%Y-%m-%d").to_string(),
                 n.id,
-                truncate(&n.notebook, 12),
-                truncate(&n.title, 30),
-                tags,
+                marked_cell(&n.notebook, 12, marks("notebook")),
+                marked_cell(&n.title, 30, marks("title")),
+                marked_cell(&n.tags.join(";"), 0, &tag_marks),
             );
+            for s in hit.snippets.iter().filter(|s| s.field == "body") {
+                println!("    {}", marked_cell(&s.text, 0, if color { &s.matches } else { &[] }));
+            }
+        }
+    }
+
+    /// `text` cut and left-aligned to `width` chars (0: as is), with the
+    /// `[start, end)` byte ranges `marks` in ANSI bold red. Padding is
+    /// counted on the visible text, since the escape codes would throw
+    /// `{:<width}` off.
+    pub fn marked_cell(text: &str, width: usize, marks: &[(usize, usize)]) -> String {
+        let shown = if width == 0 { text.to_string() } else { truncate(text, width) };
+        // A truncated cell ends in "…"; hits past the cut aren't shown.
+        let kept = if shown == text { shown.len() } else { shown.len() - '…'.len_utf8() };
+        let pad = " ".repeat(width.saturating_sub(shown.chars().count()));
+        let mut out = String::with_capacity(shown.len() + pad.len());
+        let mut last = 0;
+        for &(start, end) in marks {
+            let end = end.min(kept);
+            if start < last || start >= end || !shown.is_char_boundary(start) || !shown.is_char_boundary(end) {
+                continue;
+            }
+            out.push_str(&shown[last..start]);
+            out.push_str("\x1b[1;31m");
+            out.push_str(&shown[start..end]);
+            out.push_str("\x1b[0m");
+            last = end;
         }
+        out.push_str(&shown[last..]);
+        out + &pad
     }
 
     pub fn truncate(s: &str, n: usize) -> String {
@@ -981,6 +1591,23 @@ mod notes_cmd {
             }
         }
 
+        #[test]
+        fn query_arg_groups_arguments_with_spaces() {
+            assert_eq!(query_arg("rust"), "rust");
+            assert_eq!(query_arg("t:foo bar"), "t:(foo bar)");
+            assert_eq!(query_arg("rust OR go"), "(rust OR go)");
+            assert_eq!(query_arg("x:y z"), "(x:y z)");
+        }
+
+        #[test]
+        fn marked_cell_pads_the_visible_text() {
+            assert_eq!(marked_cell("foo", 5, &[]), "foo  ");
+            assert_eq!(marked_cell("foo", 5, &[(1, 3)]), "f\x1b[1;31moo\x1b[0m  ");
+            assert_eq!(marked_cell("bar", 0, &[(9, 12)]), "bar");
+            // Cut to "ab…": the hit is clipped at the cut.
+            assert_eq!(marked_cell("abcdef", 3, &[(1, 4)]), "a\x1b[1;31mb\x1b[0m…");
+        }
+
         #[test]
         fn draft_buffer_round_trip() {
             let buffer = draft_to_buffer(&content());
@@ -1023,6 +1650,74 @@ mod notes_cmd {
             assert_eq!(resolve_target("note-1").unwrap(), "note-1");
         }
 
+        #[test]
+        fn concurrent_edits_merge_per_line() {
+            let at = chrono::NaiveDate::from_ymd_opt(2026, 8, 6).unwrap().and_hms_opt(12, 0, 0).unwrap();
+            let base = Note::new("n1".into(), "Title".into(), vec!["a".into()], "nb".into(), at, at, "one\ntwo\n".into());
+            let mut theirs = base.clone();
+            theirs.tags.push("b".into());
+            let mut mine = parse_editor_buffer(&note_buffer(&base)).unwrap();
+            mine.body = "one\ntwo\nthree\n".into();
+            let labels = ["your edit", "as you opened it", "server"];
+            let (merged, n) =
+                ron::git::merge_text(&note_buffer(&base), &parsed_buffer(&mine), &note_buffer(&theirs), labels).unwrap();
+            assert_eq!(n, 0);
+            assert!(!has_conflict_markers(&merged));
+            let p = parse_editor_buffer(&merged).unwrap();
+            assert_eq!(p.tags, vec!["a".to_string(), "b".to_string()]);
+            assert_eq!(p.body, "one\ntwo\nthree");
+
+            theirs.title = "Server title".into();
+            mine.title = "My title".into();
+            let (merged, n) =
+                ron::git::merge_text(&note_buffer(&base), &parsed_buffer(&mine), &note_buffer(&theirs), labels).unwrap();
+            assert_eq!(n, 1);
+            assert!(merged.starts_with("<<<<<<< your edit\nTitle: My title\n"));
+            assert!(has_conflict_markers(&merged));
+            // Markdown that merely looks like a marker isn't a conflict.
+            assert!(!has_conflict_markers("Heading\n=======\n\n>>>>>>> quoted\n"));
+            assert!(!has_conflict_markers("<<<<<<< a\nmine\n=======\ntheirs\n"));
+        }
+
+        #[test]
+        fn conflicting_updates_are_redone_on_the_server_copy() {
+            let at = chrono::NaiveDate::from_ymd_opt(2026, 8, 6).unwrap().and_hms_opt(12, 0, 0).unwrap();
+            let stale = Note::new("n1".into(), "T".into(), vec![], "nb".into(), at, at, "one\n".into());
+            let mut current = stale.clone();
+            current.body = "one\ntwo\n".into();
+            current.updated = at + chrono::Duration::seconds(1);
+            let sends = std::cell::Cell::new(0);
+            let saved = update_retrying(stale.clone(), |n| {
+                sends.set(sends.get() + 1);
+                if n.updated < current.updated {
+                    return Err(client::Conflict { current: current.clone() }.into());
+                }
+                let mut n = n.clone();
+                n.body = append_link(&n.body, "[x](resources/x)");
+                Ok(n)
+            })
+            .unwrap();
+            assert_eq!((sends.get(), saved.body.as_str()), (2, "one\ntwo\n\n[x](resources/x)\n"));
+
+            let always = |n: &Note| Err(client::Conflict { current: n.clone() }.into());
+            assert!(update_retrying(stale.clone(), always).is_err());
+            assert_eq!(update_retrying(stale, |_| Err(anyhow!("offline"))).unwrap_err().to_string(), "offline");
+        }
+
+        #[test]
+        fn unknown_title_lists_close_notes() {
+            let none = client::Suggestions { query: None, notes: vec![] };
+            assert_eq!(no_title_message("Powr", &none), "no note with ID, index or title \"Powr\"");
+            let some = client::Suggestions {
+                query: Some("power".into()),
+                notes: vec![client::Suggestion { id: "note-1".into(), title: "Power".into(), notebook: "nb".into() }],
+            };
+            assert_eq!(
+                no_title_message("Powr", &some),
+                "no note with ID, index or title \"Powr\"; did you mean:\n  note-1  Power"
+            );
+        }
+
         #[test]
         fn resume_command_matches_key_shape() {
             assert_eq!(resume_command("new"), "ron add");
@@ -1030,6 +1725,28 @@ mod notes_cmd {
             // Recovery hints render the same command backticked.
             assert_eq!(recover_hint("note:note-1"), "`ron edit note-1`");
         }
+
+        #[test]
+        fn wiki_links_show_resolved_titles() {
+            let links = vec![
+                client::ResolvedLink {
+                    target: "note-20260101-0000-abc".into(),
+                    id: Some("note-20260101-0000-abc".into()),
+                    title: Some("Home".into()),
+                },
+                client::ResolvedLink { target: "Nope".into(), id: None, title: None },
+            ];
+            assert_eq!(
+                resolve_wiki_text("[[note-20260101-0000-abc]] / [[ Nope ]]", &links),
+                "[Home](note-20260101-0000-abc) / [[ Nope ]]"
+            );
+        }
+
+        #[test]
+        fn attached_link_gets_its_own_paragraph() {
+            assert_eq!(append_link("", "![x](resources/a.png)"), "![x](resources/a.png)\n");
+            assert_eq!(append_link("text\n\n", "[f](resources/b)"), "text\n\n[f](resources/b)\n");
+        }
     }
 }
 
@@ -1090,8 +1807,8 @@ mod drafts_cmd {
             return Ok(());
         }
         println!(
-            "{:<28}  {:<19}  {:<13}  {:<24}  {}",
-            "key", "saved", "where", "title", "resume"
+            "{:<28}  {:<19}  {:<13}  {:<24}  resume",
+            "key", "saved", "where", "title"
         );
         for k in &keys {
             let sd = server.iter().find(|d| &d.key == k);
@@ -1186,7 +1903,7 @@ mod pulses_cmd {
             println!("(no pulses)");
             return Ok(());
         }
-        println!("{:<24}  {:<8}  {:<6}  {}", "id", "interval", "today", "topic");
+        println!("{:<24}  {:<8}  {:<6}  topic", "id", "interval", "today");
         for p in &pulses {
             let today = p.interval.current_slot(chrono::Local::now().naive_local());
             let state = if p.get_slot(&today).unwrap_or(false) { "✓" } else { " " };
@@ -1198,7 +1915,7 @@ mod pulses_cmd {
     pub fn delete(sub: &clap::ArgMatches) -> Result<()> {
         let id = sub.get_one::<String>("id").unwrap().clone();
         client::delete_pulse(&id)?;
-        println!("deleted {id}");
+        println!("moved {id} to the trash (`ron trash restore {id}` to undo)");
         Ok(())
     }
 
@@ -1266,7 +1983,7 @@ mod metrics_cmd {
             println!("(no metrics)");
             return Ok(());
         }
-        println!("{:<24}  {:<10}  {}", "id", "points", "topic");
+        println!("{:<24}  {:<10}  topic", "id", "points");
         for m in metrics {
             println!("{:<24}  {:<10}  {}", m.id, m.points.len(), m.topic);
         }
@@ -1276,7 +1993,7 @@ mod metrics_cmd {
     pub fn delete(sub: &clap::ArgMatches) -> Result<()> {
         let id = sub.get_one::<String>("id").unwrap().clone();
         client::delete_metric(&id)?;
-        println!("deleted {id}");
+        println!("moved {id} to the trash (`ron trash restore {id}` to undo)");
         Ok(())
     }
 
@@ -1292,6 +2009,193 @@ mod metrics_cmd {
     }
 }
 
+// ----- trash -----
+
+mod trash_cmd {
+    use super::*;
+    use ron::client;
+    use ron::yaml::Item;
+
+    pub fn list() -> Result<()> {
+        let entries = client::list_trash()?;
+        if entries.is_empty() {
+            println!("(trash is empty)");
+            return Ok(());
+        }
+        println!("{:<28}  {:<6}  {:<16}  title", "id", "type", "deleted");
+        for e in &entries {
+            println!(
+                "{:<28}  {:<6}  {:<16}  {}",
+                e.id,
+                e.kind,
+                e.deleted_at.format("%Y-%m-%d %H:%M"),
+                e.title
+            );
+        }
+        Ok(())
+    }
+
+    pub fn restore(id: &str) -> Result<()> {
+        let label = match client::restore_from_trash(id)? {
+            Item::Note(n) => n.title,
+            Item::Pulse(p) => p.topic,
+            Item::Metric(m) => m.topic,
+        };
+        println!("restored {id} ({label})");
+        Ok(())
+    }
+
+    pub fn empty() -> Result<()> {
+        let r = client::empty_trash()?;
+        println!("purged {} item(s)", r.purged.len());
+        Ok(())
+    }
+}
+
+// ----- notebooks -----
+
+mod notebooks_cmd {
+    use super::*;
+    use ron::client;
+
+    pub fn list(tree: bool) -> Result<()> {
+        if tree {
+            print_tree(&client::notebook_tree()?, 0);
+            return Ok(());
+        }
+        let nbs = client::notebook_counts()?;
+        let width = nbs.iter().map(|n| n.notebook.chars().count()).max().unwrap_or(0);
+        for n in &nbs {
+            println!("{:<width$}  {}", n.notebook, n.count);
+        }
+        Ok(())
+    }
+
+    /// One line per level, indented by depth: `name  total`.
+    pub fn print_tree(nodes: &[client::TreeNode], depth: usize) {
+        for n in nodes {
+            println!("{:indent$}{}  {}", "", n.name, n.total, indent = depth * 2);
+            print_tree(&n.children, depth + 1);
+        }
+    }
+
+    pub fn rename(old: &str, new: &str) -> Result<()> {
+        let r = client::rename_notebook(old, new)?;
+        println!("renamed {old} -> {new} ({} note(s))", r.notes.len());
+        Ok(())
+    }
+
+    pub fn merge(from: &str, into: &str) -> Result<()> {
+        let r = client::merge_notebooks(from, into)?;
+        println!("merged {from} into {into} ({} note(s))", r.notes.len());
+        Ok(())
+    }
+}
+
+// ----- saved searches -----
+
+mod searches_cmd {
+    use super::*;
+    use ron::client;
+
+    pub fn list() -> Result<()> {
+        let searches = client::list_searches()?;
+        if searches.is_empty() {
+            println!("(no saved searches)");
+            return Ok(());
+        }
+        let width = searches.iter().map(|s| s.name.chars().count() + 1).max().unwrap_or(0);
+        for s in &searches {
+            let scope = if s.field == "content" { String::new() } else { format!("  [{}]", s.field) };
+            println!("{:<width$}  {}{scope}", format!("@{}", s.name), s.query);
+        }
+        Ok(())
+    }
+
+    pub fn delete(name: &str) -> Result<()> {
+        let name = name.strip_prefix('@').unwrap_or(name);
+        client::delete_search(name)?;
+        println!("deleted @{name}");
+        Ok(())
+    }
+}
+
+// ----- tags -----
+
+mod tags_cmd {
+    use super::*;
+    use ron::client;
+
+    pub fn list(tree: bool) -> Result<()> {
+        if tree {
+            notebooks_cmd::print_tree(&client::tag_tree()?, 0);
+            return Ok(());
+        }
+        let tags = client::list_tags()?;
+        if tags.is_empty() {
+            println!("(no tags)");
+            return Ok(());
+        }
+        let width = tags.iter().map(|t| t.tag.chars().count()).max().unwrap_or(0);
+        for t in &tags {
+            println!("{:<width$}  {}", t.tag, t.count);
+        }
+        Ok(())
+    }
+
+    pub fn rename(old: &str, new: &str) -> Result<()> {
+        let r = client::rename_tag(old, new)?;
+        println!("renamed {old} -> {new} on {} note(s)", r.notes.len());
+        Ok(())
+    }
+
+    pub fn merge(from: Vec<String>, into: &str) -> Result<()> {
+        let r = client::merge_tags(&from, into)?;
+        println!("merged {} into {into} on {} note(s)", from.join(", "), r.notes.len());
+        Ok(())
+    }
+
+    pub fn delete(tag: &str) -> Result<()> {
+        let r = client::delete_tag(tag)?;
+        println!("removed {tag} from {} note(s)", r.notes.len());
+        Ok(())
+    }
+}
+
+// ----- resources -----
+
+mod resources_cmd {
+    use super::*;
+    use ron::client;
+
+    pub fn check(delete_orphans: bool) -> Result<()> {
+        let audit = client::audit_resources()?;
+        if audit.broken.is_empty() {
+            println!("no broken links");
+        } else {
+            println!("missing files ({}):", audit.broken.len());
+            for b in &audit.broken {
+                println!("  resources/{}  <- {}", b.name, b.notes.join(", "));
+            }
+        }
+        if audit.orphans.is_empty() {
+            println!("no unreferenced files");
+            return Ok(());
+        }
+        println!("unreferenced files ({}):", audit.orphans.len());
+        for f in &audit.orphans {
+            println!("  resources/{}  ({} bytes)", f.name, f.size);
+        }
+        if delete_orphans {
+            let r = client::delete_orphan_resources()?;
+            println!("deleted {} file(s)", r.deleted.len());
+        } else {
+            println!("(`ron resources check --delete-orphans` to remove them)");
+        }
+        Ok(())
+    }
+}
+
 // ----- admin commands -----
 
 mod admin_cmd {
@@ -1307,31 +2211,61 @@ mod admin_cmd {
         Ok(())
     }
 
-    pub fn import() -> Result<()> {
-        let r = client::import()?;
-        println!("imported {} items", r.items);
+    pub fn import(full: bool) -> Result<()> {
+        let r = client::import(full)?;
+        if r.full {
+            println!("imported {} items (full reload)", r.items);
+        } else {
+            println!("imported {} changed items, removed {}", r.items, r.removed);
+        }
         Ok(())
     }
 
-    pub fn backup(dry_run: bool) -> Result<()> {
-        let r = client::backup(dry_run)?;
+    pub fn backup(dry_run: bool, to: Option<&str>) -> Result<()> {
+        let r = client::backup(dry_run, to)?;
         if let Some(st) = &r.status {
-            print_backup_status(st);
+            print_backup_status(st, &r.remote, &r.branch);
         } else {
-            println!("pushed");
+            println!("pushed to {}/{}", r.remote, r.branch);
         }
         Ok(())
     }
 
+    pub fn remote_add(name: &str, url: &str) -> Result<()> {
+        let r = client::add_remote(name, url)?;
+        println!("added {} -> {}", r.name, r.url);
+        Ok(())
+    }
+
+    pub fn remote_list() -> Result<()> {
+        let remotes = client::list_remotes()?;
+        if remotes.is_empty() {
+            println!("(no remotes; add one with `ron remote add <name> <url>`)");
+            return Ok(());
+        }
+        let width = remotes.iter().map(|r| r.name.chars().count()).max().unwrap_or(0);
+        for r in &remotes {
+            let mark = if r.default { '*' } else { ' ' };
+            println!("{mark} {:<width$}  {}", r.name, r.url);
+        }
+        Ok(())
+    }
+
+    pub fn remote_remove(name: &str) -> Result<()> {
+        client::remove_remote(name)?;
+        println!("removed {name}");
+        Ok(())
+    }
+
     /// Human rendering of the `--dry-run` status report, with hints on
     /// what to do next (backup / sync / manual divergence recovery).
-    fn print_backup_status(st: &client::BackupStatus) {
+    fn print_backup_status(st: &client::BackupStatus, remote: &str, branch: &str) {
         let Some(url) = &st.remote_url else {
             println!("no remote configured; add one to enable backup/sync:");
-            println!("  git -C ~/.local/share/ron/repo remote add origin <url>");
+            println!("  ron remote add {remote} <url>");
             return;
         };
-        println!("remote: {url} (origin/master)");
+        println!("remote: {url} ({remote}/{branch})");
         if st.fetched {
             println!("fetch: ok");
         } else {
@@ -1358,14 +2292,10 @@ mod admin_cmd {
         }
         if st.ahead > 0 && st.behind > 0 {
             println!(
-                "warning: local and origin/master have diverged (ahead {}, behind {})",
+                "warning: local and {remote}/{branch} have diverged (ahead {}, behind {})",
                 st.ahead, st.behind
             );
-            println!("  `ron sync` will fail (--ff-only); resolve manually, then reload:");
-            println!("    1. git -C ~/.local/share/ron/repo pull --rebase origin master");
-            println!("       (resolve YAML conflicts: git add <file> && git rebase --continue)");
-            println!("    2. ron import     # rebuild SQLite from the reconciled YAML");
-            println!("    3. ron backup     # push the reconciled history");
+            println!("hint: run `ron sync` to merge them, then `ron backup` to push the merge");
         } else if st.behind > 0 {
             println!("hint: run `ron sync` to pull");
         } else if st.ahead > 0 {
@@ -1375,8 +2305,8 @@ mod admin_cmd {
         }
     }
 
-    pub fn sync() -> Result<()> {
-        let r = client::sync()?;
+    pub fn sync(ff_only: bool) -> Result<()> {
+        let r = client::sync(ff_only)?;
         if r.changed_files.is_empty() {
             println!("up to date");
         } else {
@@ -1385,7 +2315,31 @@ mod admin_cmd {
                 println!("  {f}");
             }
         }
-        println!("loaded {} items", r.items_loaded);
+        if r.merged {
+            println!("merged local and remote changes (run `ron backup` to push the merge)");
+        }
+        for c in &r.conflicts {
+            println!("conflict: {} {}: kept {}", c.file, c.field, c.kept);
+        }
+        println!("loaded {} items, removed {}", r.items_loaded, r.items_removed);
+        Ok(())
+    }
+
+    pub fn upgrade_format(dry_run: bool) -> Result<()> {
+        let r = client::upgrade_format(dry_run)?;
+        let verb = if r.dry_run { "would upgrade" } else { "upgraded" };
+        for f in &r.upgraded {
+            println!("{verb} {f}");
+        }
+        for f in &r.failed {
+            eprintln!("failed: {f}");
+        }
+        println!(
+            "{} file(s) {verb} to format v{} (committed={})",
+            r.upgraded.len(),
+            r.format_version,
+            r.committed
+        );
         Ok(())
     }
 
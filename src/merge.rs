@@ -0,0 +1,547 @@
+//! Item-aware three-way merge, for `sync` when this repo and the remote
+//! both committed since they last met.
+//!
+//! Git merges the paths only one side touched. Files changed on both sides
+//! are merged here, as items rather than text:
+//! - notes: each field takes the side that changed it; when both did, the
+//!   copy with the newer `updated` wins (the body is line-merged first and
+//!   only counts as a conflict if that fails);
+//! - pulses and metrics: slots / points are unioned, with removals on one
+//!   side honoured; scalar fields changed on both sides keep the local one.
+//!
+//! Every field decided by picking a side is reported as a [`Conflict`]; the
+//! losing value stays in the merged-in commit's history.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::git;
+use crate::models::{Metric, MetricPoint, Note, Pulse, PulseSlot};
+use crate::yaml::{self, Item};
+
+/// Conflict-marker labels for line merges: ours, base, theirs.
+const LABELS: [&str; 3] = ["local", "base", "remote"];
+
+/// Which side of a merge a value came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Local,
+    Remote,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Side::Local => "local",
+            Side::Remote => "remote",
+        })
+    }
+}
+
+/// A field both sides changed differently, settled by keeping one side.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Conflict {
+    /// Repo-relative path of the file.
+    pub file: String,
+    /// Field name (`title`, `slots[2026-08-06]`, ...); `deleted` when one
+    /// side deleted the file and the other changed it, `file` when the
+    /// whole file was kept as-is.
+    pub field: String,
+    pub kept: Side,
+}
+
+/// A merged file: its text (`None` when it's deleted) and what conflicted.
+#[derive(Debug)]
+pub struct Merged {
+    pub text: Option<String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Three-way merge one file's `base`, local (`ours`) and remote (`theirs`)
+/// contents (`None` = absent on that side). Item YAML is merged field by
+/// field; anything else (or YAML that doesn't parse) is line-merged, keeping
+/// the local file when that conflicts.
+pub fn merge_file(path: &str, base: Option<&str>, ours: Option<&str>, theirs: Option<&str>) -> Result<Merged> {
+    let mut fields = Fields { file: path, conflicts: Vec::new() };
+    let text = match (ours, theirs) {
+        _ if ours == theirs || theirs == base => ours.map(str::to_string),
+        _ if ours == base => theirs.map(str::to_string),
+        // Deleted on one side, changed on the other: keep the change.
+        (None, Some(t)) => {
+            fields.keep("deleted", Side::Remote);
+            Some(t.to_string())
+        }
+        (Some(o), None) => {
+            fields.keep("deleted", Side::Local);
+            Some(o.to_string())
+        }
+        (Some(o), Some(t)) => {
+            let items = yaml::parse(o).and_then(|o| Ok((o, yaml::parse(t)?)));
+            match items {
+                Ok((o_item, t_item)) => {
+                    let b_item = base.and_then(|b| yaml::parse(b).ok());
+                    match fields.item(b_item, o_item, t_item)? {
+                        Some(item) => Some(yaml::serialize_item(&item)?.1),
+                        None => Some(fields.text(base, o, t)?),
+                    }
+                }
+                Err(_) => Some(fields.text(base, o, t)?),
+            }
+        }
+        (None, None) => None,
+    };
+    Ok(Merged { text, conflicts: fields.conflicts })
+}
+
+/// Git merge driver (`merge.ron.driver`, registered by `git::ensure_repo`):
+/// merge the `base`, `current` and `other` files git hands over for `path`
+/// and write the result over `current`. An empty `base` (both sides added
+/// the file) counts as absent. The merge is clean unless a whole file had to
+/// be kept as-is (a `file` conflict), which git should flag for the user.
+pub fn merge_driver(base: &Path, current: &Path, other: &Path, path: &str) -> Result<Merged> {
+    let read = |p: &Path| std::fs::read_to_string(p).with_context(|| format!("reading {}", p.display()));
+    let base = read(base)?;
+    let merged = merge_file(
+        path,
+        Some(base.as_str()).filter(|b| !b.is_empty()),
+        Some(&read(current)?),
+        Some(&read(other)?),
+    )?;
+    if let Some(text) = &merged.text {
+        std::fs::write(current, text).with_context(|| format!("writing {}", current.display()))?;
+    }
+    Ok(merged)
+}
+
+/// What a [`sync`] did.
+#[derive(Debug, Default)]
+pub struct SyncOutcome {
+    /// Repo-relative paths the sync changed locally.
+    pub changed: Vec<String>,
+    /// Whether a merge commit was made (the histories had diverged).
+    pub merged: bool,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Bring `<remote>/<branch>` into the checked-out branch: fast-forward when
+/// possible, otherwise merge (per [`merge_file`]) and commit the merge. With
+/// `ff_only`, diverged histories are an error instead.
+pub fn sync(repo: &Path, remote: &str, branch: &str, ff_only: bool) -> Result<SyncOutcome> {
+    git::fetch_branch(repo, remote, branch)?;
+    let tracking = format!("{remote}/{branch}");
+    let theirs = git::resolve(repo, &tracking).with_context(|| format!("{tracking} not found"))?;
+    let head = git::resolve(repo, "HEAD");
+    let mut outcome = SyncOutcome::default();
+    let Some(head) = head else {
+        // Unborn branch: everything comes from the remote.
+        git::fast_forward(repo, &theirs)?;
+        outcome.changed = git::head_files(repo)?
+            .iter()
+            .filter_map(|p| p.strip_prefix(repo).ok())
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+        return Ok(outcome);
+    };
+    let base = git::merge_base(repo, &head, &theirs)
+        .with_context(|| format!("local history and {tracking} have nothing in common"))?;
+    if base == theirs {
+        // Up to date, or only ahead.
+        return Ok(outcome);
+    }
+    if base == head {
+        git::fast_forward(repo, &theirs)?;
+    } else if ff_only {
+        anyhow::bail!("local and {tracking} have diverged; sync without --ff-only to merge them");
+    } else {
+        git::begin_merge(repo, &theirs)?;
+        match merge_diverged(repo, &base, &head, &theirs, &tracking) {
+            Ok(conflicts) => outcome.conflicts = conflicts,
+            Err(e) => {
+                git::abort_merge(repo);
+                return Err(e);
+            }
+        }
+        outcome.merged = true;
+    }
+    outcome.changed = git::changed_paths(repo, &head, "HEAD")?;
+    Ok(outcome)
+}
+
+/// Settle the YAML files both sides changed since `base`, keep the local
+/// copy of anything else git couldn't merge, and commit.
+fn merge_diverged(repo: &Path, base: &str, head: &str, theirs: &str, tracking: &str) -> Result<Vec<Conflict>> {
+    let remote_changed: HashSet<String> = git::changed_paths(repo, base, theirs)?.into_iter().collect();
+    let both: Vec<String> = git::changed_paths(repo, base, head)?
+        .into_iter()
+        .filter(|p| remote_changed.contains(p) && p.ends_with(".yaml"))
+        .collect();
+    let mut conflicts = Vec::new();
+    for path in &both {
+        let show = |rev: &str| git::show_file(repo, rev, path);
+        let merged = merge_file(path, show(base)?.as_deref(), show(head)?.as_deref(), show(theirs)?.as_deref())?;
+        let file = repo.join(path);
+        match &merged.text {
+            Some(text) => {
+                if let Some(dir) = file.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(&file, text).with_context(|| format!("writing {path}"))?;
+            }
+            None => {
+                let _ = std::fs::remove_file(&file);
+            }
+        }
+        conflicts.extend(merged.conflicts);
+    }
+    git::stage(repo, &both)?;
+    // Non-YAML files (attachments, .gitignore) git couldn't merge keep
+    // their local version, absent or not.
+    let rest = git::unmerged_paths(repo)?;
+    for path in &rest {
+        if git::show_file(repo, head, path)?.is_some() {
+            git::checkout_file(repo, head, path)?;
+        } else {
+            let _ = std::fs::remove_file(repo.join(path));
+        }
+    }
+    git::stage(repo, &rest)?;
+    conflicts.extend(rest.into_iter().map(|file| Conflict { file, field: "file".into(), kept: Side::Local }));
+    git::commit_merge(repo, &format!("sync: merge {tracking}"))?;
+    Ok(conflicts)
+}
+
+/// Field-by-field merge state for one file.
+struct Fields<'a> {
+    file: &'a str,
+    conflicts: Vec<Conflict>,
+}
+
+impl Fields<'_> {
+    fn keep(&mut self, field: &str, kept: Side) {
+        self.conflicts.push(Conflict { file: self.file.to_string(), field: field.to_string(), kept });
+    }
+
+    /// The side that changed `field` from `base`; `winner`'s when both did.
+    fn pick<T: PartialEq + Clone>(&mut self, field: &str, base: Option<&T>, ours: &T, theirs: &T, winner: Side) -> T {
+        if ours == theirs || base == Some(theirs) {
+            ours.clone()
+        } else if base == Some(ours) {
+            theirs.clone()
+        } else {
+            self.keep(field, winner);
+            match winner {
+                Side::Local => ours.clone(),
+                Side::Remote => theirs.clone(),
+            }
+        }
+    }
+
+    /// Line merge; the local text when that conflicts.
+    fn text(&mut self, base: Option<&str>, ours: &str, theirs: &str) -> Result<String> {
+        let (merged, n) = git::merge_text(base.unwrap_or(""), ours, theirs, LABELS)?;
+        if n == 0 {
+            return Ok(merged);
+        }
+        self.keep("file", Side::Local);
+        Ok(ours.to_string())
+    }
+
+    /// Merged item, or `None` when the two sides aren't the same kind.
+    fn item(&mut self, base: Option<Item>, ours: Item, theirs: Item) -> Result<Option<Item>> {
+        Ok(Some(match (ours, theirs) {
+            (Item::Note(o), Item::Note(t)) => {
+                let b = match base {
+                    Some(Item::Note(b)) => Some(b),
+                    _ => None,
+                };
+                Item::Note(self.note(b.as_ref(), o, t)?)
+            }
+            (Item::Pulse(o), Item::Pulse(t)) => {
+                let b = match base {
+                    Some(Item::Pulse(b)) => Some(b),
+                    _ => None,
+                };
+                Item::Pulse(self.pulse(b.as_ref(), o, t))
+            }
+            (Item::Metric(o), Item::Metric(t)) => {
+                let b = match base {
+                    Some(Item::Metric(b)) => Some(b),
+                    _ => None,
+                };
+                Item::Metric(self.metric(b.as_ref(), o, t))
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    fn note(&mut self, base: Option<&Note>, ours: Note, theirs: Note) -> Result<Note> {
+        let newer = if theirs.updated > ours.updated { Side::Remote } else { Side::Local };
+        let body = match base {
+            Some(b) if ours.body != theirs.body && b.body != ours.body && b.body != theirs.body => {
+                match git::merge_text(&b.body, &ours.body, &theirs.body, LABELS)? {
+                    (merged, 0) => merged,
+                    _ => self.pick("body", Some(&b.body), &ours.body, &theirs.body, newer),
+                }
+            }
+            _ => self.pick("body", base.map(|b| &b.body), &ours.body, &theirs.body, newer),
+        };
+        Ok(Note {
+            title: self.pick("title", base.map(|b| &b.title), &ours.title, &theirs.title, newer),
+            tags: self.pick("tags", base.map(|b| &b.tags), &ours.tags, &theirs.tags, newer),
+            notebook: self.pick("notebook", base.map(|b| &b.notebook), &ours.notebook, &theirs.notebook, newer),
+            created: self.pick("created", base.map(|b| &b.created), &ours.created, &theirs.created, newer),
+            related: self.pick("related", base.map(|b| &b.related), &ours.related, &theirs.related, newer),
+            deleted_at: self.pick("deleted_at", base.map(|b| &b.deleted_at), &ours.deleted_at, &theirs.deleted_at, newer),
+            updated: ours.updated.max(theirs.updated),
+            body,
+            id: ours.id,
+        })
+    }
+
+    fn pulse(&mut self, base: Option<&Pulse>, ours: Pulse, theirs: Pulse) -> Pulse {
+        let pairs = |p: &Pulse| p.slots.iter().map(|s| (s.slot.clone(), s.checked)).collect::<Vec<_>>();
+        let slots = self.union("slots", &base.map(pairs).unwrap_or_default(), &pairs(&ours), &pairs(&theirs));
+        Pulse {
+            topic: self.pick("topic", base.map(|b| &b.topic), &ours.topic, &theirs.topic, Side::Local),
+            interval: self.pick("interval", base.map(|b| &b.interval), &ours.interval, &theirs.interval, Side::Local),
+            created: self.pick("created", base.map(|b| &b.created), &ours.created, &theirs.created, Side::Local),
+            deleted_at: self.pick("deleted_at", base.map(|b| &b.deleted_at), &ours.deleted_at, &theirs.deleted_at, Side::Local),
+            slots: slots.into_iter().map(|(slot, checked)| PulseSlot { slot, checked }).collect(),
+            id: ours.id,
+        }
+    }
+
+    fn metric(&mut self, base: Option<&Metric>, ours: Metric, theirs: Metric) -> Metric {
+        let pairs = |m: &Metric| m.points.iter().map(|p| (p.ts, p.value)).collect::<Vec<_>>();
+        let points = self.union("points", &base.map(pairs).unwrap_or_default(), &pairs(&ours), &pairs(&theirs));
+        Metric {
+            topic: self.pick("topic", base.map(|b| &b.topic), &ours.topic, &theirs.topic, Side::Local),
+            created: self.pick("created", base.map(|b| &b.created), &ours.created, &theirs.created, Side::Local),
+            deleted_at: self.pick("deleted_at", base.map(|b| &b.deleted_at), &ours.deleted_at, &theirs.deleted_at, Side::Local),
+            points: points.into_iter().map(|(ts, value)| MetricPoint { ts, value }).collect(),
+            id: ours.id,
+        }
+    }
+
+    /// Keyed union of two edits of `base`: entries added on either side are
+    /// kept, entries one side removed (and the other left alone) are
+    /// dropped, and a key both sides set differently keeps the local value.
+    fn union<K, V>(&mut self, field: &str, base: &[(K, V)], ours: &[(K, V)], theirs: &[(K, V)]) -> Vec<(K, V)>
+    where
+        K: PartialEq + Clone + fmt::Display,
+        V: PartialEq + Clone,
+    {
+        let get = |side: &[(K, V)], k: &K| side.iter().find(|(key, _)| key == k).map(|(_, v)| v.clone());
+        let mut keys: Vec<&K> = Vec::new();
+        for (k, _) in ours.iter().chain(theirs).chain(base) {
+            if !keys.contains(&k) {
+                keys.push(k);
+            }
+        }
+        keys.into_iter()
+            .filter_map(|k| {
+                let (b, o, t) = (get(base, k), get(ours, k), get(theirs, k));
+                self.pick(&format!("{field}[{k}]"), Some(&b), &o, &t, Side::Local)
+                    .map(|v| (k.clone(), v))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Interval;
+
+    fn dt(s: &str) -> chrono::NaiveDateTime {
+        s.parse().unwrap()
+    }
+
+    fn note(title: &str, tags: &[&str], body: &str, updated: &str) -> String {
+        let n = Note::new(
+            "note-20260806-1432-a8f".into(),
+            title.into(),
+            tags.iter().map(|t| t.to_string()).collect(),
+            "default".into(),
+            dt("2026-08-06T14:32:00"),
+            dt(updated),
+            body.into(),
+        );
+        yaml::serialize(&n).unwrap()
+    }
+
+    fn merged_note(m: &Merged) -> Note {
+        match yaml::parse(m.text.as_deref().unwrap()).unwrap() {
+            Item::Note(n) => n,
+            other => panic!("not a note: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn notes_merge_per_field_and_newer_wins() {
+        let path = "notes/note-20260806-1432-a8f.yaml";
+        let base = note("Hello", &["a"], "one\ntwo\nthree\n", "2026-08-06T14:32:00");
+        // Local retitles and edits line one; remote retags and edits line
+        // three, later.
+        let ours = note("Hello there", &["a"], "ONE\ntwo\nthree\n", "2026-08-07T09:00:00");
+        let theirs = note("Hello", &["a", "b"], "one\ntwo\nTHREE\n", "2026-08-08T09:00:00");
+        let m = merge_file(path, Some(&base), Some(&ours), Some(&theirs)).unwrap();
+        let n = merged_note(&m);
+        assert!(m.conflicts.is_empty(), "{:?}", m.conflicts);
+        assert_eq!(n.title, "Hello there");
+        assert_eq!(n.tags, vec!["a", "b"]);
+        assert_eq!(n.body, "ONE\ntwo\nTHREE\n");
+        assert_eq!(n.updated, dt("2026-08-08T09:00:00"));
+
+        // Both retitle: the newer (remote) wins and it's reported.
+        let ours = note("Local title", &["a"], "one\ntwo\nthree\n", "2026-08-07T09:00:00");
+        let theirs = note("Remote title", &["a"], "one\nTWO\nthree\n", "2026-08-08T09:00:00");
+        let m = merge_file(path, Some(&base), Some(&ours), Some(&theirs)).unwrap();
+        assert_eq!(merged_note(&m).title, "Remote title");
+        assert_eq!(
+            m.conflicts,
+            vec![Conflict { file: path.into(), field: "title".into(), kept: Side::Remote }]
+        );
+
+        // Same body line edited both ways: the whole body is picked.
+        let ours = note("Hello", &["a"], "mine\ntwo\nthree\n", "2026-08-09T09:00:00");
+        let theirs = note("Hello", &["a"], "yours\ntwo\nthree\n", "2026-08-08T09:00:00");
+        let m = merge_file(path, Some(&base), Some(&ours), Some(&theirs)).unwrap();
+        assert_eq!(merged_note(&m).body, "mine\ntwo\nthree\n");
+        assert_eq!(m.conflicts[0].field, "body");
+        assert_eq!(m.conflicts[0].kept, Side::Local);
+
+        // Deleted remotely, edited locally: the edit survives.
+        let m = merge_file(path, Some(&base), Some(&ours), None).unwrap();
+        assert_eq!(m.text.as_deref(), Some(ours.as_str()));
+        assert_eq!(m.conflicts[0].field, "deleted");
+        // Deleted remotely, untouched locally: gone.
+        let m = merge_file(path, Some(&base), Some(&base), None).unwrap();
+        assert!(m.text.is_none() && m.conflicts.is_empty());
+    }
+
+    #[test]
+    fn pulses_and_metrics_union_their_samples() {
+        let path = "pulses/pulse-x.yaml";
+        let mut base = Pulse::new("pulse-x".into(), "run".into(), Interval::Daily, dt("2026-08-01T00:00:00"));
+        base.set_slot("2026-08-01", true);
+        base.set_slot("2026-08-02", true);
+        let mut ours = base.clone();
+        ours.set_slot("2026-08-03", true);
+        ours.set_slot("2026-08-05", true);
+        let mut theirs = base.clone();
+        theirs.slots.retain(|s| s.slot != "2026-08-02");
+        theirs.set_slot("2026-08-04", false);
+        theirs.set_slot("2026-08-05", false);
+        let text = |p: &Pulse| yaml::serialize_pulse(p).unwrap();
+        let m = merge_file(path, Some(&text(&base)), Some(&text(&ours)), Some(&text(&theirs))).unwrap();
+        let Item::Pulse(p) = yaml::parse(m.text.as_deref().unwrap()).unwrap() else { panic!() };
+        let mut slots: Vec<(String, bool)> = p.slots.into_iter().map(|s| (s.slot, s.checked)).collect();
+        slots.sort();
+        assert_eq!(
+            slots,
+            vec![
+                ("2026-08-01".into(), true),
+                ("2026-08-03".into(), true),
+                ("2026-08-04".into(), false),
+                ("2026-08-05".into(), true),
+            ]
+        );
+        assert_eq!(m.conflicts.len(), 1);
+        assert_eq!(m.conflicts[0].field, "slots[2026-08-05]");
+
+        let mut base = Metric::new("metric-w".into(), "weight".into(), dt("2026-08-01T00:00:00"));
+        base.append(dt("2026-08-01T08:00:00"), 70.0);
+        let mut ours = base.clone();
+        ours.append(dt("2026-08-02T08:00:00"), 70.5);
+        let mut theirs = base.clone();
+        theirs.append(dt("2026-08-03T08:00:00"), 69.8);
+        theirs.topic = "body weight".into();
+        let text = |m: &Metric| yaml::serialize_metric(m).unwrap();
+        let m = merge_file("metrics/metric-w.yaml", Some(&text(&base)), Some(&text(&ours)), Some(&text(&theirs))).unwrap();
+        let Item::Metric(metric) = yaml::parse(m.text.as_deref().unwrap()).unwrap() else { panic!() };
+        assert_eq!(metric.topic, "body weight");
+        assert_eq!(metric.points.len(), 3);
+        assert!(m.conflicts.is_empty());
+    }
+
+    #[test]
+    fn merge_driver_rewrites_the_current_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut base = Metric::new("metric-w".into(), "weight".into(), dt("2026-08-01T00:00:00"));
+        base.append(dt("2026-08-01T08:00:00"), 70.0);
+        let (mut ours, mut theirs) = (base.clone(), base.clone());
+        ours.append(dt("2026-08-02T08:00:00"), 70.5);
+        theirs.append(dt("2026-08-02T21:00:00"), 70.1);
+        let file = |name: &str, m: &Metric| {
+            let p = dir.path().join(name);
+            std::fs::write(&p, yaml::serialize_metric(m).unwrap()).unwrap();
+            p
+        };
+        let (o, a, b) = (file("O", &base), file("A", &ours), file("B", &theirs));
+        let merged = merge_driver(&o, &a, &b, "metrics/metric-w.yaml").unwrap();
+        assert!(merged.conflicts.is_empty());
+        let Item::Metric(m) = yaml::read_item(&a).unwrap() else { panic!() };
+        assert_eq!(m.points.len(), 3);
+
+        // Unparseable and conflicting: the current file is left alone.
+        std::fs::write(&o, "a\n").unwrap();
+        std::fs::write(&a, "b\n").unwrap();
+        std::fs::write(&b, "c\n").unwrap();
+        let merged = merge_driver(&o, &a, &b, "notes/x.yaml").unwrap();
+        assert_eq!(merged.conflicts[0].field, "file");
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "b\n");
+    }
+
+    #[test]
+    fn sync_merges_diverged_histories() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = |repo: &Path, args: &[&str]| {
+            let out = std::process::Command::new("git").arg("-C").arg(repo).args(args).output().unwrap();
+            assert!(out.status.success(), "git {args:?}: {}", String::from_utf8_lossy(&out.stderr));
+        };
+        let origin = dir.path().join("origin.git");
+        std::fs::create_dir_all(&origin).unwrap();
+        git(&origin, &["init", "--bare", "-q", "-b", "master"]);
+        let clone = |name: &str| {
+            let repo = dir.path().join(name);
+            git(dir.path(), &["clone", "-q", origin.to_str().unwrap(), name]);
+            git(&repo, &["config", "user.name", "ron"]);
+            git(&repo, &["config", "user.email", "ron@localhost"]);
+            repo
+        };
+        let write = |repo: &Path, text: &str| {
+            std::fs::create_dir_all(repo.join("notes")).unwrap();
+            std::fs::write(repo.join("notes/note-20260806-1432-a8f.yaml"), text).unwrap();
+            git(repo, &["add", "-A"]);
+            git(repo, &["commit", "-q", "-m", "edit"]);
+        };
+
+        let (a, b) = (clone("a"), clone("b"));
+        write(&a, &note("Hello", &[], "one\n", "2026-08-06T14:32:00"));
+        git(&a, &["push", "-q", "origin", "master"]);
+        // Fresh clone: a plain fast-forward.
+        let out = sync(&b, "origin", "master", true).unwrap();
+        assert!(!out.merged);
+        assert_eq!(out.changed, vec!["notes/note-20260806-1432-a8f.yaml"]);
+
+        write(&a, &note("Hello", &["remote"], "one\n", "2026-08-07T09:00:00"));
+        git(&a, &["push", "-q", "origin", "master"]);
+        write(&b, &note("Local title", &[], "one\n", "2026-08-08T09:00:00"));
+        assert!(sync(&b, "origin", "master", true).is_err());
+        let out = sync(&b, "origin", "master", false).unwrap();
+        assert!(out.merged && out.conflicts.is_empty());
+        let text = std::fs::read_to_string(b.join("notes/note-20260806-1432-a8f.yaml")).unwrap();
+        let Item::Note(n) = yaml::parse(&text).unwrap() else { panic!() };
+        assert_eq!((n.title.as_str(), n.tags), ("Local title", vec!["remote".to_string()]));
+        assert!(!git::is_dirty(&b).unwrap());
+        // Nothing new on the remote now.
+        let out = sync(&b, "origin", "master", false).unwrap();
+        assert!(out.changed.is_empty() && !out.merged);
+    }
+}
@@ -81,7 +81,7 @@ pub fn parse_v1(text: &str) -> Result<Option<ParsedV1>> {
         body_lines.push(line);
     }
     // Trim a single leading blank line if present.
-    if body_lines.first().map_or(false, |l| l.is_empty()) {
+    if body_lines.first().is_some_and(|l| l.is_empty()) {
         body_lines.remove(0);
     }
     let body = body_lines.join("\n");
@@ -124,6 +124,7 @@ impl ParsedV1 {
             updated: self.updated,
             related: Vec::new(),
             body: self.body,
+            deleted_at: None,
         }
     }
 }
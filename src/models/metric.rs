@@ -16,6 +16,9 @@ pub struct Metric {
     pub created: NaiveDateTime,
     /// Points in no particular order; the DB layer sorts on read.
     pub points: Vec<MetricPoint>,
+    /// When the metric was moved to the trash. `None` = live.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 impl Metric {
@@ -25,6 +28,7 @@ impl Metric {
             topic,
             created,
             points: Vec::new(),
+            deleted_at: None,
         }
     }
 
@@ -50,8 +54,8 @@ impl Metric {
         let values: Vec<f64> = self
             .sorted_points()
             .into_iter()
-            .filter(|p| from.map_or(true, |f| p.ts >= f))
-            .filter(|p| to.map_or(true, |t| p.ts <= t))
+            .filter(|p| from.is_none_or(|f| p.ts >= f))
+            .filter(|p| to.is_none_or(|t| p.ts <= t))
             .map(|p| p.value)
             .collect();
         Stats::from_values(values)
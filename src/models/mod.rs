@@ -1,11 +1,13 @@
-//! Data models for Note, Pulse, and Metric.
+//! Data models for Note, Pulse, and Metric (plus drafts and saved searches).
 
 pub mod draft;
 pub mod metric;
 pub mod note;
 pub mod pulse;
+pub mod search;
 
 pub use draft::{valid_draft_key, Draft, DraftContent};
 pub use metric::{Metric, MetricPoint};
 pub use note::{Note, RelatedRef};
 pub use pulse::{Interval, Pulse, PulseSlot};
+pub use search::{valid_search_name, SavedSearch};
@@ -19,6 +19,10 @@ pub struct Note {
     pub related: Vec<RelatedRef>,
     /// Markdown body. Rendered to HTML in browser view mode.
     pub body: String,
+    /// When the note was moved to the trash. `None` = live. Trashed notes
+    /// keep their YAML file until purged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 impl Note {
@@ -40,6 +44,7 @@ impl Note {
             updated,
             related: Vec::new(),
             body,
+            deleted_at: None,
         }
     }
 }
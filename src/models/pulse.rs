@@ -64,6 +64,9 @@ pub struct Pulse {
     /// "unrecorded", which is semantically false but distinguishable in the
     /// UI from explicitly false.
     pub slots: Vec<PulseSlot>,
+    /// When the pulse was moved to the trash. `None` = live.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 impl Pulse {
@@ -74,6 +77,7 @@ impl Pulse {
             interval,
             created,
             slots: Vec::new(),
+            deleted_at: None,
         }
     }
 
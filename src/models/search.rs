@@ -0,0 +1,57 @@
+//! Saved search model: a named query ("smart notebook"), stored in the YAML
+//! repo at `searches/<name>.yaml` so it travels with the notes on
+//! backup/sync. Not mirrored in the DB: there are only a handful, read
+//! straight from disk.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    /// In the search language (`crate::query`), or a regex with `regex`.
+    pub query: String,
+    /// Field for unscoped terms: `content`, `title`, `tags` or `notebook`.
+    #[serde(default = "default_field")]
+    pub field: String,
+    #[serde(default = "default_true")]
+    pub ignore_case: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+    #[serde(default)]
+    pub regex: bool,
+    pub created: NaiveDateTime,
+    pub updated: NaiveDateTime,
+}
+
+fn default_field() -> String {
+    "content".into()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A valid saved-search name: 1-64 of `[A-Za-z0-9_.-]`, not starting with
+/// a dot. It's the file name, and `@name` on the command line.
+pub fn valid_search_name(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_names_are_plain_file_names() {
+        assert!(valid_search_name("todo"));
+        assert!(valid_search_name("work-2026.q1_x"));
+        assert!(!valid_search_name(""));
+        assert!(!valid_search_name(".hidden"));
+        assert!(!valid_search_name("a/b"));
+        assert!(!valid_search_name("with space"));
+        assert!(!valid_search_name(&"x".repeat(65)));
+    }
+}
@@ -13,10 +13,13 @@
 //!   server.json              <- listen address, optional viewer gate, the
 //!                              `url` CLI clients dial as a fallback
 //!                              when $RON_URL is unset, plus optional
-//!                              `default_notebook` / `editor` / `viewer`
+//!                              `default_notebook` / `editor` / `viewer` /
+//!                              `trash_retention_days` / `remote` /
+//!                              `branch` / `backup_targets`
 //!   tokens.json              <- bearer-token store (NOT committed to git)
 //! ```
 
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
@@ -95,6 +98,22 @@ pub struct ServerConfig {
     /// for an API-only server. `true` by default.
     #[serde(default = "default_viewer")]
     pub viewer: bool,
+    /// Days a deleted note/pulse/metric stays in the trash before the server
+    /// purges it for good. `0` keeps trashed items until `ron trash empty`.
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    /// Git remote `ron backup` pushes to and `ron sync` pulls from.
+    #[serde(default = "default_remote")]
+    pub remote: String,
+    /// Branch backed up and synced, on both ends.
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    /// Named git remotes (name -> URL) managed by `ron remote add/remove`.
+    /// Any of them can be pushed to with `ron backup --to <name>`; the one
+    /// named by `remote` is also the default. The server re-applies the URL
+    /// to the repo's git config before each push/pull.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub backup_targets: BTreeMap<String, String>,
 }
 
 fn default_listen() -> String {
@@ -116,6 +135,18 @@ fn default_viewer() -> bool {
     true
 }
 
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
+fn default_remote() -> String {
+    "origin".to_string()
+}
+
+fn default_branch() -> String {
+    "master".to_string()
+}
+
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
@@ -126,6 +157,10 @@ impl Default for ServerConfig {
             editor: None,
             cli_viewer: default_cli_viewer(),
             viewer: default_viewer(),
+            trash_retention_days: default_trash_retention_days(),
+            remote: default_remote(),
+            branch: default_branch(),
+            backup_targets: BTreeMap::new(),
         }
     }
 }
@@ -210,6 +245,10 @@ mod tests {
             editor: Some("code -w".into()),
             cli_viewer: "bat -l md".into(),
             viewer: false,
+            trash_retention_days: 7,
+            remote: "nas".into(),
+            branch: "main".into(),
+            backup_targets: BTreeMap::from([("nas".into(), "ssh://nas/ron.git".into())]),
         };
         std::fs::write(&path, serde_json::to_string(&cfg).unwrap()).unwrap();
         let text = std::fs::read_to_string(&path).unwrap();
@@ -221,6 +260,9 @@ mod tests {
         assert_eq!(back.editor.as_deref(), Some("code -w"));
         assert_eq!(back.cli_viewer, "bat -l md");
         assert!(!back.viewer);
+        assert_eq!(back.trash_retention_days, 7);
+        assert_eq!((back.remote.as_str(), back.branch.as_str()), ("nas", "main"));
+        assert_eq!(back.backup_targets["nas"], "ssh://nas/ron.git");
     }
 
     #[test]
@@ -234,6 +276,9 @@ mod tests {
         assert!(cfg.editor.is_none());
         assert_eq!(cfg.cli_viewer, "mdless");
         assert!(cfg.viewer);
+        assert_eq!(cfg.trash_retention_days, 30);
+        assert_eq!((cfg.remote.as_str(), cfg.branch.as_str()), ("origin", "master"));
+        assert!(cfg.backup_targets.is_empty());
     }
 
     #[test]
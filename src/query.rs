@@ -0,0 +1,647 @@
+//! The note search language, shared by `GET /api/notes/search`, the viewer's
+//! `/search` page and `ron search`.
+//!
+//! ```text
+//! linux python              both words (AND is implicit)
+//! rust OR go                either
+//! -draft, NOT draft         without
+//! "exact phrase", prefix*   phrase / explicit prefix
+//! (a OR b) c                grouping
+//! t:word  g:tag  n:nb  a:x  scope to title / tags / notebook / anything;
+//!                           also title: tag: tags: notebook: content:,
+//!                           and on a group: t:(a OR b)
+//! created:>2026-01-01       filters on created / updated: >D >=D <D <=D,
+//! updated:last-7d           D (that day), D..E, last-N{d,w,m,y}
+//! has:attachment            also has:related, has:tags, has:links
+//! related:<id>              notes listing <id> in `related`
+//! ```
+//!
+//! `OR`, `AND` and `NOT` are operators only in capitals. Text that isn't
+//! valid syntax is searched for as-is (a stray `)` is dropped, an open `(`
+//! or `"` runs to the end); only a filter with a bad value is an error.
+//! A [`Query`] prints back in this language (filters with their dates
+//! spelled out), which is how `db::did_you_mean` hands out a corrected one.
+//! Matching itself lives in `db::search_query`. Regex mode skips this
+//! language: the pattern goes to [`build_regex`] as-is.
+
+use std::fmt;
+
+use chrono::{Duration, Months, NaiveDateTime, SubsecRound};
+
+use crate::db::{parse_when, NoteField};
+use crate::models::Note;
+
+/// A parsed query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Query {
+    /// All must match; empty matches everything.
+    And(Vec<Query>),
+    /// Any must match.
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    Term(Term),
+    Filter(Filter),
+}
+
+/// A word or phrase to find in the full-text index.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Term {
+    /// `None`: the search's default field.
+    pub field: Option<NoteField>,
+    pub text: String,
+    /// Quoted: the words must appear together, in order.
+    pub phrase: bool,
+    /// Ends in `*`: always a prefix match, even for whole-word searches.
+    pub star: bool,
+}
+
+/// A condition on note metadata rather than text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Created(DateRange),
+    Updated(DateRange),
+    Has(Has),
+    Related(String),
+}
+
+/// Inclusive bounds; `None` is open-ended.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Has {
+    /// The body links a file under `resources/`.
+    Attachment,
+    /// A non-empty `related` list.
+    Related,
+    Tags,
+    /// A `[[wiki link]]` or note ID in the body.
+    Links,
+}
+
+/// Why a query couldn't be parsed.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct QueryError(pub String);
+
+impl Query {
+    /// No terms and no filters: nothing to search for.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Query::And(qs) | Query::Or(qs) => qs.iter().all(Query::is_empty),
+            Query::Not(q) => q.is_empty(),
+            Query::Term(_) | Query::Filter(_) => false,
+        }
+    }
+
+    /// Every term, negated or not, in order.
+    pub fn terms(&self) -> Vec<&Term> {
+        let mut out = Vec::new();
+        self.walk(false, &mut |t, _| out.push(t));
+        out
+    }
+
+    /// Terms whose presence counts toward a match (not under a `NOT`), for
+    /// ranking.
+    pub fn positive_terms(&self) -> Vec<&Term> {
+        let mut out = Vec::new();
+        self.walk(false, &mut |t, negated| {
+            if !negated {
+                out.push(t)
+            }
+        });
+        out
+    }
+
+    fn walk<'a>(&'a self, negated: bool, f: &mut impl FnMut(&'a Term, bool)) {
+        match self {
+            Query::And(qs) | Query::Or(qs) => qs.iter().for_each(|q| q.walk(negated, f)),
+            Query::Not(q) => q.walk(!negated, f),
+            Query::Term(t) => f(t, negated),
+            Query::Filter(_) => {}
+        }
+    }
+
+    /// A copy with each term replaced by `f(term, negated)`.
+    pub fn map_terms(&self, f: &mut impl FnMut(&Term, bool) -> Term) -> Query {
+        self.map_terms_under(false, f)
+    }
+
+    fn map_terms_under(&self, negated: bool, f: &mut impl FnMut(&Term, bool) -> Term) -> Query {
+        match self {
+            Query::And(qs) => Query::And(qs.iter().map(|q| q.map_terms_under(negated, f)).collect()),
+            Query::Or(qs) => Query::Or(qs.iter().map(|q| q.map_terms_under(negated, f)).collect()),
+            Query::Not(q) => Query::Not(Box::new(q.map_terms_under(!negated, f))),
+            Query::Term(t) => Query::Term(f(t, negated)),
+            Query::Filter(f) => Query::Filter(f.clone()),
+        }
+    }
+
+    /// Evaluate with `term` deciding each term.
+    pub fn matches(&self, note: &Note, term: &impl Fn(&Term) -> bool) -> bool {
+        match self {
+            Query::And(qs) => qs.iter().all(|q| q.matches(note, term)),
+            Query::Or(qs) => qs.iter().any(|q| q.matches(note, term)),
+            Query::Not(q) => !q.matches(note, term),
+            Query::Term(t) => term(t),
+            Query::Filter(f) => f.matches(note),
+        }
+    }
+}
+
+impl Filter {
+    pub fn matches(&self, note: &Note) -> bool {
+        match self {
+            Filter::Created(r) => r.contains(note.created),
+            Filter::Updated(r) => r.contains(note.updated),
+            Filter::Has(Has::Attachment) => !crate::links::referenced_names(&note.body).is_empty(),
+            Filter::Has(Has::Related) => !note.related.is_empty(),
+            Filter::Has(Has::Tags) => !note.tags.is_empty(),
+            Filter::Has(Has::Links) => {
+                !crate::wikilink::parse(&note.body).is_empty()
+                    || !crate::id::note_ids_in(&note.body).is_empty()
+            }
+            Filter::Related(id) => note.related.iter().any(|r| r == id),
+        }
+    }
+}
+
+/// Back to query text that parses to the same query. Groups get the
+/// parentheses they need; `NOT` prints as `-`.
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let grouped = |q: &Query, f: &mut fmt::Formatter<'_>| match q {
+            Query::And(_) | Query::Or(_) => write!(f, "({q})"),
+            _ => write!(f, "{q}"),
+        };
+        match self {
+            Query::And(qs) | Query::Or(qs) => {
+                let sep = if matches!(self, Query::And(_)) { " " } else { " OR " };
+                for (i, q) in qs.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(sep)?;
+                    }
+                    // `a b OR c` already reads as `(a b) OR c`.
+                    match (self, q) {
+                        (Query::Or(_), Query::And(_)) => write!(f, "{q}")?,
+                        _ => grouped(q, f)?,
+                    }
+                }
+                Ok(())
+            }
+            Query::Not(q) => {
+                f.write_str("-")?;
+                grouped(q, f)
+            }
+            Query::Term(t) => write!(f, "{t}"),
+            Query::Filter(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(field) = self.field {
+            // The first (shortest) name of each field.
+            let name = FIELDS.iter().find(|(_, fd)| *fd == field).map_or("a", |(n, _)| n);
+            write!(f, "{name}:")?;
+        }
+        if self.phrase {
+            write!(f, "\"{}\"", self.text)?;
+        } else {
+            f.write_str(&self.text)?;
+        }
+        if self.star {
+            f.write_str("*")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Created(r) => write!(f, "created:{r}"),
+            Filter::Updated(r) => write!(f, "updated:{r}"),
+            Filter::Has(h) => {
+                let name = match h {
+                    Has::Attachment => "attachment",
+                    Has::Related => "related",
+                    Has::Tags => "tags",
+                    Has::Links => "links",
+                };
+                write!(f, "has:{name}")
+            }
+            Filter::Related(id) => write!(f, "related:{id}"),
+        }
+    }
+}
+
+/// `>=D`, `<=D` or `D..E`, with `D` and `E` as full datetimes.
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ts = |t: NaiveDateTime| t.format("%Y-%m-%dT%H:%M:%S");
+        match (self.from, self.to) {
+            (Some(from), Some(to)) => write!(f, "{}..{}", ts(from), ts(to)),
+            (Some(from), None) => write!(f, ">={}", ts(from)),
+            (None, Some(to)) => write!(f, "<={}", ts(to)),
+            (None, None) => f.write_str(".."),
+        }
+    }
+}
+
+impl DateRange {
+    /// Whether `t` is in the range, compared to the second like the bounds.
+    pub fn contains(&self, t: NaiveDateTime) -> bool {
+        let t = t.trunc_subsecs(0);
+        self.from.is_none_or(|f| t >= f.trunc_subsecs(0)) && self.to.is_none_or(|to| t <= to.trunc_subsecs(0))
+    }
+}
+
+/// A regex-mode search pattern, compiled with the search's options:
+/// `ignore_case` folds case, `whole_word` wants word boundaries on both
+/// ends. The `regex` crate runs in linear time, so any pattern is safe.
+pub fn build_regex(pattern: &str, ignore_case: bool, whole_word: bool) -> Result<regex::Regex, QueryError> {
+    let pattern = if whole_word { format!(r"\b(?:{pattern})\b") } else { pattern.to_string() };
+    regex::RegexBuilder::new(&pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| QueryError(format!("bad regex: {e}")))
+}
+
+/// Parse `input`; `now` anchors relative dates (`last-7d`).
+pub fn parse(input: &str, now: NaiveDateTime) -> Result<Query, QueryError> {
+    let tokens = lex(input);
+    let mut p = Parser { tokens, pos: 0, now };
+    let mut parts = Vec::new();
+    loop {
+        parts.push(p.or()?);
+        // Only a stray `)` stops `or` early; drop it and carry on.
+        if p.next().is_none() {
+            break;
+        }
+    }
+    Ok(and(parts))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Tok {
+    Open,
+    Close,
+    Or,
+    And,
+    Not,
+    /// `name:` with a known name.
+    Scope(String),
+    Word(String),
+    Phrase { text: String, star: bool },
+}
+
+const FIELDS: &[(&str, NoteField)] = &[
+    ("t", NoteField::Title),
+    ("title", NoteField::Title),
+    ("g", NoteField::Tags),
+    ("tag", NoteField::Tags),
+    ("tags", NoteField::Tags),
+    ("n", NoteField::Notebook),
+    ("notebook", NoteField::Notebook),
+    ("a", NoteField::Content),
+    ("content", NoteField::Content),
+];
+const FILTERS: &[&str] = &["created", "updated", "has", "related"];
+
+fn lex(input: &str) -> Vec<Tok> {
+    let mut out = Vec::new();
+    let mut rest = input.trim_start();
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '(' => {
+                out.push(Tok::Open);
+                rest = &rest[1..];
+            }
+            ')' => {
+                out.push(Tok::Close);
+                rest = &rest[1..];
+            }
+            '"' => {
+                let after = &rest[1..];
+                let (text, tail) = match after.find('"') {
+                    Some(i) => (&after[..i], &after[i + 1..]),
+                    None => (after, ""),
+                };
+                let star = tail.starts_with('*');
+                out.push(Tok::Phrase { text: text.trim().to_string(), star });
+                rest = if star { &tail[1..] } else { tail };
+            }
+            '-' if rest[1..].starts_with(|c: char| !c.is_whitespace()) => {
+                out.push(Tok::Not);
+                rest = &rest[1..];
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '"'))
+                    .unwrap_or(rest.len());
+                let word = &rest[..end];
+                rest = &rest[end..];
+                match word {
+                    "OR" => out.push(Tok::Or),
+                    "AND" => out.push(Tok::And),
+                    "NOT" => out.push(Tok::Not),
+                    _ => match word.split_once(':') {
+                        Some((name, value))
+                            if FIELDS.iter().any(|(f, _)| *f == name) || FILTERS.contains(&name) =>
+                        {
+                            out.push(Tok::Scope(name.to_string()));
+                            if !value.is_empty() {
+                                out.push(Tok::Word(value.to_string()));
+                            }
+                        }
+                        _ => out.push(Tok::Word(word.to_string())),
+                    },
+                }
+            }
+        }
+        rest = rest.trim_start();
+    }
+    out
+}
+
+struct Parser {
+    tokens: Vec<Tok>,
+    pos: usize,
+    now: NaiveDateTime,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn or(&mut self) -> Result<Query, QueryError> {
+        let mut alts = vec![self.and()?];
+        while self.peek() == Some(&Tok::Or) {
+            self.pos += 1;
+            alts.push(self.and()?);
+        }
+        alts.retain(|q| !q.is_empty());
+        Ok(if alts.len() == 1 { alts.pop().unwrap() } else { Query::Or(alts) })
+    }
+
+    fn and(&mut self) -> Result<Query, QueryError> {
+        let mut parts = Vec::new();
+        while let Some(t) = self.peek() {
+            match t {
+                Tok::Or | Tok::Close => break,
+                Tok::And => self.pos += 1,
+                _ => parts.push(self.unary()?),
+            }
+        }
+        Ok(and(parts))
+    }
+
+    fn unary(&mut self) -> Result<Query, QueryError> {
+        if self.peek() == Some(&Tok::Not) {
+            self.pos += 1;
+            let q = self.unary()?;
+            return Ok(if q.is_empty() { q } else { Query::Not(Box::new(q)) });
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Query, QueryError> {
+        match self.next() {
+            Some(Tok::Open) => {
+                let q = self.or()?;
+                if self.peek() == Some(&Tok::Close) {
+                    self.pos += 1;
+                }
+                Ok(q)
+            }
+            Some(Tok::Scope(name)) => match FIELDS.iter().find(|(f, _)| *f == name) {
+                Some(&(_, field)) => {
+                    if matches!(self.peek(), None | Some(Tok::Close | Tok::Or | Tok::And)) {
+                        return Ok(Query::And(Vec::new()));
+                    }
+                    Ok(scoped(self.unary()?, field))
+                }
+                None => {
+                    let value = match self.next() {
+                        Some(Tok::Word(w)) => w,
+                        Some(Tok::Phrase { text, .. }) => text,
+                        _ => return Err(QueryError(format!("{name}: needs a value"))),
+                    };
+                    Ok(Query::Filter(filter(&name, &value, self.now)?))
+                }
+            },
+            Some(Tok::Word(w)) => {
+                let (text, star) = match w.strip_suffix('*') {
+                    Some(w) => (w, true),
+                    None => (w.as_str(), false),
+                };
+                Ok(term(text, false, star))
+            }
+            Some(Tok::Phrase { text, star }) => Ok(term(&text, true, star)),
+            // Operators with nothing to apply to.
+            Some(Tok::Or | Tok::And | Tok::Not | Tok::Close) | None => Ok(Query::And(Vec::new())),
+        }
+    }
+}
+
+fn and(mut parts: Vec<Query>) -> Query {
+    parts.retain(|q| !q.is_empty());
+    if parts.len() == 1 {
+        parts.pop().unwrap()
+    } else {
+        Query::And(parts)
+    }
+}
+
+/// A term, or nothing when it has no word characters (it would be an empty
+/// full-text phrase).
+fn term(text: &str, phrase: bool, star: bool) -> Query {
+    if !text.chars().any(char::is_alphanumeric) {
+        return Query::And(Vec::new());
+    }
+    Query::Term(Term { field: None, text: text.to_string(), phrase, star })
+}
+
+/// Give unscoped terms in `q` the field `field`; inner scopes win.
+fn scoped(q: Query, field: NoteField) -> Query {
+    match q {
+        Query::And(qs) => Query::And(qs.into_iter().map(|q| scoped(q, field)).collect()),
+        Query::Or(qs) => Query::Or(qs.into_iter().map(|q| scoped(q, field)).collect()),
+        Query::Not(q) => Query::Not(Box::new(scoped(*q, field))),
+        Query::Term(mut t) => {
+            t.field.get_or_insert(field);
+            Query::Term(t)
+        }
+        f @ Query::Filter(_) => f,
+    }
+}
+
+fn filter(name: &str, value: &str, now: NaiveDateTime) -> Result<Filter, QueryError> {
+    Ok(match name {
+        "created" => Filter::Created(date_range(value, now)?),
+        "updated" => Filter::Updated(date_range(value, now)?),
+        "related" => Filter::Related(value.to_string()),
+        _ => Filter::Has(match value {
+            "attachment" | "attachments" => Has::Attachment,
+            "related" => Has::Related,
+            "tag" | "tags" => Has::Tags,
+            "link" | "links" => Has::Links,
+            _ => {
+                return Err(QueryError(format!(
+                    "has:{value}: expected attachment, related, tags or links"
+                )))
+            }
+        }),
+    })
+}
+
+/// `>D`, `>=D`, `<D`, `<=D`, `D`, `D..E`, or `last-N{d,w,m,y}`; `D` is a
+/// date or a `YYYY-MM-DDTHH:MM:SS` datetime.
+fn date_range(value: &str, now: NaiveDateTime) -> Result<DateRange, QueryError> {
+    let bad = || QueryError(format!("bad date {value:?}: use >D, <=D, D..E or last-7d"));
+    let date = |s: &str, end: bool| parse_when(s, end).ok_or_else(bad);
+    let second = Duration::seconds(1);
+    if let Some(span) = value.strip_prefix("last-") {
+        // Split on the last char, not byte: the unit may be any typed text.
+        let at = span.char_indices().last().map_or(0, |(i, _)| i);
+        let (n, unit) = span.split_at(at);
+        let n: u32 = n.parse().map_err(|_| bad())?;
+        let from = match unit {
+            "d" => now.checked_sub_signed(Duration::days(n.into())),
+            "w" => now.checked_sub_signed(Duration::weeks(n.into())),
+            "m" => now.checked_sub_months(Months::new(n)),
+            "y" => now.checked_sub_months(Months::new(n.saturating_mul(12))),
+            _ => None,
+        };
+        return Ok(DateRange { from: Some(from.ok_or_else(bad)?), to: None });
+    }
+    let range = if let Some(d) = value.strip_prefix(">=") {
+        DateRange { from: Some(date(d, false)?), to: None }
+    } else if let Some(d) = value.strip_prefix("<=") {
+        DateRange { from: None, to: Some(date(d, true)?) }
+    } else if let Some(d) = value.strip_prefix('>') {
+        DateRange { from: Some(date(d, true)? + second), to: None }
+    } else if let Some(d) = value.strip_prefix('<') {
+        DateRange { from: None, to: Some(date(d, false)? - second) }
+    } else if let Some((a, b)) = value.split_once("..") {
+        DateRange {
+            from: if a.is_empty() { None } else { Some(date(a, false)?) },
+            to: if b.is_empty() { None } else { Some(date(b, true)?) },
+        }
+    } else {
+        DateRange { from: Some(date(value, false)?), to: Some(date(value, true)?) }
+    };
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> NaiveDateTime {
+        "2026-03-15T12:00:00".parse().unwrap()
+    }
+
+    fn word(text: &str, field: Option<NoteField>) -> Query {
+        Query::Term(Term { field, text: text.into(), phrase: false, star: false })
+    }
+
+    #[test]
+    fn parses_operators_groups_and_scopes() {
+        let q = parse("linux (rust OR go) -draft", now()).unwrap();
+        assert_eq!(
+            q,
+            Query::And(vec![
+                word("linux", None),
+                Query::Or(vec![word("rust", None), word("go", None)]),
+                Query::Not(Box::new(word("draft", None))),
+            ])
+        );
+        let q = parse("t:(a OR n:b) NOT \"x y\"* or", now()).unwrap();
+        assert_eq!(
+            q,
+            Query::And(vec![
+                Query::Or(vec![word("a", Some(NoteField::Title)), word("b", Some(NoteField::Notebook))]),
+                Query::Not(Box::new(Query::Term(Term {
+                    field: None,
+                    text: "x y".into(),
+                    phrase: true,
+                    star: true,
+                }))),
+                word("or", None),
+            ])
+        );
+        // Junk is text or nothing, never an error.
+        assert_eq!(parse("NEAR( \" -", now()).unwrap(), word("NEAR", None));
+        assert_eq!(parse(") OR - t:", now()).unwrap(), Query::And(vec![]));
+        assert!(parse("-- ()", now()).unwrap().is_empty());
+        assert_eq!(parse("foo:bar", now()).unwrap(), word("foo:bar", None));
+    }
+
+    #[test]
+    fn parses_filters() {
+        let day = |s: &str| -> NaiveDateTime { s.parse().unwrap() };
+        let f = |s: &str| match parse(s, now()).unwrap() {
+            Query::Filter(f) => f,
+            q => panic!("{q:?}"),
+        };
+        assert_eq!(
+            f("created:>2026-01-01"),
+            Filter::Created(DateRange { from: Some(day("2026-01-02T00:00:00")), to: None })
+        );
+        assert_eq!(
+            f("updated:<2026-01-01"),
+            Filter::Updated(DateRange { from: None, to: Some(day("2025-12-31T23:59:59")) })
+        );
+        assert_eq!(
+            f("created:2026-01-01..2026-01-31"),
+            Filter::Created(DateRange { from: Some(day("2026-01-01T00:00:00")), to: Some(day("2026-01-31T23:59:59")) })
+        );
+        assert_eq!(
+            f("updated:last-7d"),
+            Filter::Updated(DateRange { from: Some(day("2026-03-08T12:00:00")), to: None })
+        );
+        assert_eq!(
+            f("updated:last-1m"),
+            Filter::Updated(DateRange { from: Some(day("2026-02-15T12:00:00")), to: None })
+        );
+        assert_eq!(f("has:attachment"), Filter::Has(Has::Attachment));
+        assert_eq!(f("related:note-1"), Filter::Related("note-1".into()));
+        assert!(parse("created:soon", now()).is_err());
+        assert!(parse("updated:last-7é", now()).is_err());
+        assert!(parse("updated:last-é", now()).is_err());
+        assert!(parse("has:cats", now()).is_err());
+        assert!(parse("related:", now()).is_err());
+    }
+
+    #[test]
+    fn prints_back_to_the_same_query() {
+        for q in [
+            "linux (rust OR go) -draft",
+            "t:(a OR n:b) NOT \"x y\"* or",
+            "a b OR c",
+            "(a OR b) OR c",
+            "(a b) c",
+            "-(a OR -b) g:\"x\" a:y*",
+            "created:>2026-01-01 updated:last-7d has:links related:note-1",
+            "updated:2026-01-01..2026-01-31 created:<=2026-02-01T10:00:00",
+        ] {
+            let parsed = parse(q, now()).unwrap();
+            assert_eq!(parse(&parsed.to_string(), now()).unwrap(), parsed, "{q} -> {parsed}");
+        }
+        assert_eq!(parse("t:(rust OR go) -draft", now()).unwrap().to_string(), "(t:rust OR t:go) -draft");
+        assert_eq!(parse("", now()).unwrap().to_string(), "");
+    }
+}
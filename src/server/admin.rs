@@ -1,31 +1,31 @@
-//! Administrative endpoints: export, import, backup, sync.
+//! Administrative endpoints: export, import, backup, sync, format upgrade,
+//! git remotes.
 //!
 //! All require a bearer token (they're destructive / reach the network).
 
-use axum::extract::State;
+use axum::extract::{Path, State};
 use axum::Json;
 use serde::{Deserialize, Serialize};
 
 use crate::git;
-use crate::server::error::ApiResult;
-use crate::server::{rebuild_db_from_yaml, AppState};
+use crate::server::error::{ApiError, ApiResult};
+use crate::server::{changed_since_load, mark_loaded, rebuild_db_from_yaml, refresh_db_from_yaml, AppState};
 use crate::yaml::Item;
 
-const REMOTE: &str = "origin";
-const BRANCH: &str = "master";
-
 /// Dump everything in the DB to YAML files in the repo dir, removing any
 /// stale YAML that has no DB counterpart. Commits the result.
 async fn export(State(state): State<AppState>) -> ApiResult<Json<ExportReport>> {
-    let (notes, pulses, metrics) = {
-        let conn = state.db();
-        (
-            crate::db::list_notes(&conn, None)?,
-            crate::db::list_pulses(&conn)?,
-            crate::db::list_metrics(&conn)?,
-        )
-    };
+    // Trashed items keep their files until purged, so they're exported too.
+    // The DB stays locked until the commit, so a sync can't land in between.
+    let conn = state.db();
+    let mut notes = crate::db::list_notes(&conn, None)?;
+    notes.extend(crate::db::list_trashed_notes(&conn)?);
+    let mut pulses = crate::db::list_pulses(&conn)?;
+    pulses.extend(crate::db::list_trashed_pulses(&conn)?);
+    let mut metrics = crate::db::list_metrics(&conn)?;
+    metrics.extend(crate::db::list_trashed_metrics(&conn)?);
     let repo = state.inner.paths.repo_dir.clone();
+    let _repo = state.repo();
     // Clear stale YAML in the repo root (legacy flat layout) and the
     // per-type subdirs first so deleted items don't survive the rewrite.
     let mut kept: std::collections::HashSet<String> = std::collections::HashSet::new();
@@ -67,63 +67,181 @@ async fn export(State(state): State<AppState>) -> ApiResult<Json<ExportReport>>
     }))
 }
 
+#[derive(Deserialize, Default)]
+struct ImportBody {
+    #[serde(default)]
+    full: bool,
+}
+
 /// Reload the DB from the YAML files in the repo dir. Use after editing YAML
-/// by hand or after a `sync` (pull).
-async fn import(State(state): State<AppState>) -> ApiResult<Json<ImportReport>> {
+/// by hand. Only the files changed since the DB was last loaded (committed
+/// or not) are re-read; `full` (or a DB with no record of that) drops every
+/// row and reloads the whole repo.
+async fn import(
+    State(state): State<AppState>,
+    body: Option<Json<ImportBody>>,
+) -> ApiResult<Json<ImportReport>> {
+    let full = body.is_some_and(|Json(b)| b.full);
+    Ok(Json(import_inner(&state, full)?))
+}
+
+pub fn import_inner(state: &AppState, full: bool) -> ApiResult<ImportReport> {
     let repo = state.inner.paths.repo_dir.clone();
-    let count = {
-        let conn = state.db();
-        rebuild_db_from_yaml(&conn, &repo)?
-    };
-    Ok(Json(ImportReport { items: count }))
+    let conn = state.db();
+    let changed = if full { None } else { changed_since_load(&conn, &repo)? };
+    Ok(match changed {
+        Some(paths) => {
+            let refresh = refresh_db_from_yaml(&conn, &repo, &paths)?;
+            mark_loaded(&conn, &repo)?;
+            ImportReport { items: refresh.loaded, removed: refresh.removed, full: false }
+        }
+        None => ImportReport { items: rebuild_db_from_yaml(&conn, &repo)?, removed: 0, full: true },
+    })
 }
 
 #[derive(Deserialize, Default)]
 struct BackupBody {
     #[serde(default)]
     dry_run: bool,
+    /// Backup target to push to instead of the default remote.
+    #[serde(default)]
+    to: Option<String>,
 }
 
-/// `git push origin master`. With `dry_run`, only gather and report the
-/// status (remote, fetch, ahead/behind, commits to push/pull) — no push.
-/// The remote must already be configured (via `git -C <repo> remote add
-/// origin <url>`).
+/// `git push <remote> <branch>` to the configured remote, or to the backup
+/// target named by `to`. With `dry_run`, only gather and report the status
+/// (remote, fetch, ahead/behind, commits to push/pull) — no push.
 async fn backup(
     State(state): State<AppState>,
     Json(body): Json<BackupBody>,
 ) -> ApiResult<Json<BackupReport>> {
+    Ok(Json(backup_inner(&state, body.to.as_deref(), body.dry_run)?))
+}
+
+pub fn backup_inner(state: &AppState, to: Option<&str>, dry_run: bool) -> ApiResult<BackupReport> {
     let repo = state.inner.paths.repo_dir.clone();
-    if body.dry_run {
-        let status = git::backup_status(&repo, REMOTE, BRANCH)?;
-        return Ok(Json(BackupReport {
+    let remote = use_remote(state, to)?;
+    let branch = state.inner.branch.clone();
+    if dry_run {
+        let status = git::backup_status(&repo, &remote, &branch)?;
+        return Ok(BackupReport {
             dry_run: true,
             pushed: false,
+            remote,
+            branch,
             status: Some(status),
-        }));
+        });
     }
-    git::push(&repo, REMOTE, BRANCH)?;
-    Ok(Json(BackupReport {
+    git::push(&repo, &remote, &branch)?;
+    Ok(BackupReport {
         dry_run: false,
         pushed: true,
+        remote,
+        branch,
         status: None,
-    }))
+    })
+}
+
+/// The remote to push to / pull from: `name`, or the configured default.
+/// A backup target's URL from `server.json` is written to the repo's git
+/// config first, so the file stays authoritative. The default remote may
+/// also have been set up by hand with `git remote add`.
+fn use_remote(state: &AppState, name: Option<&str>) -> ApiResult<String> {
+    let name = name.unwrap_or(&state.inner.remote).to_string();
+    let url = state.inner.backup_targets.read().unwrap().get(&name).cloned();
+    match url {
+        Some(url) => git::set_remote(&state.inner.paths.repo_dir, &name, &url)?,
+        None if name != state.inner.remote => {
+            return Err(ApiError::BadRequest(format!("no backup target named {name:?}; add it with `ron remote add`")));
+        }
+        None => {}
+    }
+    Ok(name)
+}
+
+#[derive(Deserialize, Default)]
+struct SyncBody {
+    #[serde(default)]
+    ff_only: bool,
 }
 
-/// `git pull --ff-only origin master`, then rebuild the DB from the YAML
-/// files that changed.
-async fn sync(State(state): State<AppState>) -> ApiResult<Json<SyncReport>> {
+/// Bring in the configured remote's branch: fast-forward, or merge item by
+/// item when both sides have new commits (see `merge`), then refresh the DB
+/// rows of just the files that changed. With `ff_only`, diverged histories
+/// fail instead of merging.
+async fn sync(
+    State(state): State<AppState>,
+    Json(body): Json<SyncBody>,
+) -> ApiResult<Json<SyncReport>> {
+    Ok(Json(sync_inner(&state, body.ff_only)?))
+}
+
+pub fn sync_inner(state: &AppState, ff_only: bool) -> ApiResult<SyncReport> {
     let repo = state.inner.paths.repo_dir.clone();
-    let changed = git::pull(&repo, REMOTE, BRANCH)?;
-    let items = {
-        let conn = state.db();
-        rebuild_db_from_yaml(&conn, &repo)?
+    let remote = use_remote(state, None)?;
+    // Hold the DB, then the repo (see `AppState::repo`), across the merge
+    // and the refresh: writers keep the DB locked from reading an item until
+    // its commit, so none can save a pre-merge copy over the merged file.
+    let conn = state.db();
+    let _repo = state.repo();
+    let outcome = crate::merge::sync(&repo, &remote, &state.inner.branch, ff_only)?;
+    let refresh = refresh_db_from_yaml(&conn, &repo, &outcome.changed)?;
+    Ok(SyncReport {
+        changed_files: outcome
+            .changed
+            .iter()
+            .map(|p| p.rsplit('/').next().unwrap_or(p).to_string())
+            .collect(),
+        items_loaded: refresh.loaded,
+        items_removed: refresh.removed,
+        merged: outcome.merged,
+        conflicts: outcome.conflicts,
+    })
+}
+
+#[derive(Deserialize, Default)]
+struct UpgradeFormatBody {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Rewrite every YAML file older than `yaml::FORMAT_VERSION` at the current
+/// version, in a single commit. The server already reads old files through
+/// the upgrade chain; this just makes the repo (and its diffs) current.
+async fn upgrade_format(
+    State(state): State<AppState>,
+    Json(body): Json<UpgradeFormatBody>,
+) -> ApiResult<Json<UpgradeFormatReport>> {
+    let repo = state.inner.paths.repo_dir.clone();
+    let _repo = state.repo();
+    let report = crate::yaml::upgrade_repo(&repo, body.dry_run)?;
+    let rel = |p: &std::path::Path| {
+        p.strip_prefix(&repo)
+            .unwrap_or(p)
+            .to_string_lossy()
+            .into_owned()
     };
-    Ok(Json(SyncReport {
-        changed_files: changed
+    let upgraded: Vec<String> = report.upgraded.iter().map(|p| rel(p)).collect();
+    let committed = if body.dry_run || upgraded.is_empty() {
+        false
+    } else {
+        let path_refs: Vec<&str> = upgraded.iter().map(|s| s.as_str()).collect();
+        git::add_and_commit(
+            &repo,
+            &path_refs,
+            &format!("format: upgrade YAML to v{}", crate::yaml::FORMAT_VERSION),
+        )?
+    };
+    Ok(Json(UpgradeFormatReport {
+        format_version: crate::yaml::FORMAT_VERSION,
+        dry_run: body.dry_run,
+        upgraded,
+        failed: report
+            .failed
             .iter()
-            .map(|p| p.file_name().unwrap_or_default().to_string_lossy().into_owned())
+            .map(|(p, e)| format!("{}: {e}", rel(p)))
             .collect(),
-        items_loaded: items,
+        committed,
     }))
 }
 
@@ -136,6 +254,89 @@ async fn config(State(state): State<AppState>) -> ApiResult<Json<ConfigReport>>
     }))
 }
 
+/// Named git remotes: the backup targets from `server.json`, plus the
+/// default remote when it was set up in git directly.
+async fn list_remotes(State(state): State<AppState>) -> ApiResult<Json<Vec<RemoteInfo>>> {
+    Ok(Json(list_remotes_inner(&state)?))
+}
+
+pub fn list_remotes_inner(state: &AppState) -> ApiResult<Vec<RemoteInfo>> {
+    let default = &state.inner.remote;
+    let mut remotes: Vec<RemoteInfo> = state
+        .inner
+        .backup_targets
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(name, url)| RemoteInfo { name: name.clone(), url: url.clone(), default: name == default })
+        .collect();
+    if !remotes.iter().any(|r| r.default) {
+        if let Some(url) = git::remote_url(&state.inner.paths.repo_dir, default)? {
+            remotes.insert(0, RemoteInfo { name: default.clone(), url, default: true });
+        }
+    }
+    Ok(remotes)
+}
+
+#[derive(Deserialize)]
+struct RemoteBody {
+    name: String,
+    url: String,
+}
+
+/// Add (or re-point) a named remote, in git and in `server.json`.
+async fn add_remote(State(state): State<AppState>, Json(body): Json<RemoteBody>) -> ApiResult<Json<RemoteInfo>> {
+    Ok(Json(add_remote_inner(&state, &body.name, &body.url)?))
+}
+
+pub fn add_remote_inner(state: &AppState, name: &str, url: &str) -> ApiResult<RemoteInfo> {
+    if !valid_remote_name(name) {
+        return Err(ApiError::BadRequest(format!("invalid remote name {name:?}")));
+    }
+    let url = url.trim();
+    if url.is_empty() || url.starts_with('-') {
+        return Err(ApiError::BadRequest(format!("invalid remote URL {url:?}")));
+    }
+    git::set_remote(&state.inner.paths.repo_dir, name, url)?;
+    state.inner.backup_targets.write().unwrap().insert(name.to_string(), url.to_string());
+    state.save_backup_targets()?;
+    Ok(RemoteInfo { name: name.to_string(), url: url.to_string(), default: name == state.inner.remote })
+}
+
+/// Remove a named remote from git and `server.json`.
+async fn remove_remote(State(state): State<AppState>, Path(name): Path<String>) -> ApiResult<Json<serde_json::Value>> {
+    remove_remote_inner(&state, &name)?;
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+pub fn remove_remote_inner(state: &AppState, name: &str) -> ApiResult<()> {
+    let configured = state.inner.backup_targets.write().unwrap().remove(name).is_some();
+    let in_git = valid_remote_name(name) && git::remove_remote(&state.inner.paths.repo_dir, name)?;
+    if !configured && !in_git {
+        return Err(ApiError::NotFound);
+    }
+    if configured {
+        state.save_backup_targets()?;
+    }
+    Ok(())
+}
+
+/// Plain git remote names: letters, digits, `.`, `_`, `-`, not starting
+/// with `.` or `-` (so they never read as a git option).
+fn valid_remote_name(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && !name.starts_with(['.', '-'])
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+#[derive(Serialize)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub url: String,
+    /// Whether this is the remote `backup` / `sync` use by default.
+    pub default: bool,
+}
+
 #[derive(Serialize)]
 pub struct ConfigReport {
     pub default_notebook: String,
@@ -151,13 +352,20 @@ pub struct ExportReport {
 
 #[derive(Serialize)]
 pub struct ImportReport {
+    /// Items (re)loaded.
     pub items: usize,
+    /// Rows dropped because their file is gone.
+    pub removed: usize,
+    /// Whether every row was dropped and the whole repo reloaded.
+    pub full: bool,
 }
 
 #[derive(Serialize)]
 pub struct BackupReport {
     pub dry_run: bool,
     pub pushed: bool,
+    pub remote: String,
+    pub branch: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<git::BackupStatus>,
 }
@@ -166,6 +374,20 @@ pub struct BackupReport {
 pub struct SyncReport {
     pub changed_files: Vec<String>,
     pub items_loaded: usize,
+    pub items_removed: usize,
+    /// Whether both sides had new commits and a merge commit was made.
+    pub merged: bool,
+    /// Fields both sides changed, settled by keeping one side.
+    pub conflicts: Vec<crate::merge::Conflict>,
+}
+
+#[derive(Serialize)]
+pub struct UpgradeFormatReport {
+    pub format_version: u32,
+    pub dry_run: bool,
+    pub upgraded: Vec<String>,
+    pub failed: Vec<String>,
+    pub committed: bool,
 }
 
 pub fn routes() -> axum::Router<AppState> {
@@ -175,5 +397,176 @@ pub fn routes() -> axum::Router<AppState> {
         .route("/api/import", routing::post(import))
         .route("/api/backup", routing::post(backup))
         .route("/api/sync", routing::post(sync))
+        .route("/api/upgrade-format", routing::post(upgrade_format))
         .route("/api/config", routing::get(config))
+        .route("/api/remotes", routing::get(list_remotes).post(add_remote))
+        .route("/api/remotes/:name", routing::delete(remove_remote))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Note;
+
+    fn note(id: &str, title: &str) -> Note {
+        let now = "2026-08-06T14:32:00".parse().unwrap();
+        Note::new(id.into(), title.into(), vec![], "default".into(), now, now, "body".into())
+    }
+
+    #[test]
+    fn import_reloads_only_what_changed() {
+        let (_dir, state) = crate::server::test_state();
+        let repo = state.inner.paths.repo_dir.clone();
+        for (id, title) in [("note-20260806-1432-aaa", "Keep"), ("note-20260806-1432-bbb", "Drop")] {
+            crate::server::notes::persist_yaml(&state, Item::Note(note(id, title))).unwrap();
+        }
+        // Nothing recorded yet: a full reload.
+        let r = import_inner(&state, false).unwrap();
+        assert_eq!((r.items, r.full), (2, true));
+
+        // Hand edits: one retitled and committed, one file deleted, one new
+        // (uncommitted) file, plus a saved search that isn't an item.
+        crate::yaml::write_item(&repo, &Item::Note(note("note-20260806-1432-aaa", "Kept"))).unwrap();
+        git::add_and_commit(&repo, &["notes/note-20260806-1432-aaa.yaml"], "hand edit").unwrap();
+        std::fs::remove_file(repo.join("notes/note-20260806-1432-bbb.yaml")).unwrap();
+        crate::yaml::write_item(&repo, &Item::Note(note("note-20260806-1432-ccc", "New"))).unwrap();
+        std::fs::create_dir_all(repo.join("searches")).unwrap();
+        std::fs::write(repo.join("searches/x.yaml"), "not an item").unwrap();
+        let r = import_inner(&state, false).unwrap();
+        assert_eq!((r.items, r.removed, r.full), (2, 1, false));
+        let conn = state.db();
+        let mut titles: Vec<String> = crate::db::list_notes(&conn, None).unwrap().into_iter().map(|n| n.title).collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Kept", "New"]);
+        drop(conn);
+
+        // Uncommitted edits, loaded and then thrown away: nothing in git
+        // points at them any more, but the next import still re-reads them.
+        crate::yaml::write_item(&repo, &Item::Note(note("note-20260806-1432-aaa", "Draft"))).unwrap();
+        import_inner(&state, false).unwrap();
+        let out = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&repo)
+            .args(["checkout", "--", "notes/note-20260806-1432-aaa.yaml"])
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        std::fs::remove_file(repo.join("notes/note-20260806-1432-ccc.yaml")).unwrap();
+        let r = import_inner(&state, false).unwrap();
+        assert_eq!((r.items, r.removed, r.full), (1, 1, false));
+        let titles: Vec<String> =
+            crate::db::list_notes(&state.db(), None).unwrap().into_iter().map(|n| n.title).collect();
+        assert_eq!(titles, vec!["Kept"]);
+
+        let r = import_inner(&state, true).unwrap();
+        assert_eq!((r.items, r.full), (1, true));
+    }
+
+    #[tokio::test]
+    async fn export_commits_items_but_not_the_local_gitattributes() {
+        let (_dir, state) = crate::server::test_state();
+        let repo = state.inner.paths.repo_dir.clone();
+        {
+            let conn = state.db();
+            crate::db::upsert_note(&conn, &note("note-20260806-1432-aaa", "One")).unwrap();
+        }
+        let Json(r) = export(State(state.clone())).await.unwrap();
+        assert!(r.committed);
+        assert!(git::is_tracked(&repo, "notes/note-20260806-1432-aaa.yaml"));
+        assert!(repo.join(".gitattributes").exists());
+        assert!(!git::is_tracked(&repo, ".gitattributes"));
+    }
+
+    #[test]
+    fn edits_racing_a_sync_keep_the_merged_fields() {
+        let (dir, state) = crate::server::test_state();
+        let repo = state.inner.paths.repo_dir.clone();
+        let git = |cwd: &std::path::Path, args: &[&str]| {
+            let out = std::process::Command::new("git").arg("-C").arg(cwd).args(args).output().unwrap();
+            assert!(out.status.success(), "git {args:?}: {}", String::from_utf8_lossy(&out.stderr));
+        };
+        let origin = dir.path().join("origin.git");
+        git(dir.path(), &["init", "-q", "--bare", "-b", "master", origin.to_str().unwrap()]);
+        let id = "note-20260806-1432-aaa";
+        {
+            let conn = state.db();
+            crate::db::upsert_note(&conn, &note(id, "One")).unwrap();
+        }
+        crate::server::notes::persist_yaml(&state, Item::Note(note(id, "One"))).unwrap();
+        git(&repo, &["remote", "add", "origin", origin.to_str().unwrap()]);
+        git(&repo, &["push", "-q", "origin", "master"]);
+        let other = dir.path().join("other");
+        git(dir.path(), &["clone", "-q", origin.to_str().unwrap(), "other"]);
+        git(&other, &["config", "user.name", "ron"]);
+        git(&other, &["config", "user.email", "ron@localhost"]);
+
+        for round in 0..5 {
+            // The other clone tags the note while this server retitles it.
+            let tag = format!("t{round}");
+            let Item::Note(mut theirs) = crate::yaml::read_item(&other.join(format!("notes/{id}.yaml"))).unwrap()
+            else { panic!() };
+            theirs.tags.push(tag.clone());
+            crate::yaml::write_item(&other, &Item::Note(theirs)).unwrap();
+            git(&other, &["commit", "-q", "-am", "tag"]);
+            git(&other, &["push", "-q", "origin", "master"]);
+
+            let title = format!("Title {round}");
+            std::thread::scope(|s| {
+                s.spawn(|| {
+                    let body = crate::server::notes::UpdateBody {
+                        title: Some(title.clone()),
+                        tags: None,
+                        notebook: None,
+                        body: None,
+                        related: None,
+                        expected_updated: None,
+                    };
+                    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+                    rt.block_on(crate::server::notes::update_note_inner(&state, id, body)).unwrap();
+                });
+                s.spawn(|| sync_inner(&state, false).unwrap());
+            });
+
+            // Whichever ran first, both edits survive in the row and the file.
+            let row = crate::db::get_note(&state.db(), id).unwrap().unwrap();
+            let Item::Note(file) = crate::yaml::read_item(&repo.join(format!("notes/{id}.yaml"))).unwrap()
+            else { panic!() };
+            for n in [&row, &file] {
+                assert_eq!(n.title, title);
+                assert!(n.tags.contains(&tag), "round {round}: lost {tag} in {:?}", n.tags);
+            }
+            git(&repo, &["push", "-q", "origin", "master"]);
+            git(&other, &["pull", "-q", "--no-rebase", "origin", "master"]);
+        }
+    }
+
+    #[test]
+    fn remotes_are_configured_and_backed_up_to() {
+        let (dir, state) = crate::server::test_state();
+        crate::server::notes::persist_yaml(&state, Item::Note(note("note-20260806-1432-aaa", "One"))).unwrap();
+        let nas = dir.path().join("nas.git");
+        let out = std::process::Command::new("git").args(["init", "-q", "--bare"]).arg(&nas).output().unwrap();
+        assert!(out.status.success());
+
+        assert!(list_remotes_inner(&state).unwrap().is_empty());
+        assert!(matches!(add_remote_inner(&state, "-x", "u"), Err(ApiError::BadRequest(_))));
+        add_remote_inner(&state, "nas", nas.to_str().unwrap()).unwrap();
+        let remotes = list_remotes_inner(&state).unwrap();
+        assert_eq!(remotes.len(), 1);
+        assert!(!remotes[0].default);
+        // Saved for the next start.
+        let cfg = crate::paths::ServerConfig::load(&state.inner.paths).unwrap();
+        assert_eq!(cfg.backup_targets["nas"], nas.to_str().unwrap());
+
+        let r = backup_inner(&state, Some("nas"), false).unwrap();
+        assert!(r.pushed);
+        assert_eq!((r.remote.as_str(), r.branch.as_str()), ("nas", "master"));
+        assert!(git::resolve(&nas, "master").is_some());
+        assert!(matches!(backup_inner(&state, Some("usb"), false), Err(ApiError::BadRequest(_))));
+
+        remove_remote_inner(&state, "nas").unwrap();
+        assert!(list_remotes_inner(&state).unwrap().is_empty());
+        assert!(git::remote_url(&state.inner.paths.repo_dir, "nas").unwrap().is_none());
+        assert!(matches!(remove_remote_inner(&state, "nas"), Err(ApiError::NotFound)));
+    }
 }
@@ -8,7 +8,7 @@ use axum::Router;
 use tokio::net::TcpListener;
 
 use crate::paths::{Paths, ServerConfig};
-use crate::server::{admin, auth, drafts, metrics, notes, pulses, tokens, AppState};
+use crate::server::{admin, auth, drafts, graph, metrics, notebooks, notes, pulses, resources, searches, tags, tokens, trash, AppState};
 use crate::viewer;
 
 /// Build the full application router with the bearer-auth layer applied.
@@ -22,6 +22,12 @@ pub fn build(state: AppState) -> Router {
         .merge(drafts::routes())
         .merge(admin::routes())
         .merge(tokens::routes())
+        .merge(trash::routes())
+        .merge(resources::routes())
+        .merge(graph::routes())
+        .merge(tags::routes())
+        .merge(notebooks::routes())
+        .merge(searches::routes())
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth::require_token,
@@ -34,6 +40,7 @@ pub fn build(state: AppState) -> Router {
     let app = if state.inner.viewer_enabled {
         let viewer_routes = viewer::routes()
             .merge(drafts::viewer_routes())
+            .merge(resources::viewer_routes())
             .layer(middleware::from_fn_with_state(
                 state.clone(),
                 auth::require_viewer,
@@ -52,6 +59,7 @@ pub fn build(state: AppState) -> Router {
 pub async fn run(paths: Paths, cfg: ServerConfig) -> Result<()> {
     let state = AppState::new(paths.clone(), &cfg)?;
     state.load_tokens()?;
+    trash::spawn_purge_task(state.clone());
     let app = build(state);
     let addr: SocketAddr = cfg.listen.parse()?;
     eprintln!("ron listening on http://{addr}");
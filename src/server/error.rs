@@ -14,6 +14,9 @@ pub enum ApiError {
     BadRequest(String),
     #[error("unauthorized")]
     Unauthorized,
+    /// A write made against an outdated copy; carries the current one.
+    #[error("conflict: {0}")]
+    Conflict(String, serde_json::Value),
     #[error("internal error: {0}")]
     Internal(#[from] anyhow::Error),
 }
@@ -24,6 +27,10 @@ impl IntoResponse for ApiError {
             ApiError::NotFound => (StatusCode::NOT_FOUND, self.to_string()),
             ApiError::BadRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, self.to_string()),
+            ApiError::Conflict(_, current) => {
+                let body = Json(json!({ "error": self.to_string(), "current": current }));
+                return (StatusCode::CONFLICT, body).into_response();
+            }
             ApiError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
         };
         let body = Json(json!({ "error": msg }));
@@ -37,6 +44,12 @@ impl From<JsonRejection> for ApiError {
     }
 }
 
+impl From<crate::query::QueryError> for ApiError {
+    fn from(e: crate::query::QueryError) -> Self {
+        ApiError::BadRequest(e.to_string())
+    }
+}
+
 impl From<rusqlite::Error> for ApiError {
     fn from(e: rusqlite::Error) -> Self {
         ApiError::Internal(anyhow::anyhow!(e))
@@ -0,0 +1,185 @@
+//! The note network: every live note as a node, every link between two of
+//! them as an edge. Links are `related` IDs plus body references — note IDs
+//! mentioned in the text and `[[wiki links]]`. Backs `GET /api/graph` and
+//! the viewer's `/graph` page.
+
+use std::collections::{BTreeSet, HashMap};
+
+use axum::extract::{Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+use crate::server::error::ApiResult;
+use crate::server::AppState;
+use crate::wikilink::fold_title;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub title: String,
+    pub notebook: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    /// `related` when listed in the source's `related`, `body` for a
+    /// mention or wiki link in its text.
+    pub kind: String,
+}
+
+/// Reply of `GET /api/graph`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    /// Every notebook and tag in use, for filter pickers (unfiltered).
+    pub notebooks: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GraphParams {
+    /// Only notes in this notebook.
+    #[serde(default)]
+    pub notebook: Option<String>,
+    /// Only notes carrying this tag.
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+async fn graph(State(state): State<AppState>, Query(p): Query<GraphParams>) -> ApiResult<Json<Graph>> {
+    Ok(Json(graph_inner(&state, &p)?))
+}
+
+/// Build the graph, keeping only notes that pass the filters and edges
+/// whose ends both survive. One edge per ordered pair; `related` wins over
+/// `body` when a note does both.
+pub fn graph_inner(state: &AppState, p: &GraphParams) -> ApiResult<Graph> {
+    let notes = {
+        let conn = state.db();
+        db::list_notes(&conn, None)?
+    };
+    let notebooks: BTreeSet<&str> = notes.iter().map(|n| n.notebook.as_str()).collect();
+    let tags: BTreeSet<&str> = notes.iter().flat_map(|n| n.tags.iter().map(String::as_str)).collect();
+
+    // Newest first, so the first title seen is the one `[[Title]]` resolves to.
+    let mut by_title: HashMap<String, &str> = HashMap::new();
+    for n in &notes {
+        by_title.entry(fold_title(&n.title)).or_insert(&n.id);
+    }
+    let notebook = p.notebook.as_deref().filter(|s| !s.is_empty());
+    let tag = p.tag.as_deref().filter(|s| !s.is_empty());
+    let kept: Vec<_> = notes
+        .iter()
+        .filter(|n| notebook.is_none_or(|nb| n.notebook == nb))
+        .filter(|n| tag.is_none_or(|t| n.tags.iter().any(|x| x == t)))
+        .collect();
+    let kept_ids: BTreeSet<&str> = kept.iter().map(|n| n.id.as_str()).collect();
+
+    let mut edges: Vec<GraphEdge> = Vec::new();
+    for n in &kept {
+        let mut push = |target: &str, kind: &str| {
+            if target == n.id || !kept_ids.contains(target) {
+                return;
+            }
+            if !edges.iter().any(|e| e.source == n.id && e.target == target) {
+                edges.push(GraphEdge {
+                    source: n.id.clone(),
+                    target: target.to_string(),
+                    kind: kind.to_string(),
+                });
+            }
+        };
+        for r in &n.related {
+            push(r, "related");
+        }
+        for m in crate::id::note_ids_in(&n.body) {
+            push(&m, "body");
+        }
+        for link in crate::wikilink::parse(&n.body) {
+            if let Some(id) = by_title.get(&fold_title(&link.target)) {
+                push(id, "body");
+            }
+        }
+    }
+
+    Ok(Graph {
+        nodes: kept
+            .into_iter()
+            .map(|n| GraphNode {
+                id: n.id.clone(),
+                title: n.title.clone(),
+                notebook: n.notebook.clone(),
+                tags: n.tags.clone(),
+            })
+            .collect(),
+        edges,
+        notebooks: notebooks.into_iter().map(str::to_string).collect(),
+        tags: tags.into_iter().map(str::to_string).collect(),
+    })
+}
+
+pub fn routes() -> axum::Router<AppState> {
+    axum::Router::new().route("/api/graph", axum::routing::get(graph))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Note;
+
+    #[test]
+    fn edges_follow_related_mentions_and_wiki_links_within_the_filter() {
+        let (_dir, state) = crate::server::test_state();
+        let now = chrono::Local::now().naive_local();
+        let note = |id: &str, title: &str, nb: &str, tags: &[&str], body: &str| {
+            Note::new(
+                id.into(),
+                title.into(),
+                tags.iter().map(|t| t.to_string()).collect(),
+                nb.into(),
+                now,
+                now,
+                body.into(),
+            )
+        };
+        let a_id = "note-20260101-0000-00a";
+        let b_id = "note-20260101-0000-00b";
+        let c_id = "note-20260101-0000-00c";
+        let mut a = note(a_id, "A", "work", &["x"], &format!("see {b_id} and [[c]]"));
+        a.related = vec![b_id.into(), "note-20000101-0000-fff".into()];
+        let b = note(b_id, "B", "work", &[], "[[A]]");
+        let c = note(c_id, "C", "home", &["x"], "");
+        {
+            let conn = state.db();
+            for n in [&a, &b, &c] {
+                db::upsert_note(&conn, n).unwrap();
+            }
+        }
+
+        let g = graph_inner(&state, &GraphParams::default()).unwrap();
+        assert_eq!(g.nodes.len(), 3);
+        let edges: Vec<(&str, &str, &str)> = g
+            .edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str(), e.kind.as_str()))
+            .collect();
+        assert_eq!(edges.len(), 3);
+        assert!(edges.contains(&(a_id, b_id, "related")));
+        assert!(edges.contains(&(a_id, c_id, "body")));
+        assert!(edges.contains(&(b_id, a_id, "body")));
+        assert_eq!(g.notebooks, ["home", "work"]);
+        assert_eq!(g.tags, ["x"]);
+
+        let by_nb = graph_inner(&state, &GraphParams { notebook: Some("work".into()), tag: None }).unwrap();
+        assert_eq!(by_nb.nodes.len(), 2);
+        assert_eq!(by_nb.edges.len(), 2);
+        let by_tag = graph_inner(&state, &GraphParams { notebook: None, tag: Some("x".into()) }).unwrap();
+        assert_eq!(by_tag.nodes.len(), 2);
+        assert_eq!(by_tag.edges.len(), 1);
+    }
+}
@@ -9,7 +9,7 @@ use crate::db;
 use crate::id::{new_id, Kind};
 use crate::models::{Metric, MetricPoint};
 use crate::server::error::{ApiError, ApiResult};
-use crate::server::notes::{delete_yaml, persist_yaml};
+use crate::server::notes::{persist_yaml, persist_yaml_with_message};
 use crate::server::AppState;
 
 async fn list(State(state): State<AppState>) -> ApiResult<Json<Vec<Metric>>> {
@@ -48,8 +48,8 @@ pub async fn create_metric_inner(state: &AppState, body: CreateBody) -> ApiResul
     {
         let conn = state.db();
         db::upsert_metric(&conn, &metric)?;
+        persist_yaml(state, crate::yaml::Item::Metric(metric.clone()))?;
     }
-    persist_yaml(state, crate::yaml::Item::Metric(metric.clone()))?;
     Ok(metric)
 }
 
@@ -73,20 +73,15 @@ pub async fn update_metric_inner(
     id: &str,
     body: UpdateBody,
 ) -> ApiResult<Metric> {
-    let mut metric = {
-        let conn = state.db();
-        db::get_metric(&conn, id)?.ok_or(ApiError::NotFound)?
-    };
+    let conn = state.db();
+    let mut metric = db::get_metric(&conn, id)?.ok_or(ApiError::NotFound)?;
     if let Some(t) = body.topic {
         if t.trim().is_empty() {
             return Err(ApiError::BadRequest("topic must not be empty".into()));
         }
         metric.topic = t;
     }
-    {
-        let conn = state.db();
-        db::upsert_metric(&conn, &metric)?;
-    }
+    db::upsert_metric(&conn, &metric)?;
     persist_yaml(state, crate::yaml::Item::Metric(metric.clone()))?;
     Ok(metric)
 }
@@ -112,16 +107,11 @@ pub async fn append_point_inner(
     id: &str,
     body: AppendBody,
 ) -> ApiResult<Metric> {
-    let mut metric = {
-        let conn = state.db();
-        db::get_metric(&conn, id)?.ok_or(ApiError::NotFound)?
-    };
+    let conn = state.db();
+    let mut metric = db::get_metric(&conn, id)?.ok_or(ApiError::NotFound)?;
     let ts = body.ts.unwrap_or_else(|| Local::now().naive_local());
     metric.append(ts, body.value);
-    {
-        let conn = state.db();
-        db::upsert_metric(&conn, &metric)?;
-    }
+    db::upsert_metric(&conn, &metric)?;
     persist_yaml(state, crate::yaml::Item::Metric(metric.clone()))?;
     Ok(metric)
 }
@@ -158,8 +148,8 @@ async fn stats(
     let points: Vec<MetricPoint> = metric
         .sorted_points()
         .into_iter()
-        .filter(|p| q.from.map_or(true, |f| p.ts >= f))
-        .filter(|p| q.to.map_or(true, |t| p.ts <= t))
+        .filter(|p| q.from.is_none_or(|f| p.ts >= f))
+        .filter(|p| q.to.is_none_or(|t| p.ts <= t))
         .cloned()
         .collect();
     Ok(Json(StatsResponse {
@@ -184,16 +174,15 @@ async fn delete(
     Ok(Json(serde_json::json!({ "ok": true })))
 }
 
-/// Shared delete logic (used by the JSON API and the viewer's delete form).
+/// Shared delete logic (used by the JSON API and the viewer's delete form):
+/// moves the metric to the trash.
 pub async fn delete_metric_inner(state: &AppState, id: &str) -> ApiResult<bool> {
-    let removed = {
-        let conn = state.db();
-        db::delete_metric(&conn, id)?
-    };
-    if removed {
-        delete_yaml(state, id)?;
-    }
-    Ok(removed)
+    let conn = state.db();
+    let Some(mut metric) = db::get_metric(&conn, id)? else { return Ok(false) };
+    metric.deleted_at = Some(Local::now().naive_local());
+    db::upsert_metric(&conn, &metric)?;
+    persist_yaml_with_message(state, crate::yaml::Item::Metric(metric), &format!("trash: {id}"))?;
+    Ok(true)
 }
 
 pub fn routes() -> axum::Router<AppState> {
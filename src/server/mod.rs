@@ -5,10 +5,16 @@ pub mod app;
 pub mod auth;
 pub mod drafts;
 pub mod error;
+pub mod graph;
 pub mod metrics;
+pub mod notebooks;
 pub mod notes;
 pub mod pulses;
+pub mod resources;
+pub mod searches;
+pub mod tags;
 pub mod tokens;
+pub mod trash;
 
 use std::sync::Arc;
 
@@ -27,6 +33,8 @@ pub struct AppState {
 pub struct Inner {
     pub paths: Paths,
     pub db: std::sync::Mutex<Connection>,
+    /// Guards the git worktree; see [`AppState::repo`].
+    pub repo: std::sync::Mutex<()>,
     pub tokens: std::sync::RwLock<TokenStore>,
     /// Optional passphrase gating the browser viewer. `None` = open viewer
     /// (historical behaviour); `Some` = cookie-gated. See docs/phone-access.md.
@@ -35,6 +43,14 @@ pub struct Inner {
     pub default_notebook: String,
     /// Serve the browser viewer (HTML routes)? `false` = API-only server.
     pub viewer_enabled: bool,
+    /// Days before trashed items are purged; `0` = never.
+    pub trash_retention_days: u32,
+    /// Default git remote and branch for backup/sync.
+    pub remote: String,
+    pub branch: String,
+    /// Named remotes (name -> URL); changed by `/api/remotes` and written
+    /// back to `server.json`.
+    pub backup_targets: std::sync::RwLock<std::collections::BTreeMap<String, String>>,
 }
 
 impl AppState {
@@ -42,7 +58,7 @@ impl AppState {
         // Ensure the git repo exists before opening the DB; .gitignore below
         // keeps the SQLite store (which lives *outside* the repo anyway) from
         // being tracked if it's ever moved in.
-        crate::git::ensure_repo(&paths.repo_dir)?;
+        crate::git::ensure_repo_on(&paths.repo_dir, &cfg.branch)?;
         write_gitignore(&paths.repo_dir)?;
         migrate_flat_layout(&paths.repo_dir)?;
         let conn = crate::db::open(&paths.db_path)
@@ -54,10 +70,15 @@ impl AppState {
             inner: Arc::new(Inner {
                 paths,
                 db: std::sync::Mutex::new(conn),
+                repo: std::sync::Mutex::new(()),
                 tokens: std::sync::RwLock::new(TokenStore::default()),
                 viewer_secret: cfg.viewer_secret.clone(),
                 default_notebook: cfg.default_notebook.clone(),
                 viewer_enabled: cfg.viewer,
+                trash_retention_days: cfg.trash_retention_days,
+                remote: cfg.remote.clone(),
+                branch: cfg.branch.clone(),
+                backup_targets: std::sync::RwLock::new(cfg.backup_targets.clone()),
             }),
         })
     }
@@ -73,19 +94,48 @@ impl AppState {
         store.save(&self.inner.paths.tokens_file)
     }
 
+    /// Write the current backup targets to `server.json`, keeping whatever
+    /// else is in the file (it may have been edited since startup).
+    pub fn save_backup_targets(&self) -> Result<()> {
+        let mut cfg = crate::paths::ServerConfig::load(&self.inner.paths)?;
+        cfg.backup_targets = self.inner.backup_targets.read().unwrap().clone();
+        cfg.save(&self.inner.paths)
+    }
+
     /// Lock the DB connection. Panics if poisoned.
     pub fn db(&self) -> std::sync::MutexGuard<'_, Connection> {
         self.inner.db.lock().unwrap()
     }
+
+    /// Lock the git worktree: held from writing files in the repo through
+    /// committing them, and across a whole `sync`, so a commit never lands
+    /// mid-merge or stages another request's half-written files. It may be
+    /// taken while holding [`db`](Self::db), never the other way round;
+    /// item writes and `sync` take both, so a write can't straddle a sync.
+    /// Panics if poisoned.
+    pub fn repo(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.inner.repo.lock().unwrap()
+    }
 }
 
 /// Add a `.gitignore` that excludes the SQLite store and other transient
-/// files if they ever end up under the repo dir.
+/// files if they ever end up under the repo dir, plus the machine-local
+/// `.gitattributes` (see `git::ensure_repo`). Lines missing from an
+/// existing file are appended; anything added by hand stays.
 fn write_gitignore(repo_dir: &std::path::Path) -> Result<()> {
     let path = repo_dir.join(".gitignore");
-    const CONTENT: &str = "*.sqlite*\n*.db*\n.wal\n.shm\n";
-    if !path.exists() {
-        std::fs::write(&path, CONTENT).ok();
+    const LINES: [&str; 5] = ["*.sqlite*", "*.db*", ".wal", ".shm", ".gitattributes"];
+    let mut text = std::fs::read_to_string(&path).unwrap_or_default();
+    let missing: Vec<&str> = LINES.into_iter().filter(|line| !text.lines().any(|l| l.trim() == *line)).collect();
+    if !missing.is_empty() {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        for line in missing {
+            text.push_str(line);
+            text.push('\n');
+        }
+        std::fs::write(&path, text).ok();
     }
     Ok(())
 }
@@ -153,32 +203,187 @@ pub fn bootstrap_from_yaml(conn: &Connection, repo_dir: &std::path::Path) -> Res
     }
     let tx = conn.unchecked_transaction()?;
     for item in items {
-        match item {
-            crate::yaml::Item::Note(n) => crate::db::upsert_note(&tx, &n)?,
-            crate::yaml::Item::Pulse(p) => crate::db::upsert_pulse(&tx, &p)?,
-            crate::yaml::Item::Metric(m) => crate::db::upsert_metric(&tx, &m)?,
-        }
+        load_item(&tx, &item)?;
     }
+    mark_loaded(&tx, repo_dir)?;
     tx.commit()?;
     Ok(())
 }
 
 /// Drop every row from every data table, then reload from YAML. Used by
-/// `import` and `sync` after the YAML has been refreshed from disk.
+/// `import --full`, and by `import` when there's no recorded commit to
+/// refresh from (see [`changed_since_load`]).
 pub fn rebuild_db_from_yaml(conn: &Connection, repo_dir: &std::path::Path) -> Result<usize> {
     let items = crate::yaml::read_all(repo_dir).unwrap_or_default();
     let n = items.len();
     let tx = conn.unchecked_transaction()?;
     tx.execute("DELETE FROM notes", [])?;
+    tx.execute("DELETE FROM notes_fts", [])?;
     tx.execute("DELETE FROM pulses", [])?;
     tx.execute("DELETE FROM metrics", [])?;
     for item in items {
-        match item {
-            crate::yaml::Item::Note(n) => crate::db::upsert_note(&tx, &n)?,
-            crate::yaml::Item::Pulse(p) => crate::db::upsert_pulse(&tx, &p)?,
-            crate::yaml::Item::Metric(m) => crate::db::upsert_metric(&tx, &m)?,
-        }
+        load_item(&tx, &item)?;
     }
+    mark_loaded(&tx, repo_dir)?;
     tx.commit()?;
     Ok(n)
-}
\ No newline at end of file
+}
+
+/// `meta` key holding the repo commit the DB last loaded all its items
+/// from (a bootstrap, rebuild or import).
+const YAML_HEAD: &str = "yaml_head";
+
+/// `meta` key holding the uncommitted paths (a JSON array) that load read
+/// on top of `yaml_head`. Their rows came from worktree files that a
+/// checkout or reset may put back without leaving a diff to find them by.
+const YAML_DIRTY: &str = "yaml_dirty";
+
+/// Rows touched by [`refresh_db_from_yaml`].
+#[derive(Debug, Default, PartialEq)]
+pub struct Refresh {
+    /// Items (re)loaded from their YAML file.
+    pub loaded: usize,
+    /// Rows dropped because their file is gone.
+    pub removed: usize,
+}
+
+/// Apply just `paths` (repo-relative) to the DB, in one transaction: item
+/// YAML that exists is (re)loaded, item YAML that's gone has its row
+/// dropped, and anything else (saved searches, attachments) is ignored.
+/// Files that don't parse are skipped with a warning, as in a rebuild.
+pub fn refresh_db_from_yaml(conn: &Connection, repo_dir: &std::path::Path, paths: &[String]) -> Result<Refresh> {
+    let mut refresh = Refresh::default();
+    let items: Vec<(&str, std::path::PathBuf)> = paths
+        .iter()
+        .filter_map(|p| item_id(p).map(|id| (id, repo_dir.join(p))))
+        .collect();
+    let tx = conn.unchecked_transaction()?;
+    // Removals first, so an item that moved (flat layout -> subdir) ends up
+    // loaded from its new file.
+    for (id, _) in items.iter().filter(|(_, path)| !path.exists()) {
+        let dropped = if id.starts_with("note-") {
+            crate::db::delete_note(&tx, id)?
+        } else if id.starts_with("pulse-") {
+            crate::db::delete_pulse(&tx, id)?
+        } else {
+            crate::db::delete_metric(&tx, id)?
+        };
+        if dropped {
+            refresh.removed += 1;
+        }
+    }
+    for (_, path) in items.iter().filter(|(_, path)| path.exists()) {
+        match crate::yaml::read_item(path) {
+            Ok(item) => {
+                load_item(&tx, &item)?;
+                refresh.loaded += 1;
+            }
+            Err(e) => eprintln!("skip {}: {e:#}", path.display()),
+        }
+    }
+    tx.commit()?;
+    Ok(refresh)
+}
+
+/// Item files that may differ from the DB: those changed between the commit
+/// the DB was last loaded from and HEAD, plus uncommitted edits, now or at
+/// that load. `None` when there's no such commit to diff from (never
+/// recorded, or gone after a history rewrite) and only a full rebuild will
+/// do.
+pub fn changed_since_load(conn: &Connection, repo_dir: &std::path::Path) -> Result<Option<Vec<String>>> {
+    let Some(loaded) = crate::db::get_meta(conn, YAML_HEAD)? else {
+        return Ok(None);
+    };
+    if crate::git::resolve(repo_dir, &loaded).is_none() {
+        return Ok(None);
+    }
+    let mut paths = crate::git::changed_paths(repo_dir, &loaded, "HEAD")?;
+    let was_dirty: Vec<String> = match crate::db::get_meta(conn, YAML_DIRTY)? {
+        Some(json) => serde_json::from_str(&json).unwrap_or_default(),
+        None => Vec::new(),
+    };
+    for p in was_dirty.into_iter().chain(crate::git::worktree_changes(repo_dir)?) {
+        if !paths.contains(&p) {
+            paths.push(p);
+        }
+    }
+    Ok(Some(paths))
+}
+
+/// Record HEAD as the commit the DB now reflects, along with the
+/// uncommitted paths it was loaded with, so the next refresh re-reads them
+/// even once they're clean again.
+pub fn mark_loaded(conn: &Connection, repo_dir: &std::path::Path) -> Result<()> {
+    if let Some(head) = crate::git::resolve(repo_dir, "HEAD") {
+        let dirty = crate::git::worktree_changes(repo_dir)?;
+        crate::db::set_meta(conn, YAML_HEAD, &head)?;
+        crate::db::set_meta(conn, YAML_DIRTY, &serde_json::to_string(&dirty)?)?;
+    }
+    Ok(())
+}
+
+fn load_item(conn: &Connection, item: &crate::yaml::Item) -> Result<()> {
+    match item {
+        crate::yaml::Item::Note(n) => crate::db::upsert_note(conn, n),
+        crate::yaml::Item::Pulse(p) => crate::db::upsert_pulse(conn, p),
+        crate::yaml::Item::Metric(m) => crate::db::upsert_metric(conn, m),
+    }
+}
+
+/// The item id a repo-relative path holds, if it's item YAML: `<id>.yaml`
+/// in a type subdirectory or (legacy flat layout) the repo root.
+fn item_id(path: &str) -> Option<&str> {
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let id = name.strip_suffix(".yaml")?;
+    let sub = crate::yaml::subdir_for_id(id)?;
+    (dir.is_empty() || dir == sub).then_some(id)
+}
+
+/// AppState over a temp dir with a committing git identity. The TempDir is
+/// returned so the repo outlives the test body.
+#[cfg(test)]
+pub(crate) fn test_state() -> (tempfile::TempDir, AppState) {
+    test_state_with(&crate::paths::ServerConfig::default())
+}
+
+#[cfg(test)]
+pub(crate) fn test_state_with(cfg: &crate::paths::ServerConfig) -> (tempfile::TempDir, AppState) {
+    let dir = tempfile::tempdir().unwrap();
+    let app = dir.path().join("data");
+    let config = dir.path().join("config");
+    std::fs::create_dir_all(app.join("repo")).unwrap();
+    std::fs::create_dir_all(&config).unwrap();
+    let state = AppState::new(
+        Paths {
+            db_path: app.join("db.sqlite3"),
+            repo_dir: app.join("repo"),
+            server_config: config.join("server.json"),
+            tokens_file: config.join("tokens.json"),
+            app_home: app.clone(),
+            config_dir: config,
+        },
+        cfg,
+    )
+    .unwrap();
+    for (k, v) in [("user.name", "ron"), ("user.email", "ron@localhost")] {
+        std::process::Command::new("git")
+            .arg("-C")
+            .arg(app.join("repo"))
+            .args(["config", k, v])
+            .status()
+            .unwrap();
+    }
+    (dir, state)
+}
+
+/// Subject of the repo's latest commit.
+#[cfg(test)]
+pub(crate) fn last_commit(state: &AppState) -> String {
+    let out = std::process::Command::new("git")
+        .arg("-C")
+        .arg(&state.inner.paths.repo_dir)
+        .args(["log", "-1", "--format=%s"])
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&out.stdout).trim().to_string()
+}
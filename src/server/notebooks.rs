@@ -0,0 +1,357 @@
+//! Notebooks across all notes: counts, bulk rename / merge, and moving
+//! notes between notebooks. Like the tag edits, every bulk operation
+//! rewrites the affected notes (trashed ones too) in one DB transaction and
+//! one commit.
+//!
+//! Notebook (and tag) names are slash-separated paths, `work/clients/acme`:
+//! [`path_tree`] nests them with rolled-up counts, and a rename or merge
+//! carries the sub-notebooks along.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use axum::extract::State;
+use axum::Json;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+use crate::models::Note;
+use crate::server::error::{ApiError, ApiResult};
+use crate::server::notes::persist_notes_with_message;
+use crate::server::AppState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotebookCount {
+    pub notebook: String,
+    /// Live notes in the notebook.
+    pub count: u32,
+}
+
+/// One level of a notebook or tag hierarchy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeNode {
+    /// Last path segment.
+    pub name: String,
+    /// Full path, e.g. `work/clients`.
+    pub path: String,
+    /// Live notes filed exactly here.
+    pub count: u32,
+    /// Distinct live notes here or anywhere below.
+    pub total: u32,
+    pub children: Vec<TreeNode>,
+}
+
+/// Nest `(note id, path)` pairs into a tree, sorted by name at each level.
+/// Intermediate levels nobody uses directly still appear (with `count` 0).
+/// A note counts once per level even when several of its paths fall under
+/// it (tags `lang/rust` and `lang/go` make one note in `lang`).
+pub fn path_tree<'a>(items: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<TreeNode> {
+    let mut own: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+    let mut all: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+    for (id, path) in items {
+        let segs: Vec<&str> = path.split('/').map(str::trim).filter(|s| !s.is_empty()).collect();
+        if segs.is_empty() {
+            continue;
+        }
+        own.entry(segs.join("/")).or_default().insert(id);
+        for depth in 1..=segs.len() {
+            all.entry(segs[..depth].join("/")).or_default().insert(id);
+        }
+    }
+    fn level(parent: Option<&str>, own: &BTreeMap<String, BTreeSet<&str>>, all: &BTreeMap<String, BTreeSet<&str>>) -> Vec<TreeNode> {
+        all.iter()
+            .filter(|(path, _)| match parent {
+                None => !path.contains('/'),
+                Some(p) => path
+                    .strip_prefix(p)
+                    .and_then(|rest| rest.strip_prefix('/'))
+                    .is_some_and(|rest| !rest.contains('/')),
+            })
+            .map(|(path, ids)| TreeNode {
+                name: path.rsplit('/').next().unwrap_or(path).to_string(),
+                path: path.clone(),
+                count: own.get(path).map_or(0, |s| s.len() as u32),
+                total: ids.len() as u32,
+                children: level(Some(path), own, all),
+            })
+            .collect()
+    }
+    level(None, &own, &all)
+}
+
+/// `value` with its `from` prefix swapped for `to` (`work/x` -> `job/x` for
+/// `work` -> `job`); `None` when `value` isn't `from` or below it.
+pub fn reparent(value: &str, from: &str, to: &str) -> Option<String> {
+    if value == from {
+        Some(to.to_string())
+    } else {
+        value
+            .strip_prefix(from)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .map(|rest| format!("{to}/{rest}"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenameBody {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeBody {
+    pub from: String,
+    pub into: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MoveBody {
+    pub ids: Vec<String>,
+    pub notebook: String,
+}
+
+/// Reply of the bulk endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotebookEdit {
+    /// IDs of the rewritten notes.
+    pub notes: Vec<String>,
+}
+
+async fn list(State(state): State<AppState>) -> ApiResult<Json<Vec<NotebookCount>>> {
+    Ok(Json(list_inner(&state)?))
+}
+
+pub fn list_inner(state: &AppState) -> ApiResult<Vec<NotebookCount>> {
+    let conn = state.db();
+    Ok(db::notebook_counts(&conn)?
+        .into_iter()
+        .map(|(notebook, count)| NotebookCount { notebook, count })
+        .collect())
+}
+
+async fn tree(State(state): State<AppState>) -> ApiResult<Json<Vec<TreeNode>>> {
+    Ok(Json(tree_inner(&state)?))
+}
+
+/// The notebook hierarchy of the live notes.
+pub fn tree_inner(state: &AppState) -> ApiResult<Vec<TreeNode>> {
+    let notes = {
+        let conn = state.db();
+        db::list_notes(&conn, None)?
+    };
+    Ok(tree_of(&notes))
+}
+
+/// The notebook hierarchy of `notes`.
+pub fn tree_of(notes: &[Note]) -> Vec<TreeNode> {
+    path_tree(notes.iter().map(|n| (n.id.as_str(), n.notebook.as_str())))
+}
+
+async fn rename(
+    State(state): State<AppState>,
+    Json(b): Json<RenameBody>,
+) -> ApiResult<Json<NotebookEdit>> {
+    Ok(Json(rename_inner(&state, &b.from, &b.to)?))
+}
+
+/// Rename a notebook and its sub-notebooks. Refuses when `to` already has
+/// notes — that's a merge.
+pub fn rename_inner(state: &AppState, from: &str, to: &str) -> ApiResult<NotebookEdit> {
+    let to = clean(to)?;
+    let taken = {
+        let conn = state.db();
+        !db::notes_in_notebook(&conn, &to)?.is_empty()
+    };
+    if taken && to != from {
+        return Err(ApiError::BadRequest(format!(
+            "notebook {to} already exists; merge into it instead"
+        )));
+    }
+    relocate(state, from, &to, &format!("notebooks: rename {from} -> {to}"))
+}
+
+async fn merge(
+    State(state): State<AppState>,
+    Json(b): Json<MergeBody>,
+) -> ApiResult<Json<NotebookEdit>> {
+    Ok(Json(merge_inner(&state, &b.from, &b.into)?))
+}
+
+/// Move every note of `from` into `into`, sub-notebooks keeping their
+/// place below it (`a/x` merged into `b` becomes `b/x`).
+pub fn merge_inner(state: &AppState, from: &str, into: &str) -> ApiResult<NotebookEdit> {
+    let into = clean(into)?;
+    relocate(state, from, &into, &format!("notebooks: merge {from} into {into}"))
+}
+
+async fn move_notes(
+    State(state): State<AppState>,
+    Json(b): Json<MoveBody>,
+) -> ApiResult<Json<NotebookEdit>> {
+    Ok(Json(move_inner(&state, &b.ids, &b.notebook)?))
+}
+
+/// Put the given notes into `notebook`. Every ID must be a live note;
+/// notes already there are left alone.
+pub fn move_inner(state: &AppState, ids: &[String], notebook: &str) -> ApiResult<NotebookEdit> {
+    let notebook = clean(notebook)?;
+    if ids.is_empty() {
+        return Err(ApiError::BadRequest("no notes to move".into()));
+    }
+    let mut conn = state.db();
+    let mut notes: Vec<Note> = Vec::new();
+    for id in ids {
+        let n = db::get_note(&conn, id)?.ok_or(ApiError::NotFound)?;
+        if n.notebook != notebook && !notes.iter().any(|m| m.id == n.id) {
+            notes.push(n);
+        }
+    }
+    let msg = match notes.as_slice() {
+        [one] => format!("move: {} to {notebook}", one.id),
+        many => format!("move: {} notes to {notebook}", many.len()),
+    };
+    save(state, &mut conn, notes, |_| notebook.clone(), &msg)
+}
+
+fn clean(notebook: &str) -> ApiResult<String> {
+    let notebook = notebook.trim();
+    if notebook.is_empty() {
+        return Err(ApiError::BadRequest("notebook must not be empty".into()));
+    }
+    Ok(notebook.to_string())
+}
+
+/// Move all of `from` (and below) under `to`; an empty `from` is a 404 and
+/// `to == from` a 400, since it would rewrite every note for nothing.
+fn relocate(state: &AppState, from: &str, to: &str, msg: &str) -> ApiResult<NotebookEdit> {
+    if from == to {
+        return Err(ApiError::BadRequest(format!("notebook {from} is already {to}")));
+    }
+    let mut conn = state.db();
+    let notes = db::notes_in_notebook(&conn, from)?;
+    if notes.is_empty() {
+        return Err(ApiError::NotFound);
+    }
+    let msg = format!("{msg} ({} notes)", notes.len());
+    save(state, &mut conn, notes, |nb| reparent(nb, from, to).unwrap_or_else(|| to.to_string()), &msg)
+}
+
+fn save(
+    state: &AppState,
+    conn: &mut rusqlite::Connection,
+    mut notes: Vec<Note>,
+    notebook: impl Fn(&str) -> String,
+    msg: &str,
+) -> ApiResult<NotebookEdit> {
+    let now = Local::now().naive_local();
+    for n in &mut notes {
+        n.notebook = notebook(&n.notebook);
+        n.updated = now;
+    }
+    persist_notes_with_message(state, conn, &notes, msg)?;
+    Ok(NotebookEdit {
+        notes: notes.into_iter().map(|n| n.id).collect(),
+    })
+}
+
+pub fn routes() -> axum::Router<AppState> {
+    use axum::routing::{get, post};
+    axum::Router::new()
+        .route("/api/notebooks", get(list))
+        .route("/api/notebooks/tree", get(tree))
+        .route("/api/notebooks/rename", post(rename))
+        .route("/api/notebooks/merge", post(merge))
+        .route("/api/notes/move", post(move_notes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(state: &AppState) -> Vec<String> {
+        let now = Local::now().naive_local();
+        let mut ids = Vec::new();
+        let conn = state.db();
+        for (i, nb) in ["work", "work", "home"].iter().enumerate() {
+            let id = format!("note-20260101-0000-00{i}");
+            let n = Note::new(id.clone(), format!("n{i}"), vec![], nb.to_string(), now, now, String::new());
+            db::upsert_note(&conn, &n).unwrap();
+            ids.push(id);
+        }
+        ids
+    }
+
+    fn notebook_of(state: &AppState, id: &str) -> String {
+        let conn = state.db();
+        db::get_note(&conn, id).unwrap().unwrap().notebook
+    }
+
+    #[test]
+    fn path_tree_rolls_counts_up() {
+        let tree = path_tree([
+            ("n1", "work"),
+            ("n2", "work/clients/acme"),
+            ("n3", "work/clients/beta"),
+            ("n4", "home"),
+            ("n4", "home/"),
+            ("n5", ""),
+        ]);
+        assert_eq!(tree.len(), 2);
+        assert_eq!((tree[0].path.as_str(), tree[0].count, tree[0].total), ("home", 1, 1));
+        let work = &tree[1];
+        assert_eq!((work.count, work.total), (1, 3));
+        let clients = &work.children[0];
+        assert_eq!((clients.name.as_str(), clients.path.as_str()), ("clients", "work/clients"));
+        assert_eq!((clients.count, clients.total), (0, 2));
+        assert_eq!(clients.children.len(), 2);
+        assert_eq!(clients.children[1].path, "work/clients/beta");
+
+        assert_eq!(reparent("work/x/y", "work", "job").as_deref(), Some("job/x/y"));
+        assert_eq!(reparent("work", "work", "job").as_deref(), Some("job"));
+        assert_eq!(reparent("workshop", "work", "job"), None);
+    }
+
+    #[test]
+    fn rename_merge_and_move_rewrite_notes() {
+        let (_dir, state) = crate::server::test_state();
+        let ids = seed(&state);
+        let counts: Vec<(String, u32)> = list_inner(&state)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.notebook, c.count))
+            .collect();
+        assert_eq!(counts, [("home".into(), 1), ("work".into(), 2)]);
+
+        assert!(matches!(rename_inner(&state, "work", "home"), Err(ApiError::BadRequest(_))));
+        // Onto itself: refused before any note is rewritten.
+        let head = crate::server::last_commit(&state);
+        assert!(matches!(rename_inner(&state, "work", " work "), Err(ApiError::BadRequest(_))));
+        assert!(matches!(merge_inner(&state, "work", "work"), Err(ApiError::BadRequest(_))));
+        assert_eq!(crate::server::last_commit(&state), head);
+        assert_eq!(rename_inner(&state, "work", "job").unwrap().notes.len(), 2);
+        assert_eq!(notebook_of(&state, &ids[0]), "job");
+        assert!(matches!(rename_inner(&state, "work", "x"), Err(ApiError::NotFound)));
+
+        assert_eq!(merge_inner(&state, "home", "job").unwrap().notes, [ids[2].as_str()]);
+        assert_eq!(list_inner(&state).unwrap().len(), 1);
+
+        // Sub-notebooks travel with a rename.
+        move_inner(&state, &[ids[2].clone()], "job/sub").unwrap();
+        rename_inner(&state, "job", "work").unwrap();
+        assert_eq!(notebook_of(&state, &ids[2]), "work/sub");
+        assert_eq!(tree_inner(&state).unwrap()[0].total, 3);
+
+        let moved = move_inner(&state, &[ids[0].clone(), ids[1].clone()], "archive").unwrap();
+        assert_eq!(moved.notes.len(), 2);
+        assert_eq!(notebook_of(&state, &ids[1]), "archive");
+        let yaml = std::fs::read_to_string(
+            state.inner.paths.repo_dir.join(crate::yaml::rel_path(&ids[1]).unwrap()),
+        )
+        .unwrap();
+        assert!(yaml.contains("archive"));
+        assert!(matches!(
+            move_inner(&state, &["note-20000101-0000-fff".into()], "x"),
+            Err(ApiError::NotFound)
+        ));
+    }
+}
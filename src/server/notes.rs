@@ -1,28 +1,82 @@
 //! REST API for Notes.
 
 use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::response::IntoResponse;
 use axum::Json;
-use chrono::Local;
-use serde::Deserialize;
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
 
 use crate::db;
 use crate::id::{new_id, Kind};
 use crate::models::Note;
 use crate::server::error::{ApiError, ApiResult};
 use crate::server::AppState;
+use crate::snippet::{Highlighter, SearchHit};
 use crate::yaml;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct ListParams {
     pub limit: Option<u32>,
+    /// Cursor from the previous page's `Link` header: `<key>,<id>`.
+    #[serde(default)]
+    pub after: Option<String>,
+    /// updated (default) | created | title.
+    #[serde(default)]
+    pub order: Option<SearchOrder>,
 }
 
-async fn list(State(state): State<AppState>, Query(p): Query<ListParams>) -> ApiResult<Json<Vec<Note>>> {
-    let notes = {
-        let conn = state.db();
-        db::list_notes(&conn, p.limit)?
+/// One page of notes, with the live note count and the cursor of the next
+/// page (`None` on the last).
+#[derive(Debug)]
+pub struct NotePage {
+    pub notes: Vec<Note>,
+    pub total: usize,
+    pub next: Option<db::Cursor>,
+}
+
+async fn list(State(state): State<AppState>, Query(p): Query<ListParams>) -> ApiResult<impl IntoResponse> {
+    let page = list_inner(&state, &p)?;
+    // The body stays a plain array; paging rides in the headers.
+    let mut headers = HeaderMap::new();
+    headers.insert("x-total-count", HeaderValue::from(page.total));
+    if let Some(next) = &page.next {
+        let mut link = format!(
+            "</api/notes?order={}&after={}",
+            db::NoteOrder::from(p.order.unwrap_or(SearchOrder::Updated)).as_str(),
+            crate::urlencoding::encode_or_self(&next.to_string())
+        );
+        if let Some(n) = p.limit {
+            link.push_str(&format!("&limit={n}"));
+        }
+        link.push_str(">; rel=\"next\"");
+        if let Ok(v) = HeaderValue::from_str(&link) {
+            headers.insert(header::LINK, v);
+        }
+    }
+    Ok((headers, Json(page.notes)))
+}
+
+pub fn list_inner(state: &AppState, p: &ListParams) -> ApiResult<NotePage> {
+    let order: db::NoteOrder = match p.order {
+        Some(SearchOrder::Relevance) => {
+            return Err(ApiError::BadRequest("order=relevance needs a query; use /api/notes/search".into()))
+        }
+        Some(o) => o.into(),
+        None => db::NoteOrder::Updated,
+    };
+    let after = match p.after.as_deref().filter(|a| !a.is_empty()) {
+        Some(a) => Some(a.parse::<db::Cursor>().map_err(|e| ApiError::BadRequest(e.to_string()))?),
+        None => None,
     };
-    Ok(Json(notes))
+    let conn = state.db();
+    let notes = db::page_notes(&conn, order, after.as_ref(), p.limit)?;
+    // A full page may have a successor; a short one is the last.
+    let next = match (p.limit, notes.last()) {
+        (Some(n), Some(last)) if notes.len() == n as usize => Some(db::Cursor::of(last, order)),
+        _ => None,
+    };
+    Ok(NotePage { total: db::count_notes(&conn)?, notes, next })
 }
 
 #[derive(Debug, Deserialize)]
@@ -38,6 +92,15 @@ pub struct SearchParams {
     pub from: Option<String>,
     #[serde(default)]
     pub to: Option<String>,
+    #[serde(default)]
+    pub order: SearchOrder,
+    /// `q` is a regular expression, not a query.
+    #[serde(default)]
+    pub regex: bool,
+    /// Fall back to typo-tolerant title / tag matching when nothing
+    /// matches exactly.
+    #[serde(default)]
+    pub fuzzy: bool,
 }
 
 fn default_true() -> bool {
@@ -65,33 +128,133 @@ impl From<SearchField> for db::NoteField {
     }
 }
 
-async fn search(
-    State(state): State<AppState>,
-    Query(p): Query<SearchParams>,
-) -> ApiResult<Json<Vec<Note>>> {
-    let notes = {
+/// Result ordering for `/api/notes/search` (full-text rank by default)
+/// and `/api/notes`.
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchOrder {
+    #[default]
+    Relevance,
+    Updated,
+    Created,
+    Title,
+}
+
+impl From<SearchOrder> for db::NoteOrder {
+    fn from(o: SearchOrder) -> Self {
+        match o {
+            SearchOrder::Relevance => db::NoteOrder::Relevance,
+            SearchOrder::Updated => db::NoteOrder::Updated,
+            SearchOrder::Created => db::NoteOrder::Created,
+            SearchOrder::Title => db::NoteOrder::Title,
+        }
+    }
+}
+
+/// The matching notes, each with the snippets showing where it matched,
+/// and, when nothing matched as typed, the query with its typos fixed.
+#[derive(Debug)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    pub did_you_mean: Option<String>,
+}
+
+async fn search(State(state): State<AppState>, Query(p): Query<SearchParams>) -> ApiResult<impl IntoResponse> {
+    let found = search_inner(&state, &p)?;
+    // Like the list's paging, the correction rides in a header so the body
+    // stays a plain array. Percent-encoded: header values are ASCII.
+    let mut headers = HeaderMap::new();
+    if let Some(q) = &found.did_you_mean {
+        if let Ok(v) = HeaderValue::from_str(&crate::urlencoding::encode_or_self(q)) {
+            headers.insert("x-did-you-mean", v);
+        }
+    }
+    Ok((headers, Json(found.hits)))
+}
+
+pub fn search_inner(state: &AppState, p: &SearchParams) -> ApiResult<SearchResults> {
+    let opts = db::NoteMatch {
+        ignore_case: p.ignore_case,
+        whole_word: p.whole_word,
+        from: p.from.as_deref().and_then(|s| db::parse_when(s, false)),
+        to: p.to.as_deref().and_then(|s| db::parse_when(s, true)),
+        order_by: Some(p.order.into()),
+        regex: p.regex,
+        fuzzy: p.fuzzy,
+    };
+    let field = p.field.into();
+    // Parse up front so a malformed query or regex is a 400, not a 500.
+    let (notes, highlighter, did_you_mean) = if p.regex {
+        let re = crate::query::build_regex(&p.q, p.ignore_case, p.whole_word)?;
         let conn = state.db();
-        db::search_notes(
-            &conn,
-            p.field.into(),
-            &p.q,
-            db::NoteMatch {
-                ignore_case: p.ignore_case,
-                whole_word: p.whole_word,
-                from: p.from.as_deref().and_then(|s| db::parse_when(s, false)),
-                to: p.to.as_deref().and_then(|s| db::parse_when(s, true)),
-                order_by: None,
-            },
-        )?
+        (db::search_regex(&conn, field, &re, opts)?, Highlighter::regex(&re, field), None)
+    } else {
+        let query = crate::query::parse(&p.q, Local::now().naive_local())?;
+        let conn = state.db();
+        let notes = db::search_query(&conn, field, &query, opts)?;
+        if notes.is_empty() {
+            let corrected = db::did_you_mean(&conn, &query)?;
+            let notes = if p.fuzzy { db::search_fuzzy(&conn, field, &query, opts)? } else { notes };
+            // Mark the words the typos stood for.
+            let highlighter = Highlighter::query(corrected.as_ref().unwrap_or(&query), field, true, false);
+            (notes, highlighter, corrected.map(|q| q.to_string()))
+        } else {
+            (notes, Highlighter::query(&query, field, p.ignore_case, p.whole_word), None)
+        }
     };
-    Ok(Json(notes))
+    let hits = notes.into_iter().map(|n| highlighter.hit(n)).collect();
+    Ok(SearchResults { hits, did_you_mean })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestParams {
+    pub q: String,
+    #[serde(default)]
+    pub field: SearchField,
+}
+
+/// A note whose title or tags come close to a search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub id: String,
+    pub title: String,
+    pub notebook: String,
+}
+
+/// Reply of `GET /api/notes/suggest`, for a search that found nothing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Suggestions {
+    /// The query with its unknown words replaced by the closest known
+    /// ones; `None` when every word occurs in some note.
+    pub query: Option<String>,
+    /// Up to five notes the query matches with typos, closest first.
+    pub notes: Vec<Suggestion>,
+}
+
+async fn suggest(
+    State(state): State<AppState>,
+    Query(p): Query<SuggestParams>,
+) -> ApiResult<Json<Suggestions>> {
+    Ok(Json(suggest_inner(&state, &p.q, p.field.into())?))
+}
+
+pub fn suggest_inner(state: &AppState, q: &str, field: db::NoteField) -> ApiResult<Suggestions> {
+    let query = crate::query::parse(q, Local::now().naive_local())?;
+    let conn = state.db();
+    let opts = db::NoteMatch { order_by: Some(db::NoteOrder::Relevance), ..Default::default() };
+    let notes = db::search_fuzzy(&conn, field, &query, opts)?
+        .into_iter()
+        .take(5)
+        .map(|n| Suggestion { id: n.id, title: n.title, notebook: n.notebook })
+        .collect();
+    let query = db::did_you_mean(&conn, &query)?.map(|q| q.to_string());
+    Ok(Suggestions { query, notes })
 }
 
-async fn get(State(state): State<AppState>, Path(id): Path<String>) -> ApiResult<Json<Note>> {
+async fn get(State(state): State<AppState>, Path(id): Path<String>) -> ApiResult<impl IntoResponse> {
     let conn = state.db();
-    db::get_note(&conn, &id)?
-        .ok_or(ApiError::NotFound)
-        .map(Json)
+    let note = db::get_note(&conn, &id)?.ok_or(ApiError::NotFound)?;
+    Ok((etag(&note), Json(note)))
 }
 
 #[derive(Debug, Deserialize)]
@@ -134,12 +297,13 @@ pub async fn create_note_inner(state: &AppState, body: CreateBody) -> ApiResult<
         updated: now,
         related: body.related,
         body: body.body,
+        deleted_at: None,
     };
     {
         let conn = state.db();
         db::upsert_note(&conn, &note)?;
+        persist_yaml(state, yaml::Item::Note(note.clone()))?;
     }
-    persist_yaml(state, yaml::Item::Note(note.clone()))?;
     consume_draft_best_effort(state, "new");
     Ok(note)
 }
@@ -152,15 +316,49 @@ pub struct UpdateBody {
     pub body: Option<String>,
     /// Replace the related list. Use `None` to leave untouched; pass `[]` to clear.
     pub related: Option<Vec<String>>,
+    /// The `updated` of the copy this edit was made on; when the note has
+    /// changed since, the update is refused with 409 and the current copy.
+    /// An `If-Match` header (the note's `ETag`) does the same.
+    #[serde(default)]
+    pub expected_updated: Option<NaiveDateTime>,
 }
 
 async fn update(
     State(state): State<AppState>,
     Path(id): Path<String>,
-    Json(body): Json<UpdateBody>,
-) -> ApiResult<Json<Note>> {
+    headers: HeaderMap,
+    Json(mut body): Json<UpdateBody>,
+) -> ApiResult<impl IntoResponse> {
+    if let Some(v) = headers.get(header::IF_MATCH) {
+        let v = v.to_str().map_err(|_| ApiError::BadRequest("bad If-Match".into()))?;
+        if let Some(at) = version_from_etag(v)? {
+            body.expected_updated = Some(at);
+        }
+    }
     let note = update_note_inner(&state, &id, body).await?;
-    Ok(Json(note))
+    Ok((etag(&note), Json(note)))
+}
+
+/// A note's version as a strong `ETag` header: its `updated` stamp at full
+/// precision, as the DB keeps it.
+fn etag(note: &Note) -> [(header::HeaderName, String); 1] {
+    [(header::ETAG, format!("\"{}\"", note.updated.format("%Y-%m-%dT%H:%M:%S%.f")))]
+}
+
+/// The version an `If-Match` asks for; `None` for `*` (any version). Weak
+/// tags (`W/"…"`) are refused: an edit needs the exact version.
+fn version_from_etag(v: &str) -> ApiResult<Option<NaiveDateTime>> {
+    let v = v.trim();
+    if v == "*" {
+        return Ok(None);
+    }
+    if v.starts_with("W/") {
+        return Err(ApiError::BadRequest(format!("weak If-Match {v:?}: send the note's ETag as given")));
+    }
+    v.trim_matches('"')
+        .parse()
+        .map(Some)
+        .map_err(|_| ApiError::BadRequest(format!("bad If-Match {v:?}: expected the note's ETag")))
 }
 
 /// Shared update logic (used by the JSON API and the viewer's edit form).
@@ -169,10 +367,18 @@ pub async fn update_note_inner(
     id: &str,
     body: UpdateBody,
 ) -> ApiResult<Note> {
-    let mut note = {
-        let conn = state.db();
-        db::get_note(&conn, id)?.ok_or(ApiError::NotFound)?
-    };
+    // Check and write under one lock, so two racing edits can't both pass
+    // and a sync can't land between reading the note and committing it.
+    let conn = state.db();
+    let mut note = db::get_note(&conn, id)?.ok_or(ApiError::NotFound)?;
+    if let Some(expected) = body.expected_updated {
+        if expected != note.updated {
+            return Err(ApiError::Conflict(
+                format!("{id} was changed at {} (after the copy this edit was made on)", note.updated),
+                serde_json::to_value(&note).map_err(anyhow::Error::from)?,
+            ));
+        }
+    }
     if let Some(t) = body.title {
         if t.trim().is_empty() {
             return Err(ApiError::BadRequest("title must not be empty".into()));
@@ -192,11 +398,9 @@ pub async fn update_note_inner(
         note.related = r;
     }
     note.updated = Local::now().naive_local();
-    {
-        let conn = state.db();
-        db::upsert_note(&conn, &note)?;
-    }
+    db::upsert_note(&conn, &note)?;
     persist_yaml(state, yaml::Item::Note(note.clone()))?;
+    drop(conn);
     consume_draft_best_effort(state, &format!("note:{id}"));
     Ok(note)
 }
@@ -222,21 +426,248 @@ async fn delete(State(state): State<AppState>, Path(id): Path<String>) -> ApiRes
     Ok(Json(serde_json::json!({ "ok": true })))
 }
 
-/// Shared delete logic (used by the JSON API and the viewer's delete form).
-/// Returns whether anything was removed.
+/// Shared delete logic (used by the JSON API and the viewer's delete form):
+/// moves the note to the trash (see `server::trash`). Returns whether a live
+/// note was found.
 pub async fn delete_note_inner(state: &AppState, id: &str) -> ApiResult<bool> {
-    let removed = {
+    let conn = state.db();
+    let Some(mut note) = db::get_note(&conn, id)? else { return Ok(false) };
+    note.deleted_at = Some(Local::now().naive_local());
+    db::upsert_note(&conn, &note)?;
+    persist_yaml_with_message(state, yaml::Item::Note(note), &format!("trash: {id}"))?;
+    Ok(true)
+}
+
+async fn history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Vec<crate::git::Revision>>> {
+    Ok(Json(note_history_inner(&state, &id)?))
+}
+
+/// Commits that touched a note's YAML file, newest first (including the one
+/// that deleted it, if any). Shared by the JSON API and the viewer.
+pub fn note_history_inner(state: &AppState, id: &str) -> ApiResult<Vec<crate::git::Revision>> {
+    let rel = yaml::rel_path(id)
+        .filter(|_| id.starts_with("note-"))
+        .ok_or(ApiError::NotFound)?;
+    let revs = crate::git::file_log(&state.inner.paths.repo_dir, &rel)?;
+    if revs.is_empty() {
+        return Err(ApiError::NotFound);
+    }
+    Ok(revs)
+}
+
+async fn history_rev(
+    State(state): State<AppState>,
+    Path((id, rev)): Path<(String, String)>,
+) -> ApiResult<Json<Note>> {
+    let (_, note) = note_at_rev_inner(&state, &id, &rev)?;
+    Ok(Json(note))
+}
+
+/// A note as it was at commit `rev` (a full or abbreviated hash from its
+/// history), together with that history entry. A revision that deleted the
+/// note has no content and reads as not found.
+pub fn note_at_rev_inner(
+    state: &AppState,
+    id: &str,
+    rev: &str,
+) -> ApiResult<(crate::git::Revision, Note)> {
+    if !crate::git::is_commit_hash(rev) {
+        return Err(ApiError::BadRequest("rev must be a commit hash".into()));
+    }
+    let rev = rev.to_ascii_lowercase();
+    let entry = note_history_inner(state, id)?
+        .into_iter()
+        .find(|r| r.hash.starts_with(&rev))
+        .ok_or(ApiError::NotFound)?;
+    let text = crate::git::show_file(&state.inner.paths.repo_dir, &entry.hash, &entry.path)?
+        .ok_or(ApiError::NotFound)?;
+    match yaml::parse(&text)? {
+        yaml::Item::Note(n) => Ok((entry, n)),
+        _ => Err(ApiError::NotFound),
+    }
+}
+
+/// A note pointing at another one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backlink {
+    pub id: String,
+    pub title: String,
+    pub notebook: String,
+    /// Listed in the linking note's `related`.
+    pub related: bool,
+    /// Mentioned in the linking note's body, by ID or as a `[[Title]]`
+    /// wiki link.
+    pub mentioned: bool,
+}
+
+/// Reply of `GET /api/notes/:id/backlinks`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backlinks {
+    /// Live notes linking here, most recently updated first.
+    pub backlinks: Vec<Backlink>,
+    /// This note's `related` IDs that no longer resolve to a live note
+    /// (trashed or purged).
+    pub dangling: Vec<String>,
+}
+
+async fn backlinks(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Backlinks>> {
+    Ok(Json(backlinks_inner(&state, &id)?))
+}
+
+/// Incoming links of a note (via `related` or a body mention) plus its
+/// dangling outgoing `related` IDs. Shared by the JSON API and the viewer.
+pub fn backlinks_inner(state: &AppState, id: &str) -> ApiResult<Backlinks> {
+    let conn = state.db();
+    let note = db::get_note(&conn, id)?.ok_or(ApiError::NotFound)?;
+    let title = crate::wikilink::fold_title(&note.title);
+    let backlinks = db::notes_linking_to(&conn, id, &note.title)?
+        .into_iter()
+        .filter_map(|n| {
+            let related = n.related.iter().any(|r| r == id);
+            let mentioned = crate::id::note_ids_in(&n.body).iter().any(|m| m == id)
+                || crate::wikilink::parse(&n.body)
+                    .iter()
+                    .any(|l| l.target == id || crate::wikilink::fold_title(&l.target) == title);
+            (related || mentioned).then_some(Backlink {
+                id: n.id,
+                title: n.title,
+                notebook: n.notebook,
+                related,
+                mentioned,
+            })
+        })
+        .collect();
+    let mut dangling = Vec::new();
+    for r in &note.related {
+        if db::get_note(&conn, r)?.is_none() && !dangling.contains(r) {
+            dangling.push(r.clone());
+        }
+    }
+    Ok(Backlinks { backlinks, dangling })
+}
+
+/// A `[[target]]` wiki link and the note it resolves to, if any.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedLink {
+    pub target: String,
+    pub id: Option<String>,
+    pub title: Option<String>,
+}
+
+async fn links(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Vec<ResolvedLink>>> {
+    let note = {
         let conn = state.db();
-        db::delete_note(&conn, id)?
+        db::get_note(&conn, &id)?.ok_or(ApiError::NotFound)?
     };
-    if removed {
-        delete_yaml(state, id)?;
+    Ok(Json(resolve_wiki_links(&state, &note.body)?))
+}
+
+/// Resolve each distinct wiki-link target in `body`: a note ID resolves
+/// when that note is live, anything else is looked up as a title (see
+/// [`db::find_note_by_title`]).
+pub fn resolve_wiki_links(state: &AppState, body: &str) -> ApiResult<Vec<ResolvedLink>> {
+    let mut out: Vec<ResolvedLink> = Vec::new();
+    let conn = state.db();
+    for link in crate::wikilink::parse(body) {
+        if out.iter().any(|r| r.target == link.target) {
+            continue;
+        }
+        let note = if crate::id::is_note_id(&link.target) {
+            db::get_note(&conn, &link.target)?
+        } else {
+            db::find_note_by_title(&conn, &link.target)?
+        };
+        out.push(ResolvedLink {
+            target: link.target,
+            id: note.as_ref().map(|n| n.id.clone()),
+            title: note.map(|n| n.title),
+        });
     }
-    Ok(removed)
+    Ok(out)
 }
 
-/// Write a single item's YAML file in the repo dir, then commit it.
+#[derive(Debug, Default, Deserialize)]
+pub struct RestoreBody {
+    /// Commit hash to restore from. Omit to resurrect a deleted note from
+    /// its last revision.
+    #[serde(default)]
+    pub rev: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoreReport {
+    pub note: Note,
+    /// Full hash of the revision the content came from.
+    pub rev: String,
+}
+
+async fn restore(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(body): Json<RestoreBody>,
+) -> ApiResult<Json<RestoreReport>> {
+    Ok(Json(restore_note_inner(&state, &id, body.rev.as_deref())?))
+}
+
+/// Bring a note back from git: with `rev`, roll it back to that revision;
+/// without, resurrect a deleted note from the newest revision that still had
+/// it. Either way the result is written as a fresh save (new `updated`, new
+/// commit) — history is never rewritten.
+pub fn restore_note_inner(state: &AppState, id: &str, rev: Option<&str>) -> ApiResult<RestoreReport> {
+    let (entry, mut note) = match rev {
+        Some(rev) => note_at_rev_inner(state, id, rev)?,
+        None => {
+            let exists = {
+                let conn = state.db();
+                db::get_note(&conn, id)?.is_some()
+            };
+            if exists {
+                return Err(ApiError::BadRequest(
+                    "note exists; pass a rev to roll it back".into(),
+                ));
+            }
+            let last = note_history_inner(state, id)?
+                .into_iter()
+                .find(|r| !r.deleted)
+                .ok_or(ApiError::NotFound)?;
+            note_at_rev_inner(state, id, &last.hash)?
+        }
+    };
+    note.updated = Local::now().naive_local();
+    note.deleted_at = None;
+    let msg = format!("restore: {id}: from {}", &entry.hash[..entry.hash.len().min(8)]);
+    {
+        let conn = state.db();
+        db::upsert_note(&conn, &note)?;
+        persist_yaml_with_message(state, yaml::Item::Note(note.clone()), &msg)?;
+    }
+    Ok(RestoreReport { note, rev: entry.hash })
+}
+
+/// Write a single item's YAML file in the repo dir, then commit it. Callers
+/// hold the [`db`](AppState::db) lock from reading the item until this
+/// returns, so a `sync` can't merge into the file in between.
 pub fn persist_yaml(state: &AppState, item: yaml::Item) -> ApiResult<()> {
+    let msg = match &item {
+        yaml::Item::Note(n) => format!("note: {}: {}", n.id, summary(&n.title)),
+        yaml::Item::Pulse(p) => format!("pulse: {}: {}", p.id, summary(&p.topic)),
+        yaml::Item::Metric(m) => format!("metric: {}: {}", m.id, summary(&m.topic)),
+    };
+    persist_yaml_with_message(state, item, &msg)
+}
+
+/// [`persist_yaml`] with a caller-chosen commit message.
+pub fn persist_yaml_with_message(state: &AppState, item: yaml::Item, msg: &str) -> ApiResult<()> {
+    let _repo = state.repo();
     let path = match yaml::write_item(&state.inner.paths.repo_dir, &item) {
         Ok(p) => p,
         Err(e) => {
@@ -250,12 +681,40 @@ pub fn persist_yaml(state: &AppState, item: yaml::Item) -> ApiResult<()> {
         .unwrap_or(&path)
         .to_string_lossy()
         .into_owned();
-    let msg = match &item {
-        yaml::Item::Note(n) => format!("note: {}: {}", n.id, summary(&n.title)),
-        yaml::Item::Pulse(p) => format!("pulse: {}: {}", p.id, summary(&p.topic)),
-        yaml::Item::Metric(m) => format!("metric: {}: {}", m.id, summary(&m.topic)),
-    };
-    if let Err(e) = crate::git::add_and_commit(&state.inner.paths.repo_dir, &[&rel], &msg) {
+    if let Err(e) = crate::git::add_and_commit(&state.inner.paths.repo_dir, &[&rel], msg) {
+        eprintln!("warning: git commit failed: {e:#}");
+    }
+    Ok(())
+}
+
+/// Save a batch of edited notes: the DB rows in one transaction, then
+/// their YAML files in a single commit with `msg`. Used by the bulk tag and
+/// notebook operations, which read the notes through the same `conn` guard.
+pub fn persist_notes_with_message(
+    state: &AppState,
+    conn: &mut rusqlite::Connection,
+    notes: &[Note],
+    msg: &str,
+) -> ApiResult<()> {
+    if notes.is_empty() {
+        return Ok(());
+    }
+    let tx = conn.transaction()?;
+    for n in notes {
+        db::upsert_note(&tx, n)?;
+    }
+    tx.commit()?;
+    let repo = &state.inner.paths.repo_dir;
+    let _repo = state.repo();
+    let mut rels = Vec::new();
+    for n in notes {
+        match yaml::write_item(repo, &yaml::Item::Note(n.clone())) {
+            Ok(path) => rels.push(path.strip_prefix(repo).unwrap_or(&path).to_string_lossy().into_owned()),
+            Err(e) => eprintln!("warning: yaml write failed: {e:#}"),
+        }
+    }
+    let refs: Vec<&str> = rels.iter().map(|s| s.as_str()).collect();
+    if let Err(e) = crate::git::add_and_commit(repo, &refs, msg) {
         eprintln!("warning: git commit failed: {e:#}");
     }
     Ok(())
@@ -264,6 +723,7 @@ pub fn persist_yaml(state: &AppState, item: yaml::Item) -> ApiResult<()> {
 /// Remove a single item's YAML file by id, then commit the deletion.
 pub fn delete_yaml(state: &AppState, id: &str) -> ApiResult<()> {
     if let Some(rel) = yaml::rel_path(id) {
+        let _repo = state.repo();
         let path = state.inner.paths.repo_dir.join(&rel);
         if path.exists() {
             if let Err(e) = std::fs::remove_file(&path) {
@@ -293,5 +753,292 @@ pub fn routes() -> axum::Router<AppState> {
     axum::Router::new()
         .route("/api/notes", routing::get(list).post(create))
         .route("/api/notes/search", routing::get(search))
+        .route("/api/notes/suggest", routing::get(suggest))
         .route("/api/notes/:id", routing::get(get).put(update).delete(delete))
+        .route("/api/notes/:id/backlinks", routing::get(backlinks))
+        .route("/api/notes/:id/links", routing::get(links))
+        .route("/api/notes/:id/history", routing::get(history))
+        .route("/api/notes/:id/history/:rev", routing::get(history_rev))
+        .route("/api/notes/:id/restore", routing::post(restore))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_body(title: &str) -> CreateBody {
+        CreateBody {
+            title: title.into(),
+            tags: vec![],
+            notebook: String::new(),
+            body: "first body".into(),
+            related: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_updates_conflict() {
+        let (_dir, state) = crate::server::test_state();
+        let note = create_note_inner(&state, create_body("v1")).await.unwrap();
+        let edit = |title: &str, expected| UpdateBody {
+            title: Some(title.into()),
+            tags: None,
+            notebook: None,
+            body: None,
+            related: None,
+            expected_updated: expected,
+        };
+        // The stamp as a client got it (sub-second part included) matches.
+        let v2 = update_note_inner(&state, &note.id, edit("v2", Some(note.updated))).await.unwrap();
+        let stale = note.updated - chrono::Duration::seconds(5);
+        match update_note_inner(&state, &note.id, edit("v3", Some(stale))).await {
+            Err(ApiError::Conflict(_, current)) => assert_eq!(current["title"], "v2"),
+            other => panic!("expected a conflict, got {other:?}"),
+        }
+        update_note_inner(&state, &note.id, edit("v3", Some(v2.updated))).await.unwrap();
+        // v2's stamp is stale now, even when v3 landed within the same second.
+        assert!(matches!(
+            update_note_inner(&state, &note.id, edit("v3b", Some(v2.updated))).await,
+            Err(ApiError::Conflict(..))
+        ));
+        // No precondition: last write wins, as before.
+        update_note_inner(&state, &note.id, edit("v4", None)).await.unwrap();
+
+        assert_eq!(version_from_etag("*").unwrap(), None);
+        let [(_, tag)] = etag(&v2);
+        assert_eq!(version_from_etag(&tag).unwrap(), Some(v2.updated));
+        assert_eq!(
+            version_from_etag("\"2026-08-06T14:32:00.25\"").unwrap().unwrap().to_string(),
+            "2026-08-06 14:32:00.250"
+        );
+        assert!(matches!(version_from_etag("W/\"2026-08-06T14:32:00\""), Err(ApiError::BadRequest(_))));
+        assert!(version_from_etag("\"soon\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn list_pages_with_cursors() {
+        let (_dir, state) = crate::server::test_state();
+        for t in ["b", "c", "a"] {
+            create_note_inner(&state, create_body(t)).await.unwrap();
+        }
+        let order = Some(SearchOrder::Title);
+        let first = list_inner(&state, &ListParams { limit: Some(2), order, ..Default::default() }).unwrap();
+        assert_eq!(first.total, 3);
+        assert_eq!(first.notes.iter().map(|n| n.title.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        let after = first.next.map(|c| c.to_string());
+        let second = list_inner(&state, &ListParams { limit: Some(2), after, order }).unwrap();
+        assert_eq!(second.notes.len(), 1);
+        assert!(second.next.is_none());
+
+        let all = list_inner(&state, &ListParams::default()).unwrap();
+        assert_eq!((all.notes.len(), all.total), (3, 3));
+        let bad = |p: ListParams| matches!(list_inner(&state, &p), Err(ApiError::BadRequest(_)));
+        assert!(bad(ListParams { order: Some(SearchOrder::Relevance), ..Default::default() }));
+        assert!(bad(ListParams { after: Some("nocomma".into()), ..Default::default() }));
+    }
+
+    #[tokio::test]
+    async fn search_hits_carry_snippets() {
+        let (_dir, state) = crate::server::test_state();
+        let mut body = create_body("Shell notes");
+        body.body = "Set the PowerShell prompt colour.".into();
+        let note = create_note_inner(&state, body).await.unwrap();
+        let params = |v: serde_json::Value| serde_json::from_value::<SearchParams>(v).unwrap();
+
+        let hits = search_inner(&state, &params(serde_json::json!({"q": "power"}))).unwrap().hits;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].field.as_deref(), Some("body"));
+        let s = &hits[0].snippets[0];
+        assert_eq!(&s.text[s.matches[0].0..s.matches[0].1], "PowerShell");
+        // The note's own fields stay at the top level of the JSON.
+        let json = serde_json::to_value(&hits[0]).unwrap();
+        assert_eq!(json["id"], note.id.as_str());
+        assert_eq!(json["snippets"][0]["field"], "body");
+
+        let hits = search_inner(&state, &params(serde_json::json!({"q": "colou?r", "regex": true}))).unwrap().hits;
+        assert_eq!(hits[0].snippets[0].matches.len(), 1);
+        assert!(matches!(
+            search_inner(&state, &params(serde_json::json!({"q": "(", "regex": true}))),
+            Err(ApiError::BadRequest(_))
+        ));
+
+        // A typo finds nothing but says what was meant, and falling back to
+        // fuzzy matching marks the word it stood for.
+        let found = search_inner(&state, &params(serde_json::json!({"q": "t:notse -draft"}))).unwrap();
+        assert!(found.hits.is_empty());
+        assert_eq!(found.did_you_mean.as_deref(), Some("t:notes -draft"));
+        let hits = search_inner(&state, &params(serde_json::json!({"q": "notse", "fuzzy": true}))).unwrap().hits;
+        let s = hits[0].snippet("title").unwrap();
+        assert_eq!(&s.text[s.matches[0].0..s.matches[0].1], "notes");
+        let suggested = suggest_inner(&state, "notse", db::NoteField::Content).unwrap();
+        assert_eq!(suggested.query.as_deref(), Some("notes"));
+        assert_eq!(suggested.notes[0].id, note.id);
+    }
+
+    #[test]
+    fn concurrent_writes_each_get_their_own_commit() {
+        let (_dir, state) = crate::server::test_state();
+        let git = |args: &[&str]| {
+            let out = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&state.inner.paths.repo_dir)
+                .args(args)
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        let before: usize = git(&["rev-list", "--count", "HEAD"]).parse().unwrap_or(0);
+        let at = chrono::Local::now().naive_local();
+        std::thread::scope(|s| {
+            for i in 0..8 {
+                let state = state.clone();
+                s.spawn(move || {
+                    let id = format!("note-20260806-1432-{i:03}");
+                    let note = Note::new(id, format!("n{i}"), vec![], "nb".into(), at, at, String::new());
+                    persist_yaml(&state, yaml::Item::Note(note)).unwrap();
+                });
+            }
+        });
+        // Without the repo lock, racing `git add`/`commit`s lose commits to
+        // `index.lock` or sweep up each other's files.
+        assert_eq!(git(&["rev-list", "--count", "HEAD"]).parse::<usize>().unwrap(), before + 8);
+        assert_eq!(git(&["status", "--porcelain", "notes"]), "");
+    }
+
+    #[tokio::test]
+    async fn history_lists_revisions_and_reads_old_versions() {
+        let (_dir, state) = crate::server::test_state();
+        let note = create_note_inner(&state, create_body("v1 title")).await.unwrap();
+        let update = UpdateBody {
+            title: Some("v2 title".into()),
+            tags: None,
+            notebook: None,
+            body: None,
+            related: None,
+            expected_updated: None,
+        };
+        update_note_inner(&state, &note.id, update).await.unwrap();
+        assert!(delete_note_inner(&state, &note.id).await.unwrap());
+        crate::server::trash::purge_one_inner(&state, &note.id).unwrap();
+
+        let revs = note_history_inner(&state, &note.id).unwrap();
+        assert_eq!(revs.len(), 4);
+        assert!(revs[0].deleted);
+        assert_eq!(revs[1].subject, format!("trash: {}", note.id));
+        assert_eq!(revs[2].subject, format!("note: {}: v2 title", note.id));
+
+        let (_, old) = note_at_rev_inner(&state, &note.id, &revs[3].hash[..8]).unwrap();
+        assert_eq!(old.title, "v1 title");
+        assert_eq!(old.body, "first body");
+        assert!(matches!(note_at_rev_inner(&state, &note.id, &revs[0].hash), Err(ApiError::NotFound)));
+        assert!(matches!(note_at_rev_inner(&state, &note.id, "HEAD~1"), Err(ApiError::BadRequest(_))));
+        assert!(matches!(note_history_inner(&state, "note-20000101-0000-000"), Err(ApiError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn restore_resurrects_deleted_and_rolls_back() {
+        let (_dir, state) = crate::server::test_state();
+        let note = create_note_inner(&state, create_body("keep me")).await.unwrap();
+        let update = UpdateBody {
+            title: None,
+            tags: None,
+            notebook: None,
+            body: Some("second body".into()),
+            related: None,
+            expected_updated: None,
+        };
+        update_note_inner(&state, &note.id, update).await.unwrap();
+
+        // An existing note needs an explicit revision.
+        assert!(matches!(restore_note_inner(&state, &note.id, None), Err(ApiError::BadRequest(_))));
+        let first = note_history_inner(&state, &note.id).unwrap().pop().unwrap();
+        let r = restore_note_inner(&state, &note.id, Some(&first.hash)).unwrap();
+        assert_eq!(r.note.body, "first body");
+        assert_eq!(r.rev, first.hash);
+
+        // Trash it, then resurrect from the newest revision with content.
+        delete_note_inner(&state, &note.id).await.unwrap();
+        let r = restore_note_inner(&state, &note.id, None).unwrap();
+        assert_eq!(r.note.body, "first body");
+        assert!(r.note.deleted_at.is_none());
+        let back = {
+            let conn = state.db();
+            db::get_note(&conn, &note.id).unwrap().unwrap()
+        };
+        assert_eq!(back.title, "keep me");
+
+        // Restores are new commits on top, not rewrites.
+        let revs = note_history_inner(&state, &note.id).unwrap();
+        assert_eq!(revs.len(), 5);
+        assert!(revs[0].subject.starts_with(&format!("restore: {}: from ", note.id)));
+    }
+
+    #[tokio::test]
+    async fn backlinks_cover_related_and_mentions_and_flag_dangling() {
+        let (_dir, state) = crate::server::test_state();
+        let target = create_note_inner(&state, create_body("target")).await.unwrap();
+        let gone = create_note_inner(&state, create_body("gone")).await.unwrap();
+        let mut by_related = create_body("by related");
+        by_related.related = vec![target.id.clone()];
+        let by_related = create_note_inner(&state, by_related).await.unwrap();
+        let mut by_mention = create_body("by mention");
+        by_mention.body = format!("see {} for details", target.id);
+        let by_mention = create_note_inner(&state, by_mention).await.unwrap();
+        let mut by_title = create_body("by title");
+        by_title.body = "a [[TARGET|wiki link]] here".into();
+        let by_title = create_note_inner(&state, by_title).await.unwrap();
+        let mut near_miss = create_body("near miss");
+        near_miss.body = format!("{}0 is another thing", target.id);
+        create_note_inner(&state, near_miss).await.unwrap();
+
+        let update = UpdateBody {
+            title: None,
+            tags: None,
+            notebook: None,
+            body: None,
+            related: Some(vec![by_related.id.clone(), gone.id.clone()]),
+            expected_updated: None,
+        };
+        update_note_inner(&state, &target.id, update).await.unwrap();
+        delete_note_inner(&state, &gone.id).await.unwrap();
+
+        let b = backlinks_inner(&state, &target.id).unwrap();
+        let ids: Vec<(&str, bool, bool)> = b
+            .backlinks
+            .iter()
+            .map(|l| (l.id.as_str(), l.related, l.mentioned))
+            .collect();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.contains(&(by_related.id.as_str(), true, false)));
+        assert!(ids.contains(&(by_title.id.as_str(), false, true)));
+        assert!(ids.contains(&(by_mention.id.as_str(), false, true)));
+        assert_eq!(b.dangling, [gone.id.as_str()]);
+        assert!(matches!(backlinks_inner(&state, &gone.id), Err(ApiError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn wiki_links_resolve_by_id_and_title() {
+        let (_dir, state) = crate::server::test_state();
+        let target = create_note_inner(&state, create_body("Reading List")).await.unwrap();
+        let body = format!(
+            "[[{}]] [[reading list|books]] [[Nowhere]] [[note-20000101-0000-000]] [[Reading List]]",
+            target.id
+        );
+        let links = resolve_wiki_links(&state, &body).unwrap();
+        let got: Vec<(&str, Option<&str>)> = links
+            .iter()
+            .map(|l| (l.target.as_str(), l.id.as_deref()))
+            .collect();
+        assert_eq!(
+            got,
+            [
+                (target.id.as_str(), Some(target.id.as_str())),
+                ("reading list", Some(target.id.as_str())),
+                ("Nowhere", None),
+                ("note-20000101-0000-000", None),
+                ("Reading List", Some(target.id.as_str())),
+            ]
+        );
+        assert_eq!(links[0].title.as_deref(), Some("Reading List"));
+    }
 }
@@ -9,7 +9,7 @@ use crate::db;
 use crate::id::{new_id, Kind};
 use crate::models::{Interval, Pulse};
 use crate::server::error::{ApiError, ApiResult};
-use crate::server::notes::{delete_yaml, persist_yaml};
+use crate::server::notes::{persist_yaml, persist_yaml_with_message};
 use crate::server::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -64,8 +64,8 @@ pub async fn create_pulse_inner(state: &AppState, body: CreateBody) -> ApiResult
     {
         let conn = state.db();
         db::upsert_pulse(&conn, &pulse)?;
+        persist_yaml(state, crate::yaml::Item::Pulse(pulse.clone()))?;
     }
-    persist_yaml(state, crate::yaml::Item::Pulse(pulse.clone()))?;
     Ok(pulse)
 }
 
@@ -90,10 +90,8 @@ pub async fn update_pulse_inner(
     id: &str,
     body: UpdateBody,
 ) -> ApiResult<Pulse> {
-    let mut pulse = {
-        let conn = state.db();
-        db::get_pulse(&conn, id)?.ok_or(ApiError::NotFound)?
-    };
+    let conn = state.db();
+    let mut pulse = db::get_pulse(&conn, id)?.ok_or(ApiError::NotFound)?;
     if let Some(t) = body.topic {
         if t.trim().is_empty() {
             return Err(ApiError::BadRequest("topic must not be empty".into()));
@@ -103,10 +101,7 @@ pub async fn update_pulse_inner(
     if let Some(i) = body.interval {
         pulse.interval = i;
     }
-    {
-        let conn = state.db();
-        db::upsert_pulse(&conn, &pulse)?;
-    }
+    db::upsert_pulse(&conn, &pulse)?;
     persist_yaml(state, crate::yaml::Item::Pulse(pulse.clone()))?;
     Ok(pulse)
 }
@@ -150,20 +145,15 @@ pub async fn set_slot_inner(
     on: Option<&str>,
     checked: bool,
 ) -> ApiResult<Pulse> {
-    let mut pulse = {
-        let conn = state.db();
-        db::get_pulse(&conn, id)?.ok_or(ApiError::NotFound)?
-    };
+    let conn = state.db();
+    let mut pulse = db::get_pulse(&conn, id)?.ok_or(ApiError::NotFound)?;
     let slot = match on {
         Some(s) => s.to_string(),
         None => pulse.interval.current_slot(Local::now().naive_local()),
     };
     validate_slot(&pulse.interval, &slot)?;
     pulse.set_slot(slot, checked);
-    {
-        let conn = state.db();
-        db::upsert_pulse(&conn, &pulse)?;
-    }
+    db::upsert_pulse(&conn, &pulse)?;
     persist_yaml(state, crate::yaml::Item::Pulse(pulse.clone()))?;
     Ok(pulse)
 }
@@ -197,16 +187,15 @@ async fn delete(
     Ok(Json(serde_json::json!({ "ok": true })))
 }
 
-/// Shared delete logic (used by the JSON API and the viewer's delete form).
+/// Shared delete logic (used by the JSON API and the viewer's delete form):
+/// moves the pulse to the trash.
 pub async fn delete_pulse_inner(state: &AppState, id: &str) -> ApiResult<bool> {
-    let removed = {
-        let conn = state.db();
-        db::delete_pulse(&conn, id)?
-    };
-    if removed {
-        delete_yaml(state, id)?;
-    }
-    Ok(removed)
+    let conn = state.db();
+    let Some(mut pulse) = db::get_pulse(&conn, id)? else { return Ok(false) };
+    pulse.deleted_at = Some(Local::now().naive_local());
+    db::upsert_pulse(&conn, &pulse)?;
+    persist_yaml_with_message(state, crate::yaml::Item::Pulse(pulse), &format!("trash: {id}"))?;
+    Ok(true)
 }
 
 #[allow(dead_code)]
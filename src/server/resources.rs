@@ -0,0 +1,460 @@
+//! Note attachments: files under `<repo>/resources/`, referenced from note
+//! bodies as `resources/<name>` (the 1.x convention).
+//!
+//! Uploads are named by content — the first 16 hex digits of their SHA-256
+//! plus the original extension — so uploading the same file twice stores it
+//! once. Every upload/delete is committed, so attachments ride
+//! `backup`/`sync` with the YAML. The viewer serves the files at
+//! `/resources/<name>` (`viewer::resource_file`).
+//!
+//! `audit` cross-checks the two sides: references in note bodies whose file
+//! is missing, and files no note references.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use axum::extract::{DefaultBodyLimit, Multipart, Path, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::db;
+use crate::links::{referenced_names, valid_name};
+use crate::server::error::{ApiError, ApiResult};
+use crate::server::AppState;
+
+/// Subdirectory of the repo holding attachments.
+pub const RESOURCES_DIR: &str = "resources";
+
+/// Largest accepted upload request (all files together).
+const MAX_UPLOAD_BYTES: usize = 32 * 1024 * 1024;
+
+/// One file in `resources/`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Resource {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Outcome of storing one uploaded file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Uploaded {
+    pub name: String,
+    pub size: u64,
+    /// `resources/<name>`, as referenced from note bodies.
+    pub link: String,
+    /// Ready-to-paste markdown: `![stem](link)` for images, `[file](link)`
+    /// otherwise.
+    pub markdown: String,
+    /// The same content was already stored; nothing was written.
+    pub existed: bool,
+}
+
+/// A referenced resource that isn't on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrokenRef {
+    pub name: String,
+    /// Live notes whose body references it.
+    pub notes: Vec<String>,
+}
+
+/// Reply of `GET /api/resources/audit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Audit {
+    pub broken: Vec<BrokenRef>,
+    /// Files no note references, trashed notes included.
+    pub orphans: Vec<Resource>,
+}
+
+/// Reply of `DELETE /api/resources/orphans`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrphanPurge {
+    pub deleted: Vec<String>,
+}
+
+/// Content-addressed file name for an upload: 16 hex digits of the SHA-256
+/// of `bytes`, plus the lowercased extension of `original` when it's short
+/// and alphanumeric.
+pub fn content_name(original: &str, bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    let hash: String = digest[..8].iter().map(|b| format!("{b:02x}")).collect();
+    let ext = original
+        .rsplit_once('.')
+        .map(|(_, e)| e.to_ascii_lowercase())
+        .filter(|e| (1..=8).contains(&e.len()) && e.chars().all(|c| c.is_ascii_alphanumeric()));
+    match ext {
+        Some(ext) => format!("{hash}.{ext}"),
+        None => hash,
+    }
+}
+
+/// MIME type for a resource file, by extension.
+pub fn content_type(name: &str) -> &'static str {
+    match name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()) {
+        Some(ext) => match ext.as_str() {
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            "svg" => "image/svg+xml",
+            "bmp" => "image/bmp",
+            "ico" => "image/x-icon",
+            "avif" => "image/avif",
+            "txt" | "md" => "text/plain; charset=utf-8",
+            "pdf" => "application/pdf",
+            _ => "application/octet-stream",
+        },
+        None => "application/octet-stream",
+    }
+}
+
+/// Markdown reference to a stored resource; `original` supplies the label.
+pub fn markdown_link(original: &str, name: &str) -> String {
+    let label: String = original
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(original)
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | '\n' | '\r'))
+        .collect();
+    if content_type(name).starts_with("image/") {
+        let alt = label.rsplit_once('.').map_or(label.as_str(), |(stem, _)| stem);
+        format!("![{alt}]({RESOURCES_DIR}/{name})")
+    } else {
+        let label = if label.is_empty() { name } else { label.as_str() };
+        format!("[{label}]({RESOURCES_DIR}/{name})")
+    }
+}
+
+async fn list(State(state): State<AppState>) -> ApiResult<Json<Vec<Resource>>> {
+    Ok(Json(list_inner(&state)?))
+}
+
+/// Every file in `resources/`, sorted by name.
+pub fn list_inner(state: &AppState) -> ApiResult<Vec<Resource>> {
+    let dir = state.inner.paths.repo_dir.join(RESOURCES_DIR);
+    let mut out = Vec::new();
+    if !dir.exists() {
+        return Ok(out);
+    }
+    for entry in std::fs::read_dir(&dir)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if meta.is_file() && valid_name(&name) {
+            out.push(Resource { name, size: meta.len() });
+        }
+    }
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(out)
+}
+
+/// `POST /api/resources` (and the viewer's `POST /resources`): multipart
+/// form, one part per file. Parts without a file name are ignored.
+async fn upload(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> ApiResult<(axum::http::StatusCode, Json<Vec<Uploaded>>)> {
+    let mut files = Vec::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+    {
+        let Some(file_name) = field.file_name().map(str::to_string) else {
+            continue;
+        };
+        let bytes = field
+            .bytes()
+            .await
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        files.push((file_name, bytes.to_vec()));
+    }
+    let stored = store_inner(&state, files)?;
+    Ok((axum::http::StatusCode::CREATED, Json(stored)))
+}
+
+/// Write each `(original name, content)` under its content name and commit
+/// the new files together. Files already present are reported with
+/// `existed` and not rewritten.
+pub fn store_inner(state: &AppState, files: Vec<(String, Vec<u8>)>) -> ApiResult<Vec<Uploaded>> {
+    if files.is_empty() {
+        return Err(ApiError::BadRequest("no files in upload".into()));
+    }
+    let dir = state.inner.paths.repo_dir.join(RESOURCES_DIR);
+    let _repo = state.repo();
+    std::fs::create_dir_all(&dir)?;
+    let mut out: Vec<Uploaded> = Vec::new();
+    let mut added: Vec<String> = Vec::new();
+    for (original, bytes) in files {
+        if bytes.is_empty() {
+            return Err(ApiError::BadRequest(format!("{original}: empty file")));
+        }
+        let name = content_name(&original, &bytes);
+        let path = dir.join(&name);
+        let existed = path.exists() || out.iter().any(|u| u.name == name);
+        if !existed {
+            std::fs::write(&path, &bytes)?;
+            added.push(format!("{RESOURCES_DIR}/{name}"));
+        }
+        out.push(Uploaded {
+            link: format!("{RESOURCES_DIR}/{name}"),
+            markdown: markdown_link(&original, &name),
+            size: bytes.len() as u64,
+            name,
+            existed,
+        });
+    }
+    if !added.is_empty() {
+        let msg = match added.as_slice() {
+            [one] => format!("resource: add {}", one.trim_start_matches("resources/")),
+            many => format!("resource: add {} files", many.len()),
+        };
+        let refs: Vec<&str> = added.iter().map(|s| s.as_str()).collect();
+        if let Err(e) = crate::git::add_and_commit(&state.inner.paths.repo_dir, &refs, &msg) {
+            eprintln!("warning: git commit failed: {e:#}");
+        }
+    }
+    Ok(out)
+}
+
+async fn audit(State(state): State<AppState>) -> ApiResult<Json<Audit>> {
+    Ok(Json(audit_inner(&state)?))
+}
+
+/// Compare note bodies against `resources/`. Broken references are
+/// reported for live notes only; references from trashed notes still keep
+/// a file from counting as an orphan, since the note may be restored.
+pub fn audit_inner(state: &AppState) -> ApiResult<Audit> {
+    let (live, trashed) = {
+        let conn = state.db();
+        (db::list_notes(&conn, None)?, db::list_trashed_notes(&conn)?)
+    };
+    let files = list_inner(state)?;
+    let on_disk: BTreeSet<&str> = files.iter().map(|f| f.name.as_str()).collect();
+
+    let mut referenced: BTreeSet<String> = BTreeSet::new();
+    let mut broken: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for note in &live {
+        for name in referenced_names(&note.body) {
+            if !on_disk.contains(name.as_str()) {
+                broken.entry(name.clone()).or_default().push(note.id.clone());
+            }
+            referenced.insert(name);
+        }
+    }
+    for note in &trashed {
+        referenced.extend(referenced_names(&note.body));
+    }
+
+    Ok(Audit {
+        broken: broken
+            .into_iter()
+            .map(|(name, notes)| BrokenRef { name, notes })
+            .collect(),
+        orphans: files
+            .into_iter()
+            .filter(|f| !referenced.contains(&f.name))
+            .collect(),
+    })
+}
+
+async fn delete_orphans(State(state): State<AppState>) -> ApiResult<Json<OrphanPurge>> {
+    Ok(Json(delete_orphans_inner(&state)?))
+}
+
+/// Delete every orphan found by [`audit_inner`]; committed files go out in
+/// one commit, never-committed ones are just removed from disk.
+pub fn delete_orphans_inner(state: &AppState) -> ApiResult<OrphanPurge> {
+    let repo = &state.inner.paths.repo_dir;
+    let orphans = audit_inner(state)?.orphans;
+    let _repo = state.repo();
+    let mut tracked: Vec<String> = Vec::new();
+    for f in &orphans {
+        let rel = format!("{RESOURCES_DIR}/{}", f.name);
+        if crate::git::is_tracked(repo, &rel) {
+            tracked.push(rel);
+        } else {
+            std::fs::remove_file(repo.join(&rel))?;
+        }
+    }
+    if !tracked.is_empty() {
+        let msg = match tracked.as_slice() {
+            [one] => format!("resource: delete {}", one.trim_start_matches("resources/")),
+            many => format!("resource: delete {} orphans", many.len()),
+        };
+        let refs: Vec<&str> = tracked.iter().map(|s| s.as_str()).collect();
+        if let Err(e) = crate::git::remove_and_commit(repo, &refs, &msg) {
+            eprintln!("warning: git rm/commit failed: {e:#}");
+        }
+    }
+    Ok(OrphanPurge {
+        deleted: orphans.into_iter().map(|f| f.name).collect(),
+    })
+}
+
+async fn delete(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<serde_json::Value>> {
+    delete_inner(&state, &name)?;
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+/// Remove one file from `resources/` and commit the removal. Note bodies
+/// still referencing it are left as they are.
+pub fn delete_inner(state: &AppState, name: &str) -> ApiResult<()> {
+    if !valid_name(name) {
+        return Err(ApiError::NotFound);
+    }
+    let rel = format!("{RESOURCES_DIR}/{name}");
+    let path = state.inner.paths.repo_dir.join(&rel);
+    let _repo = state.repo();
+    if !path.is_file() {
+        return Err(ApiError::NotFound);
+    }
+    std::fs::remove_file(&path)?;
+    if let Err(e) = crate::git::remove_and_commit(
+        &state.inner.paths.repo_dir,
+        &[&rel],
+        &format!("resource: delete {name}"),
+    ) {
+        eprintln!("warning: git rm/commit failed: {e:#}");
+    }
+    Ok(())
+}
+
+pub fn routes() -> axum::Router<AppState> {
+    use axum::routing;
+    axum::Router::new()
+        .route(
+            "/api/resources",
+            routing::get(list)
+                .post(upload)
+                .layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)),
+        )
+        .route("/api/resources/audit", routing::get(audit))
+        .route("/api/resources/orphans", routing::delete(delete_orphans))
+        .route("/api/resources/:name", routing::delete(delete))
+}
+
+/// Upload endpoint for the viewer's note form (drag-and-drop / file picker),
+/// cookie-gated via app.rs like the other viewer routes.
+pub fn viewer_routes() -> axum::Router<AppState> {
+    use axum::routing::post;
+    axum::Router::new().route(
+        "/resources",
+        post(upload).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_content_hashes_with_clean_extensions() {
+        let a = content_name("Photo.PNG", b"pixels");
+        assert_eq!(a.len(), 16 + 4);
+        assert!(a.ends_with(".png"));
+        assert_eq!(a, content_name("other.png", b"pixels"));
+        assert_ne!(a, content_name("Photo.png", b"other pixels"));
+        assert_eq!(content_name("README", b"x").len(), 16);
+        assert_eq!(content_name("evil.p/ng", b"x").len(), 16);
+        assert!(valid_name(&a));
+        for bad in ["", "../x", "a/b", ".hidden", "a\\b"] {
+            assert!(!valid_name(bad), "{bad}");
+        }
+    }
+
+    #[test]
+    fn markdown_link_embeds_images_and_links_the_rest() {
+        assert_eq!(markdown_link("/tmp/cat.jpg", "ab.jpg"), "![cat](resources/ab.jpg)");
+        assert_eq!(markdown_link("spec [v2].pdf", "cd.pdf"), "[spec v2.pdf](resources/cd.pdf)");
+    }
+
+    #[test]
+    fn audit_reports_broken_refs_and_deletes_orphans() {
+        let (_dir, state) = crate::server::test_state();
+        let up = store_inner(
+            &state,
+            vec![
+                ("used.png".into(), b"used".to_vec()),
+                ("kept.png".into(), b"kept".to_vec()),
+                ("old.png".into(), b"old".to_vec()),
+            ],
+        )
+        .unwrap();
+        let repo = &state.inner.paths.repo_dir;
+        std::fs::write(repo.join("resources/loose.txt"), "never committed").unwrap();
+        std::fs::write(repo.join("resources/My File.pdf"), "spaced").unwrap();
+        std::fs::write(repo.join("resources/old notes.md"), "spaced").unwrap();
+
+        let now = chrono::Local::now().naive_local();
+        let note = |id: &str, body: String| {
+            crate::models::Note::new(id.into(), id.into(), vec![], "nb".into(), now, now, body)
+        };
+        let live = note(
+            "live",
+            format!(
+                "{} and ![gone](resources/gone.png), [a](resources/My%20File.pdf) [b](<resources/old notes.md>)",
+                up[0].markdown
+            ),
+        );
+        let mut trashed = note("trashed", up[1].markdown.clone());
+        trashed.deleted_at = Some(now);
+        {
+            let conn = state.db();
+            db::upsert_note(&conn, &live).unwrap();
+            db::upsert_note(&conn, &trashed).unwrap();
+        }
+
+        let audit = audit_inner(&state).unwrap();
+        assert_eq!(audit.broken.len(), 1);
+        assert_eq!(audit.broken[0].name, "gone.png");
+        assert_eq!(audit.broken[0].notes, ["live"]);
+        let orphans: Vec<&str> = audit.orphans.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(orphans, [up[2].name.as_str(), "loose.txt"]);
+
+        let purge = delete_orphans_inner(&state).unwrap();
+        assert_eq!(purge.deleted.len(), 2);
+        assert_eq!(list_inner(&state).unwrap().len(), 4);
+        assert!(!crate::git::is_tracked(repo, &format!("resources/{}", up[2].name)));
+        assert!(audit_inner(&state).unwrap().orphans.is_empty());
+    }
+
+    #[test]
+    fn store_dedups_and_delete_commits() {
+        let (_dir, state) = crate::server::test_state();
+        let log = |state: &AppState| {
+            let out = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&state.inner.paths.repo_dir)
+                .args(["log", "--format=%s"])
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&out.stdout).lines().map(str::to_string).collect::<Vec<_>>()
+        };
+
+        let up = store_inner(
+            &state,
+            vec![("a.png".into(), b"one".to_vec()), ("b.txt".into(), b"two".to_vec())],
+        )
+        .unwrap();
+        assert!(up.iter().all(|u| !u.existed));
+        assert_eq!(log(&state)[0], "resource: add 2 files");
+
+        // Same content again: reported, not rewritten, no new commit.
+        let again = store_inner(&state, vec![("copy.png".into(), b"one".to_vec())]).unwrap();
+        assert!(again[0].existed);
+        assert_eq!(again[0].name, up[0].name);
+        assert_eq!(log(&state).len(), 1);
+        assert_eq!(list_inner(&state).unwrap().len(), 2);
+        assert!(matches!(store_inner(&state, vec![]), Err(ApiError::BadRequest(_))));
+
+        delete_inner(&state, &up[1].name).unwrap();
+        assert_eq!(log(&state)[0], format!("resource: delete {}", up[1].name));
+        assert_eq!(list_inner(&state).unwrap().len(), 1);
+        assert!(matches!(delete_inner(&state, &up[1].name), Err(ApiError::NotFound)));
+        assert!(matches!(delete_inner(&state, "../db.sqlite3"), Err(ApiError::NotFound)));
+    }
+}
@@ -0,0 +1,236 @@
+//! Saved searches: named queries kept as `searches/<name>.yaml` in the repo
+//! (see `models::search`). Saving or deleting one is a commit; running one
+//! is an ordinary search with its stored options. The viewer lists them as
+//! virtual notebooks on the index page.
+
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::Local;
+use serde::Deserialize;
+
+use crate::db;
+use crate::models::{valid_search_name, Note, SavedSearch};
+use crate::server::error::{ApiError, ApiResult};
+use crate::server::AppState;
+use crate::yaml::{self, SEARCHES_DIR};
+
+#[derive(Debug, Deserialize)]
+pub struct SaveBody {
+    pub query: String,
+    #[serde(default)]
+    pub field: Option<String>,
+    #[serde(default)]
+    pub ignore_case: Option<bool>,
+    #[serde(default)]
+    pub whole_word: Option<bool>,
+    #[serde(default)]
+    pub regex: Option<bool>,
+}
+
+fn rel_path(name: &str) -> String {
+    format!("{SEARCHES_DIR}/{name}.yaml")
+}
+
+async fn list(State(state): State<AppState>) -> ApiResult<Json<Vec<SavedSearch>>> {
+    Ok(Json(list_inner(&state)?))
+}
+
+/// Every saved search, sorted by name. Unreadable files are skipped with a
+/// warning, like `yaml::read_all` does for items.
+pub fn list_inner(state: &AppState) -> ApiResult<Vec<SavedSearch>> {
+    let dir = state.inner.paths.repo_dir.join(SEARCHES_DIR);
+    let mut out = Vec::new();
+    if !dir.exists() {
+        return Ok(out);
+    }
+    for entry in std::fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("yaml") {
+            continue;
+        }
+        match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| yaml::parse_search(&text))
+        {
+            Ok(s) => out.push(s),
+            Err(e) => eprintln!("skip {}: {e:#}", path.display()),
+        }
+    }
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(out)
+}
+
+async fn get(State(state): State<AppState>, Path(name): Path<String>) -> ApiResult<Json<SavedSearch>> {
+    Ok(Json(get_inner(&state, &name)?))
+}
+
+pub fn get_inner(state: &AppState, name: &str) -> ApiResult<SavedSearch> {
+    if !valid_search_name(name) {
+        return Err(ApiError::NotFound);
+    }
+    let path = state.inner.paths.repo_dir.join(rel_path(name));
+    if !path.is_file() {
+        return Err(ApiError::NotFound);
+    }
+    Ok(yaml::parse_search(&std::fs::read_to_string(&path)?)?)
+}
+
+async fn save(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(b): Json<SaveBody>,
+) -> ApiResult<Json<SavedSearch>> {
+    Ok(Json(save_inner(&state, &name, b)?))
+}
+
+/// Create or replace `name`. The query must parse and search for
+/// something; options not given keep their previous (or default) values.
+pub fn save_inner(state: &AppState, name: &str, b: SaveBody) -> ApiResult<SavedSearch> {
+    if !valid_search_name(name) {
+        return Err(ApiError::BadRequest(format!(
+            "invalid name {name:?}: use letters, digits, '-', '_' and '.'"
+        )));
+    }
+    let query = b.query.trim().to_string();
+    let now = Local::now().naive_local();
+    let previous = match get_inner(state, name) {
+        Ok(s) => Some(s),
+        Err(ApiError::NotFound) => None,
+        Err(e) => return Err(e),
+    };
+    let regex = b.regex.or(previous.as_ref().map(|p| p.regex)).unwrap_or(false);
+    if regex {
+        crate::query::build_regex(&query, true, false)?;
+    }
+    if query.is_empty() || (!regex && crate::query::parse(&query, now)?.is_empty()) {
+        return Err(ApiError::BadRequest("query is empty".into()));
+    }
+    let field = match b.field {
+        Some(f) => {
+            f.parse::<db::NoteField>().map_err(|e| ApiError::BadRequest(e.to_string()))?;
+            f
+        }
+        None => previous.as_ref().map_or_else(|| "content".into(), |p| p.field.clone()),
+    };
+    let search = SavedSearch {
+        name: name.to_string(),
+        query,
+        field,
+        ignore_case: b.ignore_case.or(previous.as_ref().map(|p| p.ignore_case)).unwrap_or(true),
+        whole_word: b.whole_word.or(previous.as_ref().map(|p| p.whole_word)).unwrap_or(false),
+        regex,
+        created: previous.as_ref().map_or(now, |p| p.created),
+        updated: now,
+    };
+    let dir = state.inner.paths.repo_dir.join(SEARCHES_DIR);
+    let _repo = state.repo();
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join(format!("{name}.yaml")), yaml::serialize_search(&search)?)?;
+    let rel = rel_path(name);
+    if let Err(e) = crate::git::add_and_commit(&state.inner.paths.repo_dir, &[&rel], &format!("search: save {name}")) {
+        eprintln!("warning: git commit failed: {e:#}");
+    }
+    Ok(search)
+}
+
+async fn delete(State(state): State<AppState>, Path(name): Path<String>) -> ApiResult<Json<serde_json::Value>> {
+    delete_inner(&state, &name)?;
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+pub fn delete_inner(state: &AppState, name: &str) -> ApiResult<()> {
+    get_inner(state, name)?;
+    let rel = rel_path(name);
+    let _repo = state.repo();
+    std::fs::remove_file(state.inner.paths.repo_dir.join(&rel))?;
+    if let Err(e) = crate::git::remove_and_commit(&state.inner.paths.repo_dir, &[&rel], &format!("search: delete {name}")) {
+        eprintln!("warning: git rm/commit failed: {e:#}");
+    }
+    Ok(())
+}
+
+async fn notes(State(state): State<AppState>, Path(name): Path<String>) -> ApiResult<Json<Vec<Note>>> {
+    let search = get_inner(&state, &name)?;
+    Ok(Json(run(&state, &search)?))
+}
+
+/// The notes a saved search finds today, most relevant first.
+pub fn run(state: &AppState, search: &SavedSearch) -> ApiResult<Vec<Note>> {
+    let field = search.field.parse().unwrap_or(db::NoteField::Content);
+    let opts = db::NoteMatch {
+        ignore_case: search.ignore_case,
+        whole_word: search.whole_word,
+        order_by: Some(db::NoteOrder::Relevance),
+        regex: search.regex,
+        ..Default::default()
+    };
+    if search.regex {
+        let re = crate::query::build_regex(&search.query, search.ignore_case, search.whole_word)?;
+        let conn = state.db();
+        return Ok(db::search_regex(&conn, field, &re, opts)?);
+    }
+    let query = crate::query::parse(&search.query, Local::now().naive_local())?;
+    let conn = state.db();
+    Ok(db::search_query(&conn, field, &query, opts)?)
+}
+
+pub fn routes() -> axum::Router<AppState> {
+    use axum::routing;
+    axum::Router::new()
+        .route("/api/searches", routing::get(list))
+        .route("/api/searches/:name", routing::get(get).put(save).delete(delete))
+        .route("/api/searches/:name/notes", routing::get(notes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::last_commit;
+
+    fn body(query: &str) -> SaveBody {
+        SaveBody { query: query.into(), field: None, ignore_case: None, whole_word: None, regex: None }
+    }
+
+    #[test]
+    fn save_run_and_delete_commit_yaml() {
+        let (_dir, state) = crate::server::test_state();
+        let now = Local::now().naive_local();
+        {
+            let conn = state.db();
+            for (id, title) in [("n1", "todo: call"), ("n2", "todo: done"), ("n3", "other")] {
+                let n = Note::new(id.into(), title.into(), vec![], "nb".into(), now, now, String::new());
+                db::upsert_note(&conn, &n).unwrap();
+            }
+        }
+        let repo = state.inner.paths.repo_dir.clone();
+        let saved = save_inner(&state, "open", body("todo -done")).unwrap();
+        assert_eq!(saved.field, "content");
+        assert_eq!(last_commit(&state), "search: save open");
+        assert!(repo.join("searches/open.yaml").is_file());
+        let hits = run(&state, &get_inner(&state, "open").unwrap()).unwrap();
+        assert_eq!(hits.iter().map(|n| n.id.as_str()).collect::<Vec<_>>(), ["n1"]);
+
+        // Re-saving keeps created and options not given.
+        let mut b = body("todo");
+        b.field = Some("title".into());
+        save_inner(&state, "open", b).unwrap();
+        let again = save_inner(&state, "open", body("todo OR other")).unwrap();
+        assert_eq!((again.field.as_str(), again.created), ("title", saved.created));
+        assert_eq!(list_inner(&state).unwrap().len(), 1);
+
+        assert!(matches!(save_inner(&state, "../x", body("a")), Err(ApiError::BadRequest(_))));
+        assert!(matches!(save_inner(&state, "x", body(" -- ")), Err(ApiError::BadRequest(_))));
+        assert!(matches!(save_inner(&state, "x", body("has:cats")), Err(ApiError::BadRequest(_))));
+        let mut bad_regex = body("(");
+        bad_regex.regex = Some(true);
+        assert!(matches!(save_inner(&state, "x", bad_regex), Err(ApiError::BadRequest(_))));
+        let mut bad_field = body("a");
+        bad_field.field = Some("body".into());
+        assert!(matches!(save_inner(&state, "x", bad_field), Err(ApiError::BadRequest(_))));
+
+        delete_inner(&state, "open").unwrap();
+        assert_eq!(last_commit(&state), "search: delete open");
+        assert!(list_inner(&state).unwrap().is_empty());
+        assert!(matches!(delete_inner(&state, "open"), Err(ApiError::NotFound)));
+    }
+}
@@ -0,0 +1,226 @@
+//! Tags across all notes: counts, and bulk rename / merge / delete. A bulk
+//! edit rewrites every affected note (trashed ones too) and lands as one
+//! commit. Tags nest like notebooks (`lang/rust`): an edit to `lang` covers
+//! `lang/…` too, and `GET /api/tags/tree` returns the hierarchy.
+
+use axum::extract::State;
+use axum::Json;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+use crate::models::Note;
+use crate::server::error::{ApiError, ApiResult};
+use crate::server::notebooks::{path_tree, reparent, TreeNode};
+use crate::server::notes::persist_notes_with_message;
+use crate::server::AppState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    /// Live notes carrying the tag.
+    pub count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenameBody {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeBody {
+    pub from: Vec<String>,
+    pub into: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteBody {
+    pub tag: String,
+}
+
+/// Reply of the bulk endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagEdit {
+    /// IDs of the rewritten notes.
+    pub notes: Vec<String>,
+}
+
+async fn list(State(state): State<AppState>) -> ApiResult<Json<Vec<TagCount>>> {
+    Ok(Json(list_inner(&state)?))
+}
+
+pub fn list_inner(state: &AppState) -> ApiResult<Vec<TagCount>> {
+    let conn = state.db();
+    Ok(db::tag_counts(&conn)?
+        .into_iter()
+        .map(|(tag, count)| TagCount { tag, count })
+        .collect())
+}
+
+async fn tree(State(state): State<AppState>) -> ApiResult<Json<Vec<TreeNode>>> {
+    Ok(Json(tree_inner(&state)?))
+}
+
+/// The tag hierarchy of the live notes.
+pub fn tree_inner(state: &AppState) -> ApiResult<Vec<TreeNode>> {
+    let notes = {
+        let conn = state.db();
+        db::list_notes(&conn, None)?
+    };
+    Ok(tree_of(&notes))
+}
+
+/// The tag hierarchy of `notes`.
+pub fn tree_of(notes: &[Note]) -> Vec<TreeNode> {
+    path_tree(notes.iter().flat_map(|n| n.tags.iter().map(|t| (n.id.as_str(), t.as_str()))))
+}
+
+async fn rename(State(state): State<AppState>, Json(b): Json<RenameBody>) -> ApiResult<Json<TagEdit>> {
+    Ok(Json(rename_inner(&state, &b.from, &b.to)?))
+}
+
+/// Rename `from` to `to` on every note, `from/…` becoming `to/…`; a note
+/// that already has `to` just loses `from`.
+pub fn rename_inner(state: &AppState, from: &str, to: &str) -> ApiResult<TagEdit> {
+    let to = clean(to)?;
+    let msg = format!("tags: rename {from} -> {to}");
+    retag(state, &[from.to_string()], Some(&to), &msg)
+}
+
+async fn merge(State(state): State<AppState>, Json(b): Json<MergeBody>) -> ApiResult<Json<TagEdit>> {
+    Ok(Json(merge_inner(&state, &b.from, &b.into)?))
+}
+
+/// Replace each tag in `from` with `into` (sub-tags move below `into`).
+pub fn merge_inner(state: &AppState, from: &[String], into: &str) -> ApiResult<TagEdit> {
+    let into = clean(into)?;
+    if from.is_empty() {
+        return Err(ApiError::BadRequest("nothing to merge".into()));
+    }
+    let msg = format!("tags: merge {} into {into}", from.join(", "));
+    retag(state, from, Some(&into), &msg)
+}
+
+async fn delete(State(state): State<AppState>, Json(b): Json<DeleteBody>) -> ApiResult<Json<TagEdit>> {
+    Ok(Json(delete_inner(&state, &b.tag)?))
+}
+
+/// Drop `tag` and its sub-tags from every note.
+pub fn delete_inner(state: &AppState, tag: &str) -> ApiResult<TagEdit> {
+    retag(state, &[tag.to_string()], None, &format!("tags: delete {tag}"))
+}
+
+fn clean(tag: &str) -> ApiResult<String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(ApiError::BadRequest("tag must not be empty".into()));
+    }
+    Ok(tag.to_string())
+}
+
+/// Swap the `from` tags (and their sub-tags) for `to` (or just remove them)
+/// on every note that has one, keeping tag order and dropping duplicates;
+/// save the lot in one commit. Not finding any note is a 404.
+fn retag(state: &AppState, from: &[String], to: Option<&str>, msg: &str) -> ApiResult<TagEdit> {
+    let mut conn = state.db();
+    let mut notes: Vec<Note> = Vec::new();
+    for tag in from {
+        for n in db::notes_with_tag(&conn, tag)? {
+            if !notes.iter().any(|m| m.id == n.id) {
+                notes.push(n);
+            }
+        }
+    }
+    if notes.is_empty() {
+        return Err(ApiError::NotFound);
+    }
+    let now = Local::now().naive_local();
+    for n in &mut notes {
+        let mut tags: Vec<String> = Vec::with_capacity(n.tags.len());
+        for t in n.tags.drain(..) {
+            let moved = from.iter().find_map(|f| reparent(&t, f, to.unwrap_or(f)));
+            let t = match (moved, to) {
+                (Some(_), None) => continue,
+                (Some(moved), Some(_)) => moved,
+                (None, _) => t,
+            };
+            if !tags.contains(&t) {
+                tags.push(t);
+            }
+        }
+        n.tags = tags;
+        n.updated = now;
+    }
+    persist_notes_with_message(state, &mut conn, &notes, &format!("{msg} ({} notes)", notes.len()))?;
+    Ok(TagEdit {
+        notes: notes.into_iter().map(|n| n.id).collect(),
+    })
+}
+
+pub fn routes() -> axum::Router<AppState> {
+    use axum::routing::{get, post};
+    axum::Router::new()
+        .route("/api/tags", get(list))
+        .route("/api/tags/tree", get(tree))
+        .route("/api/tags/rename", post(rename))
+        .route("/api/tags/merge", post(merge))
+        .route("/api/tags/delete", post(delete))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::last_commit;
+
+    fn seed(state: &AppState) -> Vec<Note> {
+        let now = Local::now().naive_local();
+        let notes = vec![
+            Note::new("note-20260101-0000-001".into(), "a".into(), vec!["rust".into(), "lang".into()], "nb".into(), now, now, String::new()),
+            Note::new("note-20260101-0000-002".into(), "b".into(), vec!["rs".into(), "rust".into()], "nb".into(), now, now, String::new()),
+            Note::new("note-20260101-0000-003".into(), "c".into(), vec!["lang".into()], "nb".into(), now, now, String::new()),
+        ];
+        let conn = state.db();
+        for n in &notes {
+            db::upsert_note(&conn, n).unwrap();
+        }
+        notes
+    }
+
+    fn tags_of(state: &AppState, id: &str) -> Vec<String> {
+        let conn = state.db();
+        db::get_note(&conn, id).unwrap().unwrap().tags
+    }
+
+    #[test]
+    fn counts_and_bulk_edits_rewrite_notes_in_one_commit() {
+        let (_dir, state) = crate::server::test_state();
+        let notes = seed(&state);
+        let counts: Vec<(String, u32)> = list_inner(&state).unwrap().into_iter().map(|t| (t.tag, t.count)).collect();
+        assert_eq!(counts, [("lang".into(), 2), ("rs".into(), 1), ("rust".into(), 2)]);
+
+        // Merge folds duplicates: note 2 had both.
+        let r = merge_inner(&state, &["rs".into()], "rust").unwrap();
+        assert_eq!(r.notes, [notes[1].id.as_str()]);
+        assert_eq!(tags_of(&state, &notes[1].id), ["rust"]);
+        assert_eq!(last_commit(&state), "tags: merge rs into rust (1 notes)");
+
+        let r = rename_inner(&state, "rust", " Rust ").unwrap();
+        assert_eq!(r.notes.len(), 2);
+        assert_eq!(tags_of(&state, &notes[0].id), ["Rust", "lang"]);
+        assert_eq!(last_commit(&state), "tags: rename rust -> Rust (2 notes)");
+        let yaml = std::fs::read_to_string(
+            state.inner.paths.repo_dir.join(crate::yaml::rel_path(&notes[0].id).unwrap()),
+        )
+        .unwrap();
+        assert!(yaml.contains("Rust"));
+
+        rename_inner(&state, "Rust", "lang/rust").unwrap();
+        assert_eq!(tree_inner(&state).unwrap()[0].total, 3);
+        delete_inner(&state, "lang").unwrap();
+        assert_eq!(tags_of(&state, &notes[2].id), Vec::<String>::new());
+        assert_eq!(tags_of(&state, &notes[1].id), Vec::<String>::new());
+        assert!(matches!(delete_inner(&state, "lang"), Err(ApiError::NotFound)));
+        assert!(matches!(rename_inner(&state, "Rust", " "), Err(ApiError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,321 @@
+//! Trash: soft-deleted notes, pulses and metrics.
+//!
+//! Deleting an item only stamps its `deleted_at` (in the DB row and the YAML
+//! file, committed as `trash: <id>`); it disappears from every live read but
+//! can be restored until it is purged. Purging removes the row and the YAML
+//! file in a single commit — by hand (`ron trash empty`, the viewer's Trash
+//! page) or automatically once an item has been in the trash for longer than
+//! `trash_retention_days` (see [`spawn_purge_task`]).
+
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::{Duration, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+use crate::server::error::{ApiError, ApiResult};
+use crate::server::notes::{delete_yaml, persist_yaml_with_message};
+use crate::server::AppState;
+use crate::yaml::{self, Item};
+
+/// How often the background task looks for expired trash.
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// One trashed item, whatever its type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    /// `note` | `pulse` | `metric`.
+    pub kind: String,
+    /// Note title or pulse/metric topic.
+    pub title: String,
+    pub deleted_at: NaiveDateTime,
+}
+
+impl TrashEntry {
+    fn of(item: &Item) -> Self {
+        let (id, kind, title, deleted_at) = match item {
+            Item::Note(n) => (&n.id, "note", &n.title, n.deleted_at),
+            Item::Pulse(p) => (&p.id, "pulse", &p.topic, p.deleted_at),
+            Item::Metric(m) => (&m.id, "metric", &m.topic, m.deleted_at),
+        };
+        Self {
+            id: id.clone(),
+            kind: kind.to_string(),
+            title: title.clone(),
+            deleted_at: deleted_at.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurgeReport {
+    /// Ids removed for good.
+    pub purged: Vec<String>,
+}
+
+async fn list(State(state): State<AppState>) -> ApiResult<Json<Vec<TrashEntry>>> {
+    Ok(Json(list_trash_inner(&state)?))
+}
+
+/// Everything in the trash, most recently trashed first. Shared by the JSON
+/// API and the viewer.
+pub fn list_trash_inner(state: &AppState) -> ApiResult<Vec<TrashEntry>> {
+    let mut out: Vec<TrashEntry> = {
+        let conn = state.db();
+        let notes = db::list_trashed_notes(&conn)?.into_iter().map(Item::Note);
+        let pulses = db::list_trashed_pulses(&conn)?.into_iter().map(Item::Pulse);
+        let metrics = db::list_trashed_metrics(&conn)?.into_iter().map(Item::Metric);
+        notes.chain(pulses).chain(metrics).map(|i| TrashEntry::of(&i)).collect()
+    };
+    out.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then_with(|| a.id.cmp(&b.id)));
+    Ok(out)
+}
+
+/// A trashed item by id, `None` when it's live or doesn't exist.
+fn get_trashed(conn: &rusqlite::Connection, id: &str) -> ApiResult<Option<Item>> {
+    Ok(match yaml::subdir_for_id(id) {
+        Some("notes") => db::get_trashed_note(conn, id)?.map(Item::Note),
+        Some("pulses") => db::get_trashed_pulse(conn, id)?.map(Item::Pulse),
+        Some("metrics") => db::get_trashed_metric(conn, id)?.map(Item::Metric),
+        _ => None,
+    })
+}
+
+async fn restore(State(state): State<AppState>, Path(id): Path<String>) -> ApiResult<Json<Item>> {
+    Ok(Json(restore_inner(&state, &id)?))
+}
+
+/// Take an item out of the trash: clear `deleted_at` and commit the file as
+/// `restore: <id>: from trash`. Shared by the JSON API and the viewer.
+pub fn restore_inner(state: &AppState, id: &str) -> ApiResult<Item> {
+    let conn = state.db();
+    let mut item = get_trashed(&conn, id)?.ok_or(ApiError::NotFound)?;
+    match &mut item {
+        Item::Note(n) => {
+            n.deleted_at = None;
+            db::upsert_note(&conn, n)?;
+        }
+        Item::Pulse(p) => {
+            p.deleted_at = None;
+            db::upsert_pulse(&conn, p)?;
+        }
+        Item::Metric(m) => {
+            m.deleted_at = None;
+            db::upsert_metric(&conn, m)?;
+        }
+    }
+    persist_yaml_with_message(state, item.clone(), &format!("restore: {id}: from trash"))?;
+    Ok(item)
+}
+
+async fn purge_one(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<serde_json::Value>> {
+    purge_one_inner(&state, &id)?;
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+/// Permanently delete one trashed item (row + YAML file, committed as
+/// `delete: <id>`). Live items can't be purged directly.
+pub fn purge_one_inner(state: &AppState, id: &str) -> ApiResult<()> {
+    let conn = state.db();
+    if get_trashed(&conn, id)?.is_none() {
+        return Err(ApiError::NotFound);
+    }
+    delete_row_in(&conn, id)?;
+    delete_yaml(state, id)
+}
+
+async fn empty(State(state): State<AppState>) -> ApiResult<Json<PurgeReport>> {
+    Ok(Json(empty_trash_inner(&state)?))
+}
+
+/// Purge everything in the trash in one commit.
+pub fn empty_trash_inner(state: &AppState) -> ApiResult<PurgeReport> {
+    let ids: Vec<String> = list_trash_inner(state)?.into_iter().map(|e| e.id).collect();
+    purge(state, ids, "purge: empty trash")
+}
+
+/// Purge items trashed more than `trash_retention_days` before `now`, in
+/// one commit. A retention of `0` disables this.
+pub fn purge_expired(state: &AppState, now: NaiveDateTime) -> ApiResult<PurgeReport> {
+    let days = state.inner.trash_retention_days;
+    if days == 0 {
+        return Ok(PurgeReport { purged: Vec::new() });
+    }
+    let cutoff = now - Duration::days(i64::from(days));
+    let ids: Vec<String> = list_trash_inner(state)?
+        .into_iter()
+        .filter(|e| e.deleted_at <= cutoff)
+        .map(|e| e.id)
+        .collect();
+    purge(state, ids, &format!("purge: trash older than {days} days"))
+}
+
+/// Purge expired trash now and then every [`PURGE_INTERVAL`] while the
+/// server runs. Failures are logged; the next tick retries.
+pub fn spawn_purge_task(state: AppState) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            tick.tick().await;
+            let state = state.clone();
+            let res = tokio::task::spawn_blocking(move || purge_expired(&state, Local::now().naive_local())).await;
+            match res {
+                Ok(Ok(r)) if !r.purged.is_empty() => {
+                    eprintln!("purged {} expired item(s) from the trash", r.purged.len());
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => eprintln!("warning: trash purge failed: {e}"),
+                Err(e) => eprintln!("warning: trash purge task panicked: {e}"),
+            }
+        }
+    });
+}
+
+/// Drop the DB rows for `ids`, remove their YAML files and commit all the
+/// removals as one change.
+fn purge(state: &AppState, ids: Vec<String>, msg: &str) -> ApiResult<PurgeReport> {
+    if ids.is_empty() {
+        return Ok(PurgeReport { purged: ids });
+    }
+    let conn = state.db();
+    let tx = conn.unchecked_transaction()?;
+    for id in &ids {
+        delete_row_in(&tx, id)?;
+    }
+    tx.commit()?;
+    let repo = &state.inner.paths.repo_dir;
+    let _repo = state.repo();
+    let mut rels: Vec<String> = Vec::new();
+    for id in &ids {
+        let Some(rel) = yaml::rel_path(id) else { continue };
+        let path = repo.join(&rel);
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                eprintln!("warning: yaml delete failed {}: {e}", path.display());
+            }
+        }
+        rels.push(rel);
+    }
+    let refs: Vec<&str> = rels.iter().map(|s| s.as_str()).collect();
+    if let Err(e) = crate::git::remove_and_commit(repo, &refs, msg) {
+        eprintln!("warning: git rm/commit failed: {e:#}");
+    }
+    Ok(PurgeReport { purged: ids })
+}
+
+fn delete_row_in(conn: &rusqlite::Connection, id: &str) -> ApiResult<()> {
+    match yaml::subdir_for_id(id) {
+        Some("notes") => db::delete_note(conn, id)?,
+        Some("pulses") => db::delete_pulse(conn, id)?,
+        Some("metrics") => db::delete_metric(conn, id)?,
+        _ => false,
+    };
+    Ok(())
+}
+
+pub fn routes() -> axum::Router<AppState> {
+    use axum::routing;
+    axum::Router::new()
+        .route("/api/trash", routing::get(list).delete(empty))
+        .route("/api/trash/:id", routing::delete(purge_one))
+        .route("/api/trash/:id/restore", routing::post(restore))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Interval;
+    use crate::server::{last_commit, metrics, notes, pulses};
+
+    async fn note(state: &AppState, title: &str) -> String {
+        let body = notes::CreateBody {
+            title: title.into(),
+            tags: vec![],
+            notebook: String::new(),
+            body: String::new(),
+            related: vec![],
+        };
+        notes::create_note_inner(state, body).await.unwrap().id
+    }
+
+    #[tokio::test]
+    async fn delete_moves_to_trash_and_restore_brings_back() {
+        let (_dir, state) = crate::server::test_state();
+        let id = note(&state, "doomed").await;
+        assert!(notes::delete_note_inner(&state, &id).await.unwrap());
+        assert!(!notes::delete_note_inner(&state, &id).await.unwrap());
+        assert_eq!(last_commit(&state), format!("trash: {id}"));
+
+        // Hidden from live reads, but the file stays, marked.
+        assert!(db::get_note(&state.db(), &id).unwrap().is_none());
+        let path = state.inner.paths.repo_dir.join(yaml::rel_path(&id).unwrap());
+        assert!(std::fs::read_to_string(&path).unwrap().contains("deleted_at:"));
+        let listed = list_trash_inner(&state).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!((listed[0].kind.as_str(), listed[0].title.as_str()), ("note", "doomed"));
+
+        match restore_inner(&state, &id).unwrap() {
+            Item::Note(n) => assert!(n.deleted_at.is_none()),
+            other => panic!("restored {other:?}"),
+        }
+        assert!(db::get_note(&state.db(), &id).unwrap().is_some());
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("deleted_at:"));
+        assert_eq!(last_commit(&state), format!("restore: {id}: from trash"));
+        assert!(list_trash_inner(&state).unwrap().is_empty());
+        assert!(matches!(restore_inner(&state, &id), Err(ApiError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn empty_purges_every_kind_in_one_commit() {
+        let (_dir, state) = crate::server::test_state();
+        let keep = note(&state, "keep").await;
+        let n = note(&state, "gone").await;
+        let p = pulses::create_pulse_inner(&state, pulses::CreateBody { topic: "jog".into(), interval: Interval::Daily })
+            .await
+            .unwrap()
+            .id;
+        let m = metrics::create_metric_inner(&state, metrics::CreateBody { topic: "weight".into() })
+            .await
+            .unwrap()
+            .id;
+        notes::delete_note_inner(&state, &n).await.unwrap();
+        pulses::delete_pulse_inner(&state, &p).await.unwrap();
+        metrics::delete_metric_inner(&state, &m).await.unwrap();
+        assert_eq!(list_trash_inner(&state).unwrap().len(), 3);
+
+        let mut purged = empty_trash_inner(&state).unwrap().purged;
+        purged.sort();
+        let mut want = vec![n.clone(), p.clone(), m.clone()];
+        want.sort();
+        assert_eq!(purged, want);
+        assert_eq!(last_commit(&state), "purge: empty trash");
+        for id in [&n, &p, &m] {
+            assert!(!state.inner.paths.repo_dir.join(yaml::rel_path(id).unwrap()).exists());
+        }
+        assert!(get_trashed(&state.db(), &n).unwrap().is_none());
+        assert!(db::get_note(&state.db(), &keep).unwrap().is_some());
+        assert!(empty_trash_inner(&state).unwrap().purged.is_empty());
+    }
+
+    #[tokio::test]
+    async fn expired_items_are_purged_after_retention() {
+        let (_dir, state) = crate::server::test_state();
+        assert_eq!(state.inner.trash_retention_days, 30);
+        let id = note(&state, "old").await;
+        notes::delete_note_inner(&state, &id).await.unwrap();
+        let now = Local::now().naive_local();
+        assert!(purge_expired(&state, now + Duration::days(29)).unwrap().purged.is_empty());
+        assert_eq!(purge_expired(&state, now + Duration::days(31)).unwrap().purged, vec![id.clone()]);
+        assert_eq!(last_commit(&state), "purge: trash older than 30 days");
+
+        let cfg = crate::paths::ServerConfig { trash_retention_days: 0, ..Default::default() };
+        let (_dir, state) = crate::server::test_state_with(&cfg);
+        let id = note(&state, "forever").await;
+        notes::delete_note_inner(&state, &id).await.unwrap();
+        assert!(purge_expired(&state, now + Duration::days(3650)).unwrap().purged.is_empty());
+    }
+}
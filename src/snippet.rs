@@ -0,0 +1,320 @@
+//! Why a note matched a search: the first part of the note a hit is in,
+//! and short excerpts around the hits with their offsets, for the API, the
+//! viewer's `/search` page and `ron search` to highlight.
+//!
+//! FTS5 says which notes match but not where, so a [`Highlighter`] re-finds
+//! the hits with one regex per note part: built from the query's positive
+//! terms (prefix / whole-word / phrase semantics as in `db::search_query`),
+//! or the search regex itself in regex mode.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{term_is_prefix, NoteField};
+use crate::models::Note;
+use crate::query::{Query, Term};
+
+/// The parts of a note a hit can be in, in the order they are reported.
+pub const PARTS: [&str; 4] = ["title", "tags", "notebook", "body"];
+
+/// Characters of context kept on each side of a body hit.
+const CONTEXT: usize = 40;
+
+/// Body excerpts per hit at most.
+const MAX_BODY_SNIPPETS: usize = 3;
+
+type Range = (usize, usize);
+
+/// An excerpt of one part of a note.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snippet {
+    /// One of [`PARTS`].
+    pub field: String,
+    /// Title, notebook and tags (joined by `; `) whole; a body excerpt has
+    /// its line breaks flattened and `…` where it was cut.
+    pub text: String,
+    /// `[start, end)` UTF-8 byte offsets of the hits within `text`.
+    pub matches: Vec<(usize, usize)>,
+}
+
+/// A search result: the note plus where it matched.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub note: Note,
+    /// The first of [`PARTS`] with a hit; `None` when only filters matched.
+    #[serde(default)]
+    pub field: Option<String>,
+    #[serde(default)]
+    pub snippets: Vec<Snippet>,
+}
+
+impl From<Note> for SearchHit {
+    fn from(note: Note) -> Self {
+        Self { note, field: None, snippets: Vec::new() }
+    }
+}
+
+impl SearchHit {
+    /// The snippet for `part`, if it had a hit.
+    pub fn snippet(&self, part: &str) -> Option<&Snippet> {
+        self.snippets.iter().find(|s| s.field == part)
+    }
+
+    /// The hits in the tags snippet, split back onto each tag (offsets
+    /// into that tag), one list per entry of `note.tags`.
+    pub fn tag_marks(&self) -> Vec<Vec<(usize, usize)>> {
+        let marks = self.snippet("tags").map(|s| s.matches.as_slice()).unwrap_or_default();
+        let mut at = 0;
+        self.note
+            .tags
+            .iter()
+            .map(|t| {
+                let own = marks
+                    .iter()
+                    .filter(|&&(s, e)| s >= at && e <= at + t.len())
+                    .map(|&(s, e)| (s - at, e - at))
+                    .collect();
+                at += t.len() + "; ".len();
+                own
+            })
+            .collect()
+    }
+}
+
+/// One regex per entry of [`PARTS`]; `None` where the search can't match.
+#[derive(Clone, Debug, Default)]
+pub struct Highlighter {
+    parts: [Option<Regex>; 4],
+}
+
+impl Highlighter {
+    /// Regex mode: `re` in every part `field` covers.
+    pub fn regex(re: &Regex, field: NoteField) -> Self {
+        let mut h = Self::default();
+        for (i, part) in PARTS.iter().enumerate() {
+            if covers(field, part) {
+                h.parts[i] = Some(re.clone());
+            }
+        }
+        h
+    }
+
+    /// The positive terms of `query`, each in the parts its scope (or else
+    /// `field`) covers.
+    pub fn query(query: &Query, field: NoteField, ignore_case: bool, whole_word: bool) -> Self {
+        let mut h = Self::default();
+        for (i, part) in PARTS.iter().enumerate() {
+            let mut alternatives: Vec<String> = query
+                .positive_terms()
+                .into_iter()
+                .filter(|t| covers(t.field.unwrap_or(field), part))
+                .filter_map(|t| term_pattern(t, whole_word))
+                .collect();
+            if alternatives.is_empty() {
+                continue;
+            }
+            // Longest first, so `rust|rustacean` marks the whole word.
+            alternatives.sort_by_key(|a| std::cmp::Reverse(a.len()));
+            alternatives.dedup();
+            h.parts[i] = RegexBuilder::new(&alternatives.join("|"))
+                .case_insensitive(ignore_case)
+                .build()
+                .ok();
+        }
+        h
+    }
+
+    /// `note` with its snippets.
+    pub fn hit(&self, note: Note) -> SearchHit {
+        let mut snippets = Vec::new();
+        for (part, re) in PARTS.iter().zip(&self.parts) {
+            let Some(re) = re else { continue };
+            let text = part_text(&note, part);
+            let matches: Vec<(usize, usize)> =
+                re.find_iter(&text).filter(|m| !m.is_empty()).map(|m| (m.start(), m.end())).collect();
+            if matches.is_empty() {
+                continue;
+            }
+            if *part == "body" {
+                snippets.extend(excerpts(&text, &matches));
+            } else {
+                snippets.push(Snippet { field: part.to_string(), text, matches });
+            }
+        }
+        SearchHit { field: snippets.first().map(|s| s.field.clone()), snippets, note }
+    }
+}
+
+/// Whether a search in `field` looks at `part`.
+fn covers(field: NoteField, part: &str) -> bool {
+    match field {
+        NoteField::Content => true,
+        NoteField::Title => part == "title",
+        NoteField::Tags => part == "tags",
+        NoteField::Notebook => part == "notebook",
+    }
+}
+
+fn part_text(n: &Note, part: &str) -> String {
+    match part {
+        "title" => n.title.clone(),
+        "tags" => n.tags.join("; "),
+        "notebook" => n.notebook.clone(),
+        _ => n.body.clone(),
+    }
+}
+
+/// A term as a regex over its words, the way FTS5 tokenizes them: word
+/// boundaries in front, and either a prefix or a whole-word match at the
+/// end.
+fn term_pattern(t: &Term, whole_word: bool) -> Option<String> {
+    let words: Vec<String> =
+        t.text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(regex::escape).collect();
+    if words.is_empty() {
+        return None;
+    }
+    let end = if term_is_prefix(t, whole_word) { r"\w*" } else { r"\b" };
+    Some(format!(r"\b{}{end}", words.join(r"\W+")))
+}
+
+/// Up to [`MAX_BODY_SNIPPETS`] windows of [`CONTEXT`] characters around
+/// the hits in `body`; windows that touch are merged.
+fn excerpts(body: &str, matches: &[(usize, usize)]) -> Vec<Snippet> {
+    // (start, end, hits) in `body` bytes.
+    let mut windows: Vec<(usize, usize, Vec<Range>)> = Vec::new();
+    for &(s, e) in matches {
+        let start = back(body, s, CONTEXT);
+        let end = forward(body, e, CONTEXT);
+        let full = windows.len() == MAX_BODY_SNIPPETS;
+        match windows.last_mut() {
+            Some(w) if start <= w.1 => {
+                w.1 = w.1.max(end);
+                w.2.push((s, e));
+            }
+            _ if full => break,
+            _ => windows.push((start, end, vec![(s, e)])),
+        }
+    }
+    windows
+        .into_iter()
+        .map(|(start, end, hits)| {
+            let mut text = String::new();
+            if start > 0 {
+                text.push('…');
+            }
+            let shift = text.len();
+            text.push_str(&body[start..end].replace(['\n', '\r', '\t'], " "));
+            if end < body.len() {
+                text.push('…');
+            }
+            let matches = hits.into_iter().map(|(s, e)| (s - start + shift, e - start + shift)).collect();
+            Snippet { field: "body".into(), text, matches }
+        })
+        .collect()
+}
+
+/// The byte offset `n` characters before `at`, moved forward to a word
+/// start if the cut would split one.
+fn back(text: &str, at: usize, n: usize) -> usize {
+    let Some((i, _)) = text[..at].char_indices().rev().nth(n.saturating_sub(1)) else {
+        return 0;
+    };
+    if i == 0 {
+        return 0;
+    }
+    match text[i..at].char_indices().find(|(_, c)| c.is_whitespace()) {
+        Some((ws, c)) => i + ws + c.len_utf8(),
+        None => i,
+    }
+}
+
+/// The byte offset `n` characters after `at`, moved back to a word end if
+/// the cut would split one.
+fn forward(text: &str, at: usize, n: usize) -> usize {
+    let Some((i, _)) = text[at..].char_indices().nth(n) else {
+        return text.len();
+    };
+    let i = at + i;
+    match text[at..i].rfind(char::is_whitespace) {
+        Some(ws) => at + ws,
+        None => i,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn note(title: &str, tags: &[&str], body: &str) -> Note {
+        let at = NaiveDate::from_ymd_opt(2026, 8, 6).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        Note::new(
+            "n1".into(),
+            title.into(),
+            tags.iter().map(|t| t.to_string()).collect(),
+            "work".into(),
+            at,
+            at,
+            body.into(),
+        )
+    }
+
+    fn marked(s: &Snippet) -> Vec<&str> {
+        s.matches.iter().map(|&(a, b)| &s.text[a..b]).collect()
+    }
+
+    #[test]
+    fn query_terms_are_found_per_part() {
+        let now = NaiveDate::from_ymd_opt(2026, 8, 6).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let q = crate::query::parse("power t:shell -draft", now).unwrap();
+        let hit = Highlighter::query(&q, NoteField::Content, true, false)
+            .hit(note("PowerShell prompt", &["power"], "Set the powershell prompt.\nNot a draft."));
+        assert_eq!(hit.field.as_deref(), Some("title"));
+        let title = hit.snippet("title").unwrap();
+        // `shell` only matches at a word start; `draft` is negated.
+        assert_eq!(marked(title), vec!["PowerShell"]);
+        assert_eq!(marked(hit.snippet("tags").unwrap()), vec!["power"]);
+        let body = hit.snippet("body").unwrap();
+        assert_eq!(body.text, "Set the powershell prompt. Not a draft.");
+        assert_eq!(marked(body), vec!["powershell"]);
+
+        let whole = Highlighter::query(&q, NoteField::Content, true, true).hit(note("PowerShell", &[], "power up"));
+        assert!(whole.snippet("title").is_none());
+        assert_eq!(marked(whole.snippet("body").unwrap()), vec!["power"]);
+
+        let phrase = crate::query::parse("\"prompt color\"", now).unwrap();
+        let hit = Highlighter::query(&phrase, NoteField::Content, true, false).hit(note("x", &[], "the Prompt  color"));
+        assert_eq!(marked(hit.snippet("body").unwrap()), vec!["Prompt  color"]);
+        let exact = Highlighter::query(&phrase, NoteField::Content, false, false).hit(note("x", &[], "the Prompt color"));
+        assert!(exact.snippets.is_empty() && exact.field.is_none());
+    }
+
+    #[test]
+    fn body_excerpts_keep_context_and_offsets() {
+        let words: Vec<String> = (0..60).map(|i| format!("w{i}")).collect();
+        let mut body = words.join(" ");
+        body.push_str(" needle é end");
+        let re = Regex::new("needle|w30\\b").unwrap();
+        let hit = Highlighter::regex(&re, NoteField::Content).hit(note("t", &[], &body));
+        assert_eq!(hit.snippets.len(), 2);
+        assert!(hit.snippets.iter().all(|s| s.text.starts_with('…')));
+        assert_eq!(marked(&hit.snippets[0]), vec!["w30"]);
+        assert_eq!(marked(&hit.snippets[1]), vec!["needle"]);
+        assert!(hit.snippets[1].text.ends_with("needle é end"));
+        // Words aren't cut at the window edges.
+        assert!(hit.snippets[0].text[3..].starts_with('w'));
+        assert!(hit.snippets[0].text.ends_with("…"));
+
+        let many = "x ".repeat(200) + &"hit ".repeat(3) + &"y ".repeat(100) + &"hit y ".repeat(40) + "hit";
+        let hit = Highlighter::regex(&Regex::new("hit").unwrap(), NoteField::Content).hit(note("t", &[], &many));
+        assert!(hit.snippets.len() <= MAX_BODY_SNIPPETS);
+        assert_eq!(marked(&hit.snippets[0]), vec!["hit", "hit", "hit"]);
+
+        let title_only = Highlighter::regex(&Regex::new("t").unwrap(), NoteField::Title).hit(note("t", &[], "t"));
+        assert_eq!(title_only.snippets.len(), 1);
+
+        let tags = Highlighter::regex(&Regex::new("a").unwrap(), NoteField::Tags).hit(note("t", &["ab", "b", "xa"], ""));
+        assert_eq!(tags.tag_marks(), vec![vec![(0, 1)], vec![], vec![(1, 2)]]);
+    }
+}
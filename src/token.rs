@@ -144,7 +144,7 @@ mod tests {
         let (_, r1) = store.grant("a");
         let (s2, r2) = store.grant("b");
         assert!(store.revoke(&r1.id));
-        assert!(!store.validate(&"not-real".to_string()));
+        assert!(!store.validate("not-real"));
         assert!(store.validate(&s2));
         assert_eq!(store.tokens.len(), 1);
         assert_eq!(store.tokens[0].id, r2.id);
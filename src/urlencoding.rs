@@ -0,0 +1,40 @@
+//! Tiny percent-encoding shims.
+//! Shared by the CLI client's request paths, the viewer's links, the API's
+//! `Link` / `x-did-you-mean` headers and the resource-link scanner.
+
+/// Percent-encode everything except the RFC 3986 unreserved characters.
+pub fn encode_or_self(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~') {
+            out.push(c);
+        } else {
+            let mut buf = [0u8; 4];
+            for b in c.encode_utf8(&mut buf).as_bytes() {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        }
+    }
+    out
+}
+
+/// Decode `%XX` escapes; a malformed escape, or bytes that don't form
+/// UTF-8, leave the text as typed.
+pub fn decode(s: &str) -> String {
+    let hex = |b: &u8| (*b as char).to_digit(16).map(|d| d as u8);
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let (Some(hi), Some(lo)) = (bytes.get(i + 1).and_then(hex), bytes.get(i + 2).and_then(hex)) {
+                out.push(hi * 16 + lo);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(out).unwrap_or_else(|_| s.to_string())
+}
@@ -16,6 +16,8 @@ use crate::db;
 use crate::models::{Draft, Note};
 use crate::server::error::{ApiError, ApiResult};
 use crate::server::AppState;
+use crate::server::notes::Suggestions;
+use crate::snippet::{Highlighter, SearchHit};
 
 const PAGE_HEAD: &str = r#"<!doctype html>
 <html lang="en">
@@ -40,6 +42,7 @@ const PAGE_HEAD: &str = r#"<!doctype html>
     a { color: #2a7ae2; }
     .meta { color: rgba(127,127,127,0.85); font-size: 0.85em; }
     .note-row { padding: 0.5rem 0; border-bottom: 1px solid rgba(127,127,127,0.2); }
+    .snippet { font-size: 0.9em; opacity: 0.85; margin-top: 0.2rem; }
     .tags span { background: rgba(127,127,127,0.15); padding: 0 0.3em;
                  border-radius: 3px; margin-right: 0.2em; font-size: 0.85em; }
     nav { margin-bottom: 1.5rem; padding-bottom: 0.5rem;
@@ -54,6 +57,20 @@ const PAGE_HEAD: &str = r#"<!doctype html>
     .pill { display: inline-block; padding: 0 0.4em; border-radius: 10px;
             font-size: 0.8em; background: rgba(127,127,127,0.18); }
     .pill.done { background: rgba(40, 160, 80, 0.25); }
+    a.dangling { color: rgba(200, 60, 60, 0.9); text-decoration: line-through; }
+    #graph { width: 100%; height: 72vh; border: 1px solid rgba(127,127,127,0.25);
+             border-radius: 4px; }
+    #graph .edge { stroke: rgba(127,127,127,0.55); stroke-width: 1.2; }
+    #graph .edge.body { stroke-dasharray: 4 3; }
+    #graph .node { cursor: pointer; }
+    #graph .node text { font-size: 11px; fill: currentColor; }
+    .wikilink.missing { color: rgba(200, 60, 60, 0.9); border-bottom: 1px dashed;
+                        cursor: help; }
+    .backlinks { margin-top: 1.5rem; border-top: 1px solid rgba(127,127,127,0.25); }
+    .backlinks h3 { font-size: 1em; margin: 0.6rem 0 0.2rem; }
+    .backlinks ul { margin: 0; padding-left: 1.2rem; }
+    .hierarchy ul { margin: 0; padding-left: 1.2rem; list-style: none; }
+    .hierarchy > ul { padding-left: 0.4rem; }
     nav .nav-search { float: right; }
     nav .nav-search input { font: inherit; padding: 0.1em 0.3em; }
     #search-form { margin-bottom: 1rem; }
@@ -69,6 +86,10 @@ const PAGE_HEAD: &str = r#"<!doctype html>
     #preview { box-sizing: border-box; height: 34em; overflow: auto;
                border: 1px dashed rgba(127,127,127,0.4);
                border-radius: 4px; padding: 0.2em 0.6em; }
+    pre.diff span { display: block; }
+    pre.diff .add { background: rgba(40, 160, 80, 0.18); }
+    pre.diff .del { background: rgba(200, 60, 60, 0.18); }
+    pre.diff .hunk { color: rgba(127,127,127,0.85); }
     #edit-split.no-preview .preview-col { display: none; }
     #edit-split.no-preview .editor-col { flex: 1 1 100%; }
     @media (max-width: 900px) {
@@ -91,6 +112,8 @@ fn layout(title: &str, body_attrs: &str, body: &str) -> String {
          <a href=\"/notes/new\">+ new</a>\
          <a href=\"/pulses\">pulses</a>\
          <a href=\"/metrics\">metrics</a>\
+         <a href=\"/graph\">graph</a>\
+         <a href=\"/trash\">trash</a>\
          <form class=\"nav-search\" action=\"/search\" method=\"get\">\
          <input name=\"q\" placeholder=\"search…\" aria-label=\"search notes\">\
          </form></nav>\n{body}{PAGE_FOOT}"
@@ -107,10 +130,31 @@ fn page_wide(title: &str, body: &str) -> String {
     layout(title, " class=\"wide\"", body)
 }
 
-async fn index(State(state): State<AppState>) -> ApiResult<Html<String>> {
-    let (notes, draft) = {
+#[derive(Debug, Deserialize)]
+struct IndexParams {
+    /// Cursor of the previous page's last note.
+    #[serde(default)]
+    after: Option<String>,
+    /// updated (default) | created | title.
+    #[serde(default)]
+    order: Option<String>,
+}
+
+/// Notes per page on the index.
+const INDEX_PAGE: u32 = 50;
+
+async fn index(State(state): State<AppState>, Query(p): Query<IndexParams>) -> ApiResult<Html<String>> {
+    use crate::server::notes::{ListParams, SearchOrder};
+    let (order_str, order) = match p.order.as_deref() {
+        Some("created") => ("created", SearchOrder::Created),
+        Some("title") => ("title", SearchOrder::Title),
+        _ => ("updated", SearchOrder::Updated),
+    };
+    let params = ListParams { limit: Some(INDEX_PAGE), after: p.after.clone(), order: Some(order) };
+    let listed = crate::server::notes::list_inner(&state, &params)?;
+    let draft = {
         let conn = state.db();
-        (db::list_notes(&conn, Some(50))?, db::get_draft(&conn, "new")?)
+        db::get_draft(&conn, "new")?
     };
     let mut body = String::from("<h1>ron</h1>\n");
     if let Some(d) = draft {
@@ -122,40 +166,200 @@ async fn index(State(state): State<AppState>) -> ApiResult<Html<String>> {
         ));
         body.push('\n');
     }
-    body.push_str(&render_results(&notes, None));
+    let mut saved = Vec::new();
+    for search in crate::server::searches::list_inner(&state)? {
+        let count = crate::server::searches::run(&state, &search).map_or(0, |n| n.len());
+        saved.push((search, count));
+    }
+    body.push_str(&saved_searches_html(&saved));
+    // Both trees come from one pass over the live notes.
+    let live = {
+        let conn = state.db();
+        db::list_notes(&conn, None)?
+    };
+    body.push_str(&hierarchy_html("notebooks", "notebook", &crate::server::notebooks::tree_of(&live)));
+    body.push_str(&hierarchy_html("tags", "tags", &crate::server::tags::tree_of(&live)));
+    body.push_str(&order_links(order_str));
+    let hits: Vec<SearchHit> = listed.notes.into_iter().map(SearchHit::from).collect();
+    body.push_str(&render_results(&hits, Some(listed.total)));
+    if let Some(next) = listed.next {
+        body.push_str(&next_page_link(&format!("/?order={order_str}"), &next));
+    }
     Ok(Html(page("ron", &body)))
 }
 
+/// Sort switch for the index.
+fn order_links(current: &str) -> String {
+    let links = ["updated", "created", "title"]
+        .iter()
+        .map(|o| {
+            if *o == current {
+                format!("<strong>{o}</strong>")
+            } else {
+                format!("<a href=\"/?order={o}\">{o}</a>")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" · ");
+    format!("<div class=\"meta\">order: {links}</div>")
+}
+
+/// "Next page" link: `base` (a path with a query string) plus `after`.
+fn next_page_link(base: &str, after: &db::Cursor) -> String {
+    format!(
+        "<p class=\"pager\"><a href=\"{}&amp;after={}\">next page →</a></p>",
+        html_escape::encode_double_quoted_attribute(base),
+        crate::urlencoding::encode_or_self(&after.to_string())
+    )
+}
+
+/// Saved searches as virtual notebooks: each opens the search page with
+/// the stored query and options, next to its current hit count.
+fn saved_searches_html(searches: &[(crate::models::SavedSearch, usize)]) -> String {
+    if searches.is_empty() {
+        return String::new();
+    }
+    let enc = crate::urlencoding::encode_or_self;
+    let items: String = searches
+        .iter()
+        .map(|(s, count)| {
+            format!(
+                "<li><a href=\"/search?q={q}&amp;field={field}&amp;ignore_case={ic}&amp;whole_word={ww}&amp;regex={rx}\" \
+                 title=\"{title}\">{name}</a> <span class=\"meta\">{count}</span></li>",
+                q = enc(&s.query),
+                field = enc(&s.field),
+                ic = s.ignore_case,
+                ww = s.whole_word,
+                rx = s.regex,
+                title = html_escape::encode_double_quoted_attribute(&s.query),
+                name = html_escape::encode_text(&s.name),
+            )
+        })
+        .collect();
+    format!("<details class=\"hierarchy\" open><summary>saved searches</summary><ul>{items}</ul></details>\n")
+}
+
+/// A collapsible notebook / tag tree; each level links to a whole-word
+/// search of that path in `field`, which also finds everything below it.
+fn hierarchy_html(label: &str, field: &str, tree: &[crate::server::notebooks::TreeNode]) -> String {
+    fn nodes(field: &str, tree: &[crate::server::notebooks::TreeNode]) -> String {
+        let mut out = String::from("<ul>");
+        for n in tree {
+            let link = format!(
+                "<a href=\"/search?q={q}&amp;field={field}&amp;whole_word=true\">{name}</a> \
+                 <span class=\"meta\">{total}</span>",
+                q = crate::urlencoding::encode_or_self(&n.path),
+                name = html_escape::encode_text(&n.name),
+                total = n.total,
+            );
+            if n.children.is_empty() {
+                out.push_str(&format!("<li>{link}</li>"));
+            } else {
+                out.push_str(&format!(
+                    "<li><details><summary>{link}</summary>{}</details></li>",
+                    nodes(field, &n.children)
+                ));
+            }
+        }
+        out.push_str("</ul>");
+        out
+    }
+    if tree.is_empty() {
+        return String::new();
+    }
+    format!(
+        "<details class=\"hierarchy\"><summary>{label}</summary>{}</details>\n",
+        nodes(field, tree)
+    )
+}
+
 /// Render notes as clickable rows. When `total` is given and exceeds the
-/// number of rows shown, a "showing N of M" note is emitted.
-fn render_results(notes: &[Note], total: Option<usize>) -> String {
-    if notes.is_empty() {
+/// number of rows shown, a "showing N of M" note is emitted. Titles, tags
+/// and notebooks are highlighted where each hit's snippets say it matched.
+fn render_results(hits: &[SearchHit], total: Option<usize>) -> String {
+    if hits.is_empty() {
         return "<p class=\"meta\">(no matches)</p>".into();
     }
     let header = match total {
-        Some(t) if t > notes.len() => format!("showing {} of {} note(s)", notes.len(), t),
+        Some(t) if t > hits.len() => format!("showing {} of {} note(s)", hits.len(), t),
         Some(t) => format!("{} note(s)", t),
-        None => format!("{} note(s)", notes.len()),
+        None => format!("{} note(s)", hits.len()),
     };
     let mut out = format!("<div class=\"meta\">{header}</div>");
-    for n in notes {
+    for hit in hits {
+        let n = &hit.note;
         let date = n.updated.format("%Y-%m-%d");
+        let marks = |part: &str| hit.snippet(part).map(|s| s.matches.as_slice()).unwrap_or_default();
         let tags = n
             .tags
             .iter()
-            .map(|t| format!("<span>{}</span>", html_escape::encode_text(t)))
+            .zip(hit.tag_marks())
+            .map(|(t, own)| format!("<span>{}</span>", marked_html(t, &own)))
+            .collect::<String>();
+        let snippets = hit
+            .snippets
+            .iter()
+            .filter(|s| s.field == "body")
+            .map(|s| format!("<div class=\"snippet\">{}</div>", marked_html(&s.text, &s.matches)))
             .collect::<String>();
         out.push_str(&format!(
             "<div class=\"note-row\"><a href=\"/view/{id}\">{title}</a> \
              <span class=\"meta\">{date}</span> <span class=\"tags\">{tags}</span><br>\
-             <span class=\"meta\">{nb}</span></div>",
+             <span class=\"meta\">{nb}</span>{snippets}</div>",
             id = html_escape::encode_text(&n.id),
-            title = html_escape::encode_text(&n.title),
+            title = marked_html(&n.title, marks("title")),
             date = date,
             tags = tags,
-            nb = html_escape::encode_text(&n.notebook),
+            nb = marked_html(&n.notebook, marks("notebook")),
+        ));
+    }
+    out
+}
+
+/// "Did you mean" for a search without results: the corrected query and
+/// the notes it nearly matched.
+fn suggestions_html(s: &Suggestions, field: &str) -> String {
+    let mut out = String::new();
+    if let Some(q) = &s.query {
+        out.push_str(&format!(
+            "<p class=\"meta\">did you mean <a href=\"/search?q={enc}&amp;field={field}\">{q}</a>?</p>",
+            enc = crate::urlencoding::encode_or_self(q),
+            field = html_escape::encode_double_quoted_attribute(field),
+            q = html_escape::encode_text(q),
         ));
     }
+    if !s.notes.is_empty() {
+        let items = s
+            .notes
+            .iter()
+            .map(|n| {
+                format!(
+                    "<li><a href=\"/view/{id}\">{title}</a> <span class=\"meta\">{nb}</span></li>",
+                    id = html_escape::encode_double_quoted_attribute(&n.id),
+                    title = html_escape::encode_text(&n.title),
+                    nb = html_escape::encode_text(&n.notebook),
+                )
+            })
+            .collect::<String>();
+        out.push_str(&format!("<p class=\"meta\">close matches:</p><ul>{items}</ul>"));
+    }
+    out
+}
+
+/// HTML-escape `text`, wrapping the `[start, end)` byte ranges `marks` (in
+/// order, not overlapping) in `<mark>`.
+fn marked_html(text: &str, marks: &[(usize, usize)]) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for &(start, end) in marks {
+        if start < last || end > text.len() || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            continue;
+        }
+        out.push_str(&html_escape::encode_text(&text[last..start]));
+        out.push_str(&format!("<mark>{}</mark>", html_escape::encode_text(&text[start..end])));
+        last = end;
+    }
+    out.push_str(&html_escape::encode_text(&text[last..]));
     out
 }
 
@@ -167,7 +371,7 @@ async fn view_note(
         let conn = state.db();
         db::get_note(&conn, &id)?.ok_or(ApiError::NotFound)?
     };
-    let html_body = render::markdown_to_html(&note.body);
+    let html_body = note_body_html(&state, &note.body)?;
     let tags = note
         .tags
         .iter()
@@ -180,31 +384,170 @@ async fn view_note(
         created = note.created.format("%Y-%m-%d"),
         id = html_escape::encode_text(&note.id),
     );
+    let links = crate::server::notes::backlinks_inner(&state, &id)?;
     let related = if note.related.is_empty() {
         String::new()
     } else {
         let links = note
             .related
             .iter()
-            .map(|r| format!("<a href=\"/view/{r}\">{r}</a>", r = html_escape::encode_text(r)))
+            .map(|r| {
+                let dangling = links.dangling.contains(r);
+                let r = html_escape::encode_text(r);
+                if dangling {
+                    format!("<a class=\"dangling\" href=\"/view/{r}/history\" title=\"deleted note\">{r}</a>")
+                } else {
+                    format!("<a href=\"/view/{r}\">{r}</a>")
+                }
+            })
             .collect::<Vec<_>>()
             .join(", ");
         format!("<div class=\"meta\">related: {links}</div>")
     };
+    let backlinks = backlinks_html(&links.backlinks);
     let body = format!(
-        "<h1>{title}</h1>\n{meta}\n{related}\n<div class=\"tags\">{tags}</div>\n<div class=\"body\">{html_body}</div>",
+        "<h1>{title}</h1>\n{meta}\n{related}\n<div class=\"tags\">{tags}</div>\n<div class=\"body\">{html_body}</div>\n{backlinks}",
         title = html_escape::encode_text(&note.title),
     );
     let actions = format!(
         "<div class=\"meta\" style=\"margin-top:1.5rem\">\
          <a href=\"/notes/{id}/edit\">edit</a> · \
-         <form method=\"post\" action=\"/notes/{id}/delete\" onsubmit=\"return confirm('delete this note?')\">\
+         <a href=\"/view/{id}/history\">history</a> · \
+         <form method=\"post\" action=\"/notes/{id}/delete\" onsubmit=\"return confirm('move this note to the trash?')\">\
          <button class=\"uncheck\">delete</button></form></div>",
         id = html_escape::encode_text(&note.id),
     );
     Ok(Html(page(&note.title, &format!("{body}\n{actions}"))).into_response())
 }
 
+/// "Referenced by" section of `/view/:id`; empty when nothing links here.
+fn backlinks_html(links: &[crate::server::notes::Backlink]) -> String {
+    if links.is_empty() {
+        return String::new();
+    }
+    let items: String = links
+        .iter()
+        .map(|l| {
+            let via = match (l.related, l.mentioned) {
+                (true, true) => "related, mentioned",
+                (true, false) => "related",
+                _ => "mentioned",
+            };
+            format!(
+                "<li><a href=\"/view/{id}\">{title}</a> <span class=\"meta\">{nb} · {via}</span></li>",
+                id = html_escape::encode_double_quoted_attribute(&l.id),
+                title = html_escape::encode_text(&l.title),
+                nb = html_escape::encode_text(&l.notebook),
+            )
+        })
+        .collect();
+    format!("<div class=\"backlinks\"><h3>Referenced by</h3><ul>{items}</ul></div>")
+}
+
+/// `/view/:id/history`: the note's commits, newest first, each linking to
+/// the note as of that revision. Works for deleted notes too (the title
+/// then comes from the newest revision that still had the file).
+async fn note_history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Response> {
+    let revs = crate::server::notes::note_history_inner(&state, &id)?;
+    let (current, trashed) = {
+        let conn = state.db();
+        (db::get_note(&conn, &id)?, db::get_trashed_note(&conn, &id)?.is_some())
+    };
+    let title = match &current {
+        Some(n) => n.title.clone(),
+        None => revs
+            .iter()
+            .find(|r| !r.deleted)
+            .and_then(|r| crate::server::notes::note_at_rev_inner(&state, &id, &r.hash).ok())
+            .map(|(_, n)| n.title)
+            .unwrap_or_else(|| id.clone()),
+    };
+    let id_enc = html_escape::encode_double_quoted_attribute(&id);
+    let mut body = format!("<h1>History: {}</h1>\n", html_escape::encode_text(&title));
+    body.push_str(&if current.is_some() {
+        format!("<div class=\"meta\"><a href=\"/view/{id_enc}\">current version</a></div>\n")
+    } else if trashed {
+        format!(
+            "<div class=\"meta\">this note is in the <a href=\"/trash\">trash</a> · \
+             <form method=\"post\" action=\"/trash/{id_enc}/restore\">\
+             <button class=\"check\">restore</button></form></div>\n"
+        )
+    } else {
+        format!(
+            "<div class=\"meta\">this note is deleted · \
+             <form method=\"post\" action=\"/notes/{id_enc}/restore\">\
+             <button class=\"check\">restore last version</button></form></div>\n"
+        )
+    });
+    body.push_str("<table style=\"margin-top:1rem\"><tr><th>rev</th><th>date</th><th>change</th></tr>\n");
+    for r in &revs {
+        let short = html_escape::encode_text(&r.hash[..r.hash.len().min(8)]).into_owned();
+        let rev_cell = if r.deleted {
+            format!("<code>{short}</code>")
+        } else {
+            format!("<a href=\"/view/{id_enc}/history/{hash}\"><code>{short}</code></a>", hash = r.hash)
+        };
+        body.push_str(&format!(
+            "<tr><td>{rev_cell}</td><td>{date}</td><td>{subject}{deleted}</td></tr>\n",
+            date = r.date.format("%Y-%m-%d %H:%M"),
+            subject = html_escape::encode_text(&r.subject),
+            deleted = if r.deleted { " <span class=\"pill\">deleted</span>" } else { "" },
+        ));
+    }
+    body.push_str("</table>\n");
+    Ok(Html(page(&format!("history: {title}"), &body)).into_response())
+}
+
+/// `/view/:id/history/:rev`: the note rendered as of one revision, with the
+/// diff that revision made and links to its neighbours.
+async fn note_revision(
+    State(state): State<AppState>,
+    Path((id, rev)): Path<(String, String)>,
+) -> ApiResult<Response> {
+    let (entry, note) = crate::server::notes::note_at_rev_inner(&state, &id, &rev)?;
+    let revs = crate::server::notes::note_history_inner(&state, &id)?;
+    let pos = revs.iter().position(|r| r.hash == entry.hash).unwrap_or(0);
+    let id_enc = html_escape::encode_double_quoted_attribute(&id);
+    let link = |r: Option<&crate::git::Revision>, label: &str| match r {
+        Some(r) if !r.deleted => {
+            format!("<a href=\"/view/{id_enc}/history/{}\">{label}</a>", r.hash)
+        }
+        _ => format!("<span>{label}</span>"),
+    };
+    let nav = format!(
+        "<div class=\"meta\">{older} · {newer} · <a href=\"/view/{id_enc}/history\">all revisions</a> · \
+         <form method=\"post\" action=\"/notes/{id_enc}/restore\" onsubmit=\"return confirm('restore this version?')\">\
+         <input type=\"hidden\" name=\"rev\" value=\"{hash}\"><button class=\"check\">restore this version</button></form></div>",
+        older = link(revs.get(pos + 1), "← older"),
+        newer = link(pos.checked_sub(1).and_then(|i| revs.get(i)), "newer →"),
+        hash = entry.hash,
+    );
+    let diff = crate::git::file_diff(&state.inner.paths.repo_dir, &entry.hash, &entry.path)?;
+    let tags = note
+        .tags
+        .iter()
+        .map(|t| format!("<span>{}</span>", html_escape::encode_text(t)))
+        .collect::<String>();
+    let body = format!(
+        "<h1>{title}</h1>\n\
+         <div class=\"meta\">revision <code>{short}</code> · {date} · {subject}</div>\n\
+         {nav}\n\
+         <details open style=\"margin-top:1rem\"><summary>changes in this revision</summary>{diff}</details>\n\
+         <div class=\"meta\">{nb}</div>\n<div class=\"tags\">{tags}</div>\n<div class=\"body\">{html_body}</div>",
+        title = html_escape::encode_text(&note.title),
+        short = html_escape::encode_text(&entry.hash[..entry.hash.len().min(8)]),
+        date = entry.date.format("%Y-%m-%d %H:%M"),
+        subject = html_escape::encode_text(&entry.subject),
+        diff = render::diff_to_html(&diff),
+        nb = html_escape::encode_text(&note.notebook),
+        html_body = note_body_html(&state, &note.body)?,
+    );
+    Ok(Html(page(&note.title, &body)).into_response())
+}
+
 async fn favicon() -> Response {
     (StatusCode::NO_CONTENT, [(
         header::CONTENT_TYPE,
@@ -276,9 +619,11 @@ fn note_form_html(
   <button type="submit" style="padding:0.3em 0.8em;margin-top:0.4em">{submit_label}</button>
   <button type="button" id="save-draft-btn" style="padding:0.3em 0.8em;margin-top:0.4em">save draft</button>
   <button type="button" id="preview-btn" style="padding:0.3em 0.8em;margin-top:0.4em">hide preview</button>
+  <button type="button" id="attach-btn" style="padding:0.3em 0.8em;margin-top:0.4em">attach file</button>
+  <input type="file" id="attach-input" multiple hidden>
   <div id="draft-msg" class="meta" style="margin-top:0.4em"></div>
 </form>
-{js}"#,
+{draft_js}{preview_js}{attach_js}"#,
         action = html_escape::encode_double_quoted_attribute(action),
         title = html_escape::encode_double_quoted_attribute(title),
         tags = html_escape::encode_double_quoted_attribute(tags),
@@ -288,7 +633,9 @@ fn note_form_html(
         submit_label = submit_label,
         draft_key = html_escape::encode_double_quoted_attribute(draft_key),
         draft_anchor = html_escape::encode_double_quoted_attribute(draft_anchor),
-        js = format!("{}{}", if draft_key.is_empty() { String::new() } else { DRAFT_JS.to_string() }, PREVIEW_JS),
+        draft_js = if draft_key.is_empty() { "" } else { DRAFT_JS },
+        preview_js = PREVIEW_JS,
+        attach_js = ATTACH_JS,
     )
 }
 
@@ -436,6 +783,54 @@ const DRAFT_JS: &str = r#"<script>
 })();
 </script>"#;
 
+/// Attachment upload for the note form: files dropped onto the body textarea
+/// (or picked via the "attach file" button, for phones) are POSTed to
+/// `/resources` and their markdown links inserted at the cursor. The
+/// synthetic `input` event lets the preview and draft autosave catch up.
+const ATTACH_JS: &str = r#"<script>
+(function () {
+  var ta = document.getElementById('note-body');
+  var msg = document.getElementById('draft-msg');
+  var btn = document.getElementById('attach-btn');
+  var pick = document.getElementById('attach-input');
+  if (!ta) return;
+  function say(t) { if (msg) msg.textContent = t; }
+  function insert(text) {
+    var s = ta.selectionStart, e = ta.selectionEnd, v = ta.value;
+    ta.value = v.slice(0, s) + text + v.slice(e);
+    ta.selectionStart = ta.selectionEnd = s + text.length;
+    ta.dispatchEvent(new Event('input'));
+  }
+  function upload(files) {
+    if (!files || !files.length) return;
+    var fd = new FormData();
+    for (var i = 0; i < files.length; i++) fd.append('file', files[i], files[i].name);
+    say('uploading ' + files.length + ' file(s)…');
+    fetch('/resources', { method: 'POST', body: fd })
+      .then(function (r) { if (!r.ok) throw r.status; return r.json(); })
+      .then(function (list) {
+        insert(list.map(function (u) { return u.markdown; }).join('\n'));
+        say('attached ' + list.length + ' file(s)');
+      })
+      .catch(function (e) { say('upload failed (' + e + ')'); });
+  }
+  function hasFiles(e) {
+    return e.dataTransfer && Array.prototype.indexOf.call(e.dataTransfer.types, 'Files') !== -1;
+  }
+  ta.addEventListener('dragover', function (e) { if (hasFiles(e)) e.preventDefault(); });
+  ta.addEventListener('drop', function (e) {
+    if (!hasFiles(e)) return;
+    e.preventDefault();
+    upload(e.dataTransfer.files);
+  });
+  if (btn && pick) {
+    btn.addEventListener('click', function () { pick.click(); });
+    pick.addEventListener('change', function () { upload(pick.files); pick.value = ''; });
+  }
+})();
+</script>
+"#;
+
 /// Live markdown preview beside the edit textarea: debounced `POST
 /// /notes/preview` (server renders with the same pipeline as `/view/:id`)
 /// injected into `#preview`. The "hide preview" button toggles the panel and
@@ -530,8 +925,18 @@ async fn note_new_get(State(state): State<AppState>) -> ApiResult<Html<String>>
 /// `POST /notes/preview` — body is raw markdown (text/plain), response is the
 /// rendered HTML fragment for the edit form's preview panel. Same renderer
 /// as `/view/:id`, so the preview matches the final page exactly.
-async fn note_preview(body: String) -> Html<String> {
-    Html(render::markdown_to_html(&body))
+async fn note_preview(State(state): State<AppState>, body: String) -> ApiResult<Html<String>> {
+    Ok(Html(note_body_html(&state, &body)?))
+}
+
+/// A note body rendered for the viewer, `[[wiki links]]` resolved against
+/// the DB.
+fn note_body_html(state: &AppState, body: &str) -> ApiResult<String> {
+    let links = crate::server::notes::resolve_wiki_links(state, body)?;
+    Ok(render::markdown_to_html_with_links(body, |target| {
+        let link = links.iter().find(|l| l.target == target)?;
+        Some((link.id.clone()?, link.title.clone()?))
+    }))
 }
 
 async fn note_new_post(
@@ -581,8 +986,10 @@ async fn note_edit_get(
             note.body.clone(),
         ),
     };
+    // The action carries the version the form was opened at, so saving
+    // over someone else's newer edit is refused.
     let form = note_form_html(
-        &format!("/notes/{id}/edit"),
+        &edit_action(&note),
         &title,
         &tags,
         &notebook,
@@ -608,22 +1015,69 @@ async fn note_edit_get(
     Ok(Html(page_wide("edit note", &html)).into_response())
 }
 
+fn edit_action(note: &Note) -> String {
+    format!("/notes/{}/edit?v={}", note.id, note.updated.format("%Y-%m-%dT%H:%M:%S%.f"))
+}
+
+#[derive(Debug, Deserialize)]
+struct EditVersion {
+    /// `updated` of the note when its edit form was rendered.
+    #[serde(default)]
+    v: Option<String>,
+}
+
 async fn note_edit_post(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    Query(version): Query<EditVersion>,
     Form(form): Form<NoteForm>,
 ) -> ApiResult<Response> {
-    let tags = form.tags_vec();
-    let related = form.related_vec();
     let body = crate::server::notes::UpdateBody {
-        title: Some(form.title),
-        tags: Some(tags),
-        notebook: Some(form.notebook),
-        body: Some(form.body),
-        related: Some(related),
+        title: Some(form.title.clone()),
+        tags: Some(form.tags_vec()),
+        notebook: Some(form.notebook.clone()),
+        body: Some(form.body.clone()),
+        related: Some(form.related_vec()),
+        expected_updated: version.v.as_deref().and_then(|v| v.parse().ok()),
     };
-    let note = crate::server::notes::update_note_inner(&state, &id, body).await?;
-    Ok(Redirect::to(&format!("/view/{}", note.id)).into_response())
+    match crate::server::notes::update_note_inner(&state, &id, body).await {
+        Ok(note) => Ok(Redirect::to(&format!("/view/{}", note.id)).into_response()),
+        Err(ApiError::Conflict(..)) => {
+            let current = {
+                let conn = state.db();
+                db::get_note(&conn, &id)?.ok_or(ApiError::NotFound)?
+            };
+            Ok((StatusCode::CONFLICT, Html(page_wide("edit conflict", &conflict_html(&current, &form)))).into_response())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The edit form again, on the note as it is now, with the edit that lost
+/// the race shown beside it to copy from.
+fn conflict_html(current: &Note, mine: &NoteForm) -> String {
+    let form = note_form_html(
+        &edit_action(current),
+        &current.title,
+        &current.tags.join("; "),
+        &current.notebook,
+        &current.related.join("; "),
+        &current.body,
+        "save",
+        &format!("note:{}", current.id),
+        &current.updated.format("%Y-%m-%dT%H:%M:%S").to_string(),
+    );
+    format!(
+        "<h1>Edit conflict</h1>\n<p class=\"meta\">This note was changed at {at} after you opened it; \
+         nothing was saved. The form below holds the current version — merge your edit into it and save.</p>\n\
+         <details open><summary>your edit</summary><div class=\"meta\">{title} · {tags} · {nb}</div>\
+         <textarea readonly rows=\"12\" style=\"width:100%;font-family:monospace\">{body}</textarea></details>\n{form}",
+        at = current.updated.format("%Y-%m-%d %H:%M:%S"),
+        title = html_escape::encode_text(&mine.title),
+        tags = html_escape::encode_text(&mine.tags),
+        nb = html_escape::encode_text(&mine.notebook),
+        body = html_escape::encode_text(&mine.body),
+    )
 }
 
 async fn note_delete_post(
@@ -634,6 +1088,23 @@ async fn note_delete_post(
     Ok(Redirect::to("/").into_response())
 }
 
+#[derive(Debug, Deserialize)]
+struct RestoreForm {
+    #[serde(default)]
+    rev: Option<String>,
+}
+
+/// Restore form on the history pages; lands on the restored note.
+async fn note_restore_post(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Form(f): Form<RestoreForm>,
+) -> ApiResult<Response> {
+    let rev = f.rev.filter(|r| !r.trim().is_empty());
+    let r = crate::server::notes::restore_note_inner(&state, &id, rev.as_deref())?;
+    Ok(Redirect::to(&format!("/view/{}", r.note.id)).into_response())
+}
+
 // ----- pulses ----------------------------------------------------------------
 
 #[derive(Debug, Deserialize)]
@@ -658,9 +1129,7 @@ async fn pulses_index(
 
     let mut body = String::new();
     body.push_str("<h1>Pulses</h1>\n");
-    body.push_str(&format!(
-        "<div class=\"meta\"><a href=\"/pulses\">all</a> · <a href=\"/pulses?active=true\">today's open</a></div>\n"
-    ));
+    body.push_str("<div class=\"meta\"><a href=\"/pulses\">all</a> · <a href=\"/pulses?active=true\">today's open</a></div>\n");
     body.push_str(&pulse_create_form(None));
     if shown.is_empty() {
         body.push_str("<p>(none)</p>");
@@ -687,7 +1156,7 @@ async fn pulses_index(
         let streak = streak_html(p, &now);
         let actions = format!(
             "<a href=\"/pulses/{id}/edit\">edit</a> · \
-             <form method=\"post\" action=\"/pulses/{id}/delete\" onsubmit=\"return confirm('delete this pulse?')\" style=\"display:inline\">\
+             <form method=\"post\" action=\"/pulses/{id}/delete\" onsubmit=\"return confirm('move this pulse to the trash?')\" style=\"display:inline\">\
              <button class=\"uncheck\">del</button></form>",
             id = html_escape::encode_text(&p.id),
         );
@@ -913,7 +1382,7 @@ async fn metric_detail(
     body.push_str(&metric_log_form(&metric.id));
     body.push_str(&format!(
         "<div class=\"meta\" style=\"margin-top:1rem\"><a href=\"/metrics/{id}/edit\">edit topic</a> · \
-         <form method=\"post\" action=\"/metrics/{id}/delete\" onsubmit=\"return confirm('delete this metric?')\">\
+         <form method=\"post\" action=\"/metrics/{id}/delete\" onsubmit=\"return confirm('move this metric to the trash?')\">\
          <button class=\"uncheck\">delete</button></form></div>",
         id = html_escape::encode_text(&metric.id),
     ));
@@ -1022,10 +1491,232 @@ async fn metrics_delete_post(
     Ok(Redirect::to("/metrics").into_response())
 }
 
+// ----- trash -----------------------------------------------------------------
+
+async fn trash_index(State(state): State<AppState>) -> ApiResult<Html<String>> {
+    let entries = crate::server::trash::list_trash_inner(&state)?;
+    let days = state.inner.trash_retention_days;
+    let mut body = String::from("<h1>Trash</h1>\n");
+    body.push_str(&if days == 0 {
+        "<div class=\"meta\">deleted items stay here until the trash is emptied</div>\n".to_string()
+    } else {
+        format!("<div class=\"meta\">deleted items are purged for good after {days} days</div>\n")
+    });
+    if entries.is_empty() {
+        body.push_str("<p class=\"meta\">the trash is empty</p>\n");
+        return Ok(Html(page("trash", &body)));
+    }
+    body.push_str("<table style=\"margin-top:1rem\"><tr><th>type</th><th>title</th><th>deleted</th><th></th></tr>\n");
+    for e in &entries {
+        let id = html_escape::encode_double_quoted_attribute(&e.id);
+        body.push_str(&format!(
+            "<tr><td>{kind}</td><td>{title}</td><td>{deleted}</td><td>\
+             <form method=\"post\" action=\"/trash/{id}/restore\"><button class=\"check\">restore</button></form> \
+             <form method=\"post\" action=\"/trash/{id}/purge\" onsubmit=\"return confirm('delete this for good?')\">\
+             <button class=\"uncheck\">delete forever</button></form></td></tr>\n",
+            kind = e.kind,
+            title = html_escape::encode_text(&e.title),
+            deleted = e.deleted_at.format("%Y-%m-%d %H:%M"),
+        ));
+    }
+    body.push_str(
+        "</table>\n<p><form method=\"post\" action=\"/trash/empty\" \
+         onsubmit=\"return confirm('empty the trash? this cannot be undone from here')\">\
+         <button class=\"uncheck\">empty trash</button></form></p>\n",
+    );
+    Ok(Html(page("trash", &body)))
+}
+
+/// Restore from the trash; lands on the restored item.
+async fn trash_restore_post(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Response> {
+    let to = match crate::server::trash::restore_inner(&state, &id)? {
+        crate::yaml::Item::Note(n) => format!("/view/{}", n.id),
+        crate::yaml::Item::Pulse(_) => "/pulses".to_string(),
+        crate::yaml::Item::Metric(m) => format!("/metrics/{}", m.id),
+    };
+    Ok(Redirect::to(&to).into_response())
+}
+
+async fn trash_purge_post(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Response> {
+    crate::server::trash::purge_one_inner(&state, &id)?;
+    Ok(Redirect::to("/trash").into_response())
+}
+
+async fn trash_empty_post(State(state): State<AppState>) -> ApiResult<Response> {
+    crate::server::trash::empty_trash_inner(&state)?;
+    Ok(Redirect::to("/trash").into_response())
+}
+
+// ----- graph -----------------------------------------------------------------
+
+/// `/graph`: the note network (`server::graph`) drawn as an SVG with a small
+/// force layout in plain JS — no external libraries. The data is embedded
+/// in the page, so the cookie-gated viewer needs no API token.
+async fn graph_page(
+    State(state): State<AppState>,
+    Query(p): Query<crate::server::graph::GraphParams>,
+) -> ApiResult<Html<String>> {
+    let graph = crate::server::graph::graph_inner(&state, &p)?;
+    Ok(Html(page_wide("graph", &graph_body(&graph, &p)?)))
+}
+
+fn graph_body(
+    graph: &crate::server::graph::Graph,
+    p: &crate::server::graph::GraphParams,
+) -> ApiResult<String> {
+    let opts = |all: &[String], current: Option<&str>| -> String {
+        let mut out = String::from("<option value=\"\">all</option>");
+        for v in all {
+            let sel = if current == Some(v.as_str()) { " selected" } else { "" };
+            out.push_str(&format!(
+                "<option value=\"{val}\"{sel}>{text}</option>",
+                val = html_escape::encode_double_quoted_attribute(v),
+                text = html_escape::encode_text(v),
+            ));
+        }
+        out
+    };
+    // `</` can't appear inside a <script> element; `<\/` is the same JSON.
+    let data = serde_json::to_string(graph)
+        .map_err(|e| ApiError::Internal(e.into()))?
+        .replace("</", "<\\/");
+    Ok(format!(
+        "<h1>Graph</h1>\n\
+         <form id=\"graph-form\" method=\"get\" action=\"/graph\">\
+         <label>notebook <select name=\"notebook\" onchange=\"this.form.submit()\">{nbs}</select></label> \
+         <label>tag <select name=\"tag\" onchange=\"this.form.submit()\">{tags}</select></label> \
+         <span class=\"meta\">{n} notes · {e} links · dashed = mentioned in the text</span></form>\n\
+         <svg id=\"graph\"></svg>\n\
+         <script id=\"graph-data\" type=\"application/json\">{data}</script>\n{js}",
+        nbs = opts(&graph.notebooks, p.notebook.as_deref()),
+        tags = opts(&graph.tags, p.tag.as_deref()),
+        n = graph.nodes.len(),
+        e = graph.edges.len(),
+        js = GRAPH_JS,
+    ))
+}
+
+/// Fruchterman–Reingold layout over the embedded graph: a few hundred
+/// cooling steps, animated, then the view box is fitted to the result.
+/// Nodes are coloured by notebook; clicking one opens the note.
+const GRAPH_JS: &str = r#"<script>
+(function () {
+  var data = JSON.parse(document.getElementById('graph-data').textContent);
+  var svg = document.getElementById('graph');
+  var NS = 'http://www.w3.org/2000/svg';
+  var W = svg.clientWidth || 800, H = svg.clientHeight || 600;
+  var nodes = data.nodes.map(function (n, i) {
+    var a = 2 * Math.PI * i / Math.max(1, data.nodes.length);
+    return { id: n.id, title: n.title, notebook: n.notebook,
+             x: W / 2 + W / 3 * Math.cos(a), y: H / 2 + H / 3 * Math.sin(a), dx: 0, dy: 0 };
+  });
+  var byId = {};
+  nodes.forEach(function (n) { byId[n.id] = n; });
+  var links = data.edges.map(function (e) {
+    return { s: byId[e.source], t: byId[e.target], kind: e.kind };
+  });
+  function hue(s) {
+    var h = 0;
+    for (var i = 0; i < s.length; i++) h = (h * 31 + s.charCodeAt(i)) % 360;
+    return h;
+  }
+  var lineEls = links.map(function (l) {
+    var el = document.createElementNS(NS, 'line');
+    el.setAttribute('class', 'edge ' + l.kind);
+    svg.appendChild(el);
+    return el;
+  });
+  var nodeEls = nodes.map(function (n) {
+    var g = document.createElementNS(NS, 'g');
+    g.setAttribute('class', 'node');
+    var c = document.createElementNS(NS, 'circle');
+    c.setAttribute('r', 6);
+    c.setAttribute('fill', 'hsl(' + hue(n.notebook) + ', 60%, 50%)');
+    var tip = document.createElementNS(NS, 'title');
+    tip.textContent = n.title + ' (' + n.notebook + ')';
+    c.appendChild(tip);
+    g.appendChild(c);
+    if (nodes.length <= 80) {
+      var label = document.createElementNS(NS, 'text');
+      label.setAttribute('x', 9);
+      label.setAttribute('y', 4);
+      label.textContent = n.title;
+      g.appendChild(label);
+    }
+    g.addEventListener('click', function () {
+      location.href = '/view/' + encodeURIComponent(n.id);
+    });
+    svg.appendChild(g);
+    return g;
+  });
+
+  var k = Math.sqrt(W * H / Math.max(1, nodes.length)) * 0.5;
+  var TICKS = 300, tick = 0;
+  function step() {
+    var temp = (W / 10) * (1 - tick / TICKS), i, j, a, b, dx, dy, d2, f;
+    for (i = 0; i < nodes.length; i++) {
+      a = nodes[i];
+      for (j = i + 1; j < nodes.length; j++) {
+        b = nodes[j];
+        dx = a.x - b.x; dy = a.y - b.y;
+        d2 = dx * dx + dy * dy || 0.01;
+        f = k * k / d2;
+        a.dx += dx * f; a.dy += dy * f; b.dx -= dx * f; b.dy -= dy * f;
+      }
+    }
+    links.forEach(function (l) {
+      dx = l.t.x - l.s.x; dy = l.t.y - l.s.y;
+      f = Math.sqrt(dx * dx + dy * dy) / k;
+      l.s.dx += dx * f; l.s.dy += dy * f; l.t.dx -= dx * f; l.t.dy -= dy * f;
+    });
+    nodes.forEach(function (n) {
+      n.dx += (W / 2 - n.x) * 0.05; n.dy += (H / 2 - n.y) * 0.05;
+      var len = Math.sqrt(n.dx * n.dx + n.dy * n.dy) || 1, move = Math.min(len, temp);
+      n.x += n.dx / len * move; n.y += n.dy / len * move;
+      n.dx = 0; n.dy = 0;
+    });
+    tick++;
+  }
+  function draw() {
+    links.forEach(function (l, i) {
+      lineEls[i].setAttribute('x1', l.s.x); lineEls[i].setAttribute('y1', l.s.y);
+      lineEls[i].setAttribute('x2', l.t.x); lineEls[i].setAttribute('y2', l.t.y);
+    });
+    nodes.forEach(function (n, i) {
+      nodeEls[i].setAttribute('transform', 'translate(' + n.x + ',' + n.y + ')');
+    });
+  }
+  function fit() {
+    if (!nodes.length) return;
+    var x0 = Infinity, y0 = Infinity, x1 = -Infinity, y1 = -Infinity;
+    nodes.forEach(function (n) {
+      x0 = Math.min(x0, n.x); y0 = Math.min(y0, n.y);
+      x1 = Math.max(x1, n.x); y1 = Math.max(y1, n.y);
+    });
+    var pad = 40;
+    svg.setAttribute('viewBox', [x0 - pad, y0 - pad, x1 - x0 + 2 * pad + 120, y1 - y0 + 2 * pad].join(' '));
+  }
+  function frame() {
+    for (var i = 0; i < 5 && tick < TICKS; i++) step();
+    draw();
+    if (tick < TICKS) requestAnimationFrame(frame); else fit();
+  }
+  svg.setAttribute('viewBox', '0 0 ' + W + ' ' + H);
+  frame();
+})();
+</script>"#;
+
 // ----- static resources (note attachments) ------------------------------------
 
 /// Serve a file from `<repo>/resources/` — where note attachments referenced
-/// as `resources/<name>` in note bodies live (e.g. images migrated from 1.x).
+/// as `resources/<name>` in note bodies live (uploaded via `server::resources`
+/// or migrated from 1.x).
 /// Flat file names only: rejects anything with a path component. Reads the
 /// disk per request, so files dropped into the dir are served without a
 /// restart. Gated by `viewer_secret` like every other viewer route.
@@ -1033,34 +1724,15 @@ async fn resource_file(
     State(state): State<AppState>,
     Path(name): Path<String>,
 ) -> ApiResult<Response> {
-    if name.contains('/')
-        || name.contains('\\')
-        || name.contains("..")
-        || name.starts_with('.')
-    {
+    use crate::server::resources;
+    if !crate::links::valid_name(&name) {
         return Err(ApiError::NotFound);
     }
-    let path = state.inner.paths.repo_dir.join("resources").join(&name);
+    let path = state.inner.paths.repo_dir.join(resources::RESOURCES_DIR).join(&name);
     let bytes = std::fs::read(&path).map_err(|_| ApiError::NotFound)?;
-    let mime = match name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()) {
-        Some(ext) => match ext.as_str() {
-            "png" => "image/png",
-            "jpg" | "jpeg" => "image/jpeg",
-            "gif" => "image/gif",
-            "webp" => "image/webp",
-            "svg" => "image/svg+xml",
-            "bmp" => "image/bmp",
-            "ico" => "image/x-icon",
-            "avif" => "image/avif",
-            "txt" | "md" => "text/plain; charset=utf-8",
-            _ => "application/octet-stream",
-        },
-        None => "application/octet-stream",
-    };
     Ok((
         StatusCode::OK,
-        [(header::CONTENT_TYPE, axum::http::HeaderValue::from_str(mime)
-            .map_err(|e| ApiError::Internal(anyhow::anyhow!(e)))?)],
+        [(header::CONTENT_TYPE, axum::http::HeaderValue::from_static(resources::content_type(&name)))],
         bytes,
     )
         .into_response())
@@ -1134,16 +1806,22 @@ struct SearchParams {
     ignore_case: Option<bool>,
     #[serde(default)]
     whole_word: Option<bool>,
+    /// `q` is a regular expression; hits are highlighted.
+    #[serde(default)]
+    regex: Option<bool>,
     #[serde(default)]
     from: Option<String>,
     #[serde(default)]
     to: Option<String>,
-    /// Sort key: updated (default) | created | title.
+    /// Sort key: relevance (default) | updated | created | title.
     #[serde(default)]
     order: Option<String>,
     /// Max results to show (default 50).
     #[serde(default)]
     limit: Option<String>,
+    /// Cursor of the previous page's last result.
+    #[serde(default)]
+    after: Option<String>,
     /// When truthy, return just the results fragment for live JS injection.
     #[serde(default)]
     partial: Option<String>,
@@ -1162,7 +1840,8 @@ fn order_from_str(s: &str) -> db::NoteOrder {
     match s {
         "created" => db::NoteOrder::Created,
         "title" => db::NoteOrder::Title,
-        _ => db::NoteOrder::Updated,
+        "updated" => db::NoteOrder::Updated,
+        _ => db::NoteOrder::Relevance,
     }
 }
 
@@ -1174,9 +1853,10 @@ async fn search_page(
     let field = field_from_str(&field_str);
     let ignore_case = p.ignore_case.unwrap_or(true);
     let whole_word = p.whole_word.unwrap_or(false);
+    let regex = p.regex.unwrap_or(false);
     let from = p.from.as_deref().and_then(|s| db::parse_when(s, false));
     let to = p.to.as_deref().and_then(|s| db::parse_when(s, true));
-    let order_str = p.order.clone().unwrap_or_else(|| "updated".into());
+    let order_str = p.order.clone().unwrap_or_else(|| "relevance".into());
     let order = order_from_str(&order_str);
     let limit = p
         .limit
@@ -1188,24 +1868,59 @@ async fn search_page(
     let rows = if p.q.trim().is_empty() {
         "<p class=\"meta\">type to search notes…</p>".to_string()
     } else {
-        let matches = {
-            let conn = state.db();
-            db::search_notes(
-                &conn,
-                field,
-                p.q.trim(),
-                db::NoteMatch {
-                    ignore_case,
-                    whole_word,
-                    from,
-                    to,
-                    order_by: Some(order),
-                },
-            )?
+        let opts = db::NoteMatch {
+            ignore_case,
+            whole_word,
+            from,
+            to,
+            order_by: Some(order),
+            regex,
+            fuzzy: false,
+        };
+        let found = if regex {
+            crate::query::build_regex(p.q.trim(), ignore_case, whole_word).map(|re| {
+                let conn = state.db();
+                (db::search_regex(&conn, field, &re, opts), Highlighter::regex(&re, field))
+            })
+        } else {
+            crate::query::parse(p.q.trim(), Local::now().naive_local()).map(|query| {
+                let conn = state.db();
+                let highlighter = Highlighter::query(&query, field, ignore_case, whole_word);
+                (db::search_query(&conn, field, &query, opts), highlighter)
+            })
         };
-        let total = matches.len();
-        let shown: Vec<Note> = matches.into_iter().take(limit).collect();
-        render_results(&shown, Some(total))
+        match found {
+            Ok((matches, highlighter)) => {
+                let matches = matches?;
+                let total = matches.len();
+                let rest = match p.after.as_deref().and_then(|a| a.parse::<db::Cursor>().ok()) {
+                    Some(c) => c.skip(matches, order),
+                    None => matches,
+                };
+                let more = rest.len() > limit;
+                let shown: Vec<SearchHit> = rest.into_iter().take(limit).map(|n| highlighter.hit(n)).collect();
+                let mut rows = render_results(&shown, Some(total));
+                if let (true, Some(last)) = (more, shown.last()) {
+                    let enc = crate::urlencoding::encode_or_self;
+                    let base = format!(
+                        "/search?q={}&field={}&ignore_case={ignore_case}&whole_word={whole_word}&regex={regex}\
+                         &from={}&to={}&order={}&limit={limit}",
+                        enc(&p.q),
+                        enc(&field_str),
+                        enc(p.from.as_deref().unwrap_or_default()),
+                        enc(p.to.as_deref().unwrap_or_default()),
+                        enc(&order_str),
+                    );
+                    rows.push_str(&next_page_link(&base, &db::Cursor::of(&last.note, order)));
+                }
+                if total == 0 && !regex {
+                    let suggestions = crate::server::notes::suggest_inner(&state, p.q.trim(), field)?;
+                    rows.push_str(&suggestions_html(&suggestions, &field_str));
+                }
+                rows
+            }
+            Err(e) => format!("<p class=\"meta\">{}</p>", html_escape::encode_text(&e.to_string())),
+        }
     };
 
     if matches!(p.partial.as_deref(), Some("1") | Some("true")) {
@@ -1216,6 +1931,7 @@ async fn search_page(
         &field_str,
         ignore_case,
         whole_word,
+        regex,
         p.from.as_deref(),
         p.to.as_deref(),
         &order_str,
@@ -1250,6 +1966,7 @@ fn search_body(
     field: &str,
     ignore_case: bool,
     whole_word: bool,
+    regex: bool,
     from: Option<&str>,
     to: Option<&str>,
     order: &str,
@@ -1265,21 +1982,23 @@ fn search_body(
             .collect()
     };
     let field_opts = mk_opts(&["content", "title", "tags", "notebook"], field);
-    let order_opts = mk_opts(&["updated", "created", "title"], order);
+    let order_opts = mk_opts(&["relevance", "updated", "created", "title"], order);
     let case_checked = if !ignore_case { " checked" } else { "" };
     let whole_checked = if whole_word { " checked" } else { "" };
+    let regex_checked = if regex { " checked" } else { "" };
     let from_val = from.unwrap_or("");
     let to_val = to.unwrap_or("");
     format!(
         r#"<h1>Search</h1>
 <form id="search-form" method="get" action="/search" autocomplete="off">
-  <input type="text" name="q" value="{q}" placeholder="query…" autofocus
+  <input type="text" name="q" value="{q}" placeholder="words, &quot;a phrase&quot;, a OR b, -not, t:title, updated:last-7d…" autofocus
          style="width:50%;padding:0.2em 0.4em">
   <select name="field">{field_opts}</select>
   <label>order <select name="order">{order_opts}</select></label>
   <label>limit <input type="number" name="limit" value="{limit}" min="1" max="1000" style="width:4em"></label>
   <label><input type="checkbox" name="ignore_case" value="false"{case_checked}> case&nbsp;sensitive</label>
   <label><input type="checkbox" name="whole_word" value="true"{whole_checked}> whole&nbsp;word</label>
+  <label><input type="checkbox" name="regex" value="true"{regex_checked}> regex</label>
   <span class="meta">updated</span>
   <input type="date" name="from" value="{from_val}">
   - <input type="date" name="to" value="{to_val}">
@@ -1293,6 +2012,7 @@ fn search_body(
         limit = limit,
         case_checked = case_checked,
         whole_checked = whole_checked,
+        regex_checked = regex_checked,
         from_val = html_escape::encode_double_quoted_attribute(from_val),
         to_val = html_escape::encode_double_quoted_attribute(to_val),
         rows = rows,
@@ -1305,11 +2025,14 @@ pub fn routes() -> axum::Router<AppState> {
     axum::Router::new()
         .route("/", get(index))
         .route("/view/:id", get(view_note))
+        .route("/view/:id/history", get(note_history))
+        .route("/view/:id/history/:rev", get(note_revision))
         .route("/search", get(search_page))
         .route("/notes/new", get(note_new_get).post(note_new_post))
         .route("/notes/preview", post(note_preview))
         .route("/notes/:id/edit", get(note_edit_get).post(note_edit_post))
         .route("/notes/:id/delete", post(note_delete_post))
+        .route("/notes/:id/restore", post(note_restore_post))
         .route("/pulses", get(pulses_index).post(pulses_new_post))
         .route("/pulses/:id/check", post(pulse_check))
         .route("/pulses/:id/uncheck", post(pulse_uncheck))
@@ -1320,6 +2043,11 @@ pub fn routes() -> axum::Router<AppState> {
         .route("/metrics/:id/log", post(metric_log_post))
         .route("/metrics/:id/edit", get(metric_edit_get).post(metric_edit_post))
         .route("/metrics/:id/delete", post(metrics_delete_post))
+        .route("/graph", get(graph_page))
+        .route("/trash", get(trash_index))
+        .route("/trash/empty", post(trash_empty_post))
+        .route("/trash/:id/restore", post(trash_restore_post))
+        .route("/trash/:id/purge", post(trash_purge_post))
         .route("/login", get(login_get).post(login_post))
         .route("/resources/:name", get(resource_file))
         .route("/favicon.png", get(favicon))
@@ -1341,6 +2069,89 @@ mod tests {
         }
     }
 
+    #[test]
+    fn search_hits_are_marked_and_escaped() {
+        assert_eq!(marked_html("<b>aa</b> xa", &[(3, 5), (11, 12)]), "&lt;b&gt;<mark>aa</mark>&lt;/b&gt; x<mark>a</mark>");
+        assert_eq!(marked_html("<b>", &[]), "&lt;b&gt;");
+        // Out-of-range or overlapping marks are skipped.
+        assert_eq!(marked_html("ab", &[(0, 1), (0, 2), (1, 9)]), "<mark>a</mark>b");
+
+        let at = "2026-08-06T14:32:00".parse().unwrap();
+        let note = crate::models::Note::new(
+            "n1".into(),
+            "<Alpha>".into(),
+            vec!["beta".into(), "alphabet".into()],
+            "nb".into(),
+            at,
+            at,
+            "one two alpha three".into(),
+        );
+        let re = regex::Regex::new("(?i)alpha").unwrap();
+        let hit = Highlighter::regex(&re, db::NoteField::Content).hit(note);
+        let html = render_results(&[hit], None);
+        assert!(html.contains("&lt;<mark>Alpha</mark>&gt;</a>"));
+        assert!(html.contains("<span>beta</span><span><mark>alpha</mark>bet</span>"));
+        assert!(html.contains("<div class=\"snippet\">one two <mark>alpha</mark> three</div>"));
+    }
+
+    #[test]
+    fn pager_links_carry_the_cursor() {
+        let c = db::Cursor { key: "2026-08-06T14:32:00".into(), id: "note-1".into() };
+        assert_eq!(
+            next_page_link("/?order=updated", &c),
+            "<p class=\"pager\"><a href=\"/?order=updated&amp;after=2026-08-06T14%3A32%3A00%2Cnote-1\">next page →</a></p>"
+        );
+        let links = order_links("title");
+        assert!(links.contains("<a href=\"/?order=created\">created</a>"));
+        assert!(links.contains("<strong>title</strong>"));
+    }
+
+    #[test]
+    fn suggestions_link_the_corrected_query() {
+        let s = Suggestions {
+            query: Some("power <shell>".into()),
+            notes: vec![crate::server::notes::Suggestion {
+                id: "n1".into(),
+                title: "PowerShell & co".into(),
+                notebook: "work".into(),
+            }],
+        };
+        let html = suggestions_html(&s, "title");
+        assert!(html.contains("<a href=\"/search?q=power%20%3Cshell%3E&amp;field=title\">power &lt;shell&gt;</a>?"));
+        assert!(html.contains("<li><a href=\"/view/n1\">PowerShell &amp; co</a> <span class=\"meta\">work</span></li>"));
+        assert_eq!(suggestions_html(&Suggestions::default(), "content"), "");
+    }
+
+    #[test]
+    fn saved_searches_link_to_their_query() {
+        let at = "2026-08-06T14:32:00".parse().unwrap();
+        let s = crate::models::SavedSearch {
+            name: "open".into(),
+            query: "todo -done".into(),
+            field: "title".into(),
+            ignore_case: false,
+            whole_word: true,
+            regex: false,
+            created: at,
+            updated: at,
+        };
+        let html = saved_searches_html(&[(s, 3)]);
+        assert!(html.contains("href=\"/search?q=todo%20-done&amp;field=title&amp;ignore_case=false&amp;whole_word=true&amp;regex=false\""));
+        assert!(html.contains(">open</a> <span class=\"meta\">3</span>"));
+        assert_eq!(saved_searches_html(&[]), "");
+    }
+
+    #[test]
+    fn hierarchy_links_each_level_to_a_subtree_search() {
+        use crate::server::notebooks::path_tree;
+        let tree = path_tree([("a", "work/clients & co"), ("b", "work")]);
+        let html = hierarchy_html("notebooks", "notebook", &tree);
+        assert!(html.contains("<summary><a href=\"/search?q=work&amp;field=notebook&amp;whole_word=true\">work</a> <span class=\"meta\">2</span></summary>"));
+        assert!(html.contains("q=work%2Fclients%20%26%20co&amp;field=notebook"));
+        assert!(html.contains(">clients &amp; co</a>"));
+        assert_eq!(hierarchy_html("tags", "tags", &[]), "");
+    }
+
     #[test]
     fn fresher_draft_only_when_newer_than_note() {
         let note_updated: chrono::NaiveDateTime = "2026-08-16T12:00:00".parse().unwrap();
@@ -1393,13 +2204,27 @@ mod tests {
         assert!(PREVIEW_JS.contains("ron-draft-applied"));
     }
 
+    #[test]
+    fn note_form_uploads_dropped_files() {
+        let form = note_form_html("/notes/new", "", "", "default", "", "", "create", "new", "");
+        assert!(form.contains("id=\"attach-btn\""));
+        assert!(form.contains("id=\"attach-input\""));
+        assert!(form.contains(ATTACH_JS));
+        assert!(ATTACH_JS.contains("fetch('/resources'"));
+        assert!(ATTACH_JS.contains("'drop'"));
+    }
+
     #[tokio::test]
     async fn note_preview_returns_rendered_fragment() {
-        let Html(html) = note_preview("# Hi\n\nhello **world**\n\n$x^2$".to_string()).await;
+        let (_dir, state) = crate::server::test_state();
+        let Html(html) = note_preview(State(state), "# Hi\n\nhello **world**\n\n$x^2$ [[Nope]]".to_string())
+            .await
+            .unwrap();
         assert!(html.contains("<h1>Hi</h1>"));
         assert!(html.contains("<strong>world</strong>"));
         // math survives for MathJax, matching the /view/:id pipeline
         assert!(html.contains("$x^2$"));
+        assert!(html.contains("wikilink missing"));
     }
 
     #[test]
@@ -1425,4 +2250,46 @@ mod tests {
         assert!(html.contains("ron-draft-new"));
         assert!(html.contains("edit draft"));
     }
+
+    #[test]
+    fn backlinks_section_lists_linking_notes() {
+        assert_eq!(backlinks_html(&[]), "");
+        let link = crate::server::notes::Backlink {
+            id: "note-20260101-0000-abc".into(),
+            title: "A <b>".into(),
+            notebook: "nb".into(),
+            related: false,
+            mentioned: true,
+        };
+        let html = backlinks_html(&[link]);
+        assert!(html.contains("Referenced by"));
+        assert!(html.contains("href=\"/view/note-20260101-0000-abc\""));
+        assert!(html.contains("A &lt;b&gt;"));
+        assert!(html.contains("nb · mentioned"));
+    }
+
+    #[test]
+    fn graph_page_embeds_escaped_data_and_local_script() {
+        use crate::server::graph::{Graph, GraphNode, GraphParams};
+        let graph = Graph {
+            nodes: vec![GraphNode {
+                id: "note-20260101-0000-abc".into(),
+                title: "</script><b>".into(),
+                notebook: "work".into(),
+                tags: vec![],
+            }],
+            edges: vec![],
+            notebooks: vec!["home".into(), "work".into()],
+            tags: vec![],
+        };
+        let p = GraphParams { notebook: Some("work".into()), tag: None };
+        let html = graph_body(&graph, &p).unwrap();
+        assert!(html.contains("id=\"graph-data\""));
+        assert!(!html.contains("\"</script><b>\""));
+        assert!(html.contains("<\\/script><b>"));
+        assert!(html.contains("<option value=\"work\" selected>work</option>"));
+        assert!(html.contains("1 notes · 0 links"));
+        assert!(!GRAPH_JS.contains("src="));
+        assert!(GRAPH_JS.contains("'/view/'"));
+    }
 }
@@ -1,6 +1,7 @@
-//! Markdown -> HTML conversion (pulldown-cmark).
+//! Markdown -> HTML conversion (pulldown-cmark), plus unified-diff -> HTML
+//! for the note history pages.
 
-use pulldown_cmark::{html, Options, Parser};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
 
 /// Render GitHub-flavished markdown (tables, strikethrough, fenced code, etc.)
 /// to HTML. MathJax is left untouched: `$...$` and `$$...$$` survive the
@@ -8,16 +9,86 @@ use pulldown_cmark::{html, Options, Parser};
 /// Relative `resources/...` URLs (note attachments, see `absolutize_resource_urls`)
 /// are rewritten to the `/resources/...` server route.
 pub fn markdown_to_html(src: &str) -> String {
+    let mut out = String::new();
+    html::push_html(&mut out, Parser::new_ext(src, options()));
+    absolutize_resource_urls(&out)
+}
+
+/// [`markdown_to_html`] plus `[[target]]` / `[[target|label]]` wiki links
+/// (see [`crate::wikilink`]). `resolve` maps a target to the linked note's
+/// `(id, title)`; resolved links point at `/view/<id>` and show the label or
+/// the note's title, unresolved ones render as a `wikilink missing` span.
+/// Links inside code spans and blocks are left alone.
+pub fn markdown_to_html_with_links(
+    src: &str,
+    resolve: impl Fn(&str) -> Option<(String, String)>,
+) -> String {
+    let mut events: Vec<Event> = Vec::new();
+    let mut text = String::new();
+    let mut in_code = false;
+    for event in Parser::new_ext(src, options()) {
+        match event {
+            // pulldown-cmark splits `[[x]]` into several text events.
+            Event::Text(t) if !in_code => text.push_str(&t),
+            other => {
+                flush_wiki_text(&mut text, &mut events, &resolve);
+                match &other {
+                    Event::Start(Tag::CodeBlock(_)) => in_code = true,
+                    Event::End(TagEnd::CodeBlock) => in_code = false,
+                    _ => {}
+                }
+                events.push(other);
+            }
+        }
+    }
+    flush_wiki_text(&mut text, &mut events, &resolve);
+    let mut out = String::new();
+    html::push_html(&mut out, events.into_iter());
+    absolutize_resource_urls(&out)
+}
+
+pub(crate) fn options() -> Options {
     let mut opts = Options::empty();
     opts.insert(Options::ENABLE_TABLES);
     opts.insert(Options::ENABLE_FOOTNOTES);
     opts.insert(Options::ENABLE_STRIKETHROUGH);
     opts.insert(Options::ENABLE_TASKLISTS);
     opts.insert(Options::ENABLE_SMART_PUNCTUATION);
-    let parser = Parser::new_ext(src, opts);
-    let mut out = String::new();
-    html::push_html(&mut out, parser);
-    absolutize_resource_urls(&out)
+    opts
+}
+
+/// Emit the buffered text run, with its wiki links turned into inline HTML.
+fn flush_wiki_text(
+    text: &mut String,
+    events: &mut Vec<Event>,
+    resolve: &impl Fn(&str) -> Option<(String, String)>,
+) {
+    if text.is_empty() {
+        return;
+    }
+    let run = std::mem::take(text);
+    let mut last = 0;
+    for link in crate::wikilink::parse(&run) {
+        if link.span.start > last {
+            events.push(Event::Text(CowStr::from(run[last..link.span.start].to_string())));
+        }
+        let html = match resolve(&link.target) {
+            Some((id, title)) => format!(
+                "<a class=\"wikilink\" href=\"/view/{}\">{}</a>",
+                html_escape::encode_double_quoted_attribute(&id),
+                html_escape::encode_text(link.label.as_deref().unwrap_or(&title)),
+            ),
+            None => format!(
+                "<span class=\"wikilink missing\" title=\"no such note\">{}</span>",
+                html_escape::encode_text(link.label.as_deref().unwrap_or(&link.target)),
+            ),
+        };
+        events.push(Event::InlineHtml(CowStr::from(html)));
+        last = link.span.end;
+    }
+    if last < run.len() {
+        events.push(Event::Text(CowStr::from(run[last..].to_string())));
+    }
 }
 
 /// Notes (especially those migrated from 1.x) reference attachments as
@@ -35,6 +106,33 @@ pub fn absolutize_resource_urls(html: &str) -> String {
     out
 }
 
+/// Render a unified diff (`git show` output) as a `<pre class="diff">` with
+/// one span per line: `add` / `del` / `hunk` classes for `+` / `-` / `@@`
+/// lines. The `diff --git` / `index` / `---` / `+++` preamble is dropped.
+pub fn diff_to_html(diff: &str) -> String {
+    let mut out = String::from("<pre class=\"diff\">");
+    let mut in_hunk = false;
+    for line in diff.lines() {
+        if line.starts_with("@@") {
+            in_hunk = true;
+        } else if !in_hunk {
+            continue;
+        }
+        let class = match line.chars().next() {
+            Some('@') => "hunk",
+            Some('+') => "add",
+            Some('-') => "del",
+            _ => "ctx",
+        };
+        out.push_str(&format!(
+            "<span class=\"{class}\">{}</span>\n",
+            html_escape::encode_text(line)
+        ));
+    }
+    out.push_str("</pre>");
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,4 +190,35 @@ mod tests {
         assert!(html.contains(r#"href="https://example.com/x""#));
         assert!(html.contains(r#"src="other/img.png""#));
     }
+
+    #[test]
+    fn wiki_links_resolve_or_render_missing() {
+        let resolve = |t: &str| (t == "note-20260101-0000-abc" || t == "Home")
+            .then(|| ("note-20260101-0000-abc".to_string(), "Home <page>".to_string()));
+        let html = markdown_to_html_with_links(
+            "go [[note-20260101-0000-abc]], [[Home|back]] or [[Nope]]\n\n`[[Home]]`\n\n```\n[[Home]]\n```",
+            resolve,
+        );
+        assert!(html.contains(
+            "<a class=\"wikilink\" href=\"/view/note-20260101-0000-abc\">Home &lt;page&gt;</a>,"
+        ));
+        assert!(html.contains(">back</a>"));
+        assert!(html.contains("<span class=\"wikilink missing\" title=\"no such note\">Nope</span>"));
+        assert!(html.contains("<code>[[Home]]</code>"));
+        assert!(html.contains("<code>[[Home]]\n</code>"));
+        // without a resolver pass the syntax is plain text
+        assert!(markdown_to_html("[[Home]]").contains("[[Home]]"));
+    }
+
+    #[test]
+    fn diff_lines_are_classed_and_escaped() {
+        let diff = "diff --git a/n.yaml b/n.yaml\nindex 1..2 100644\n--- a/n.yaml\n+++ b/n.yaml\n@@ -1,2 +1,2 @@\n title: x\n-body: <old>\n+body: new\n";
+        let html = diff_to_html(diff);
+        assert!(!html.contains("diff --git"));
+        assert!(!html.contains("+++"));
+        assert!(html.contains("<span class=\"hunk\">@@ -1,2 +1,2 @@</span>"));
+        assert!(html.contains("<span class=\"ctx\"> title: x</span>"));
+        assert!(html.contains("<span class=\"del\">-body: &lt;old&gt;</span>"));
+        assert!(html.contains("<span class=\"add\">+body: new</span>"));
+    }
 }
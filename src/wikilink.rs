@@ -0,0 +1,101 @@
+//! Wiki-style links in note bodies: `[[note-20260806-1432-a8f]]`,
+//! `[[Some Title]]`, or either with a label, `[[target|label]]`.
+//!
+//! This module only finds and rewrites them; resolving a target to a note
+//! needs the DB (`server::notes::resolve_wiki_links`). The viewer renders
+//! them as links (`viewer::render::markdown_to_html_with_links`), the CLI's
+//! `ron view` as plain markdown links carrying the note's title.
+
+use std::ops::Range;
+
+/// One `[[...]]` occurrence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikiLink {
+    /// A note ID or a note title, trimmed.
+    pub target: String,
+    /// Text after `|`, if given.
+    pub label: Option<String>,
+    /// Byte range of the whole `[[...]]` in the source text.
+    pub span: Range<usize>,
+}
+
+/// All wiki links in `text`, in order. The inner text must be non-empty and
+/// on one line, without further brackets.
+pub fn parse(text: &str) -> Vec<WikiLink> {
+    let mut out = Vec::new();
+    let mut from = 0;
+    while let Some(open) = text[from..].find("[[").map(|i| from + i) {
+        let inner_start = open + 2;
+        let Some(len) = text[inner_start..].find("]]") else {
+            break;
+        };
+        let inner = &text[inner_start..inner_start + len];
+        if inner.contains(['[', ']', '\n']) {
+            // `[[[x]]` and friends: retry from the next bracket.
+            from = open + 1;
+            continue;
+        }
+        let (target, label) = match inner.split_once('|') {
+            Some((t, l)) => (t.trim(), Some(l.trim()).filter(|l| !l.is_empty())),
+            None => (inner.trim(), None),
+        };
+        let end = inner_start + len + 2;
+        if !target.is_empty() {
+            out.push(WikiLink {
+                target: target.to_string(),
+                label: label.map(str::to_string),
+                span: open..end,
+            });
+        }
+        from = end;
+    }
+    out
+}
+
+/// The form titles are compared in when resolving `[[Title]]`: trimmed and
+/// Unicode-lowercased. The DB keeps it per note (`notes.title_folded`) so a
+/// lookup there agrees with in-memory matching.
+pub fn fold_title(title: &str) -> String {
+    title.trim().to_lowercase()
+}
+
+/// Replace every wiki link in `text` with `f(link)`; the rest is copied
+/// through unchanged.
+pub fn rewrite(text: &str, mut f: impl FnMut(&WikiLink) -> String) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for link in parse(text) {
+        out.push_str(&text[last..link.span.start]);
+        out.push_str(&f(&link));
+        last = link.span.end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_targets_labels_and_spans() {
+        let text = "a [[note-20260806-1432-a8f]] b [[ Some Title | shown ]] [[]] [[x\ny]] [[[z]]";
+        let links = parse(text);
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].target, "note-20260806-1432-a8f");
+        assert_eq!(links[0].label, None);
+        assert_eq!(&text[links[0].span.clone()], "[[note-20260806-1432-a8f]]");
+        assert_eq!(links[1].target, "Some Title");
+        assert_eq!(links[1].label.as_deref(), Some("shown"));
+        assert_eq!(links[2].target, "z");
+    }
+
+    #[test]
+    fn rewrite_keeps_surrounding_text() {
+        let out = rewrite("see [[A]] and [[B|b]].", |l| {
+            format!("<{}>", l.label.as_deref().unwrap_or(&l.target))
+        });
+        assert_eq!(out, "see <A> and <b>.");
+        assert_eq!(rewrite("no links", |_| unreachable!()), "no links");
+    }
+}
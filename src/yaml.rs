@@ -2,13 +2,17 @@
 //!
 //! Each item (Note, Pulse, Metric) is stored in its own file under the repo
 //! directory, in a per-type subdirectory: `notes/`, `pulses/`, `metrics/`.
+//! Saved searches live in `searches/<name>.yaml`, outside [`Item`], with
+//! their own `SEARCH_FORMAT_VERSION` and `SEARCH_UPGRADES` chain.
 //! The file name is `<id>.yaml` (NOT `.md`, since the body lives inside YAML
 //! as a string). All files carry a `version` and a `type` field for
-//! forward-compatible migrations.
+//! forward-compatible migrations: `parse` upgrades an older file in memory
+//! through the `UPGRADES` chain, and `upgrade_repo` rewrites outdated files
+//! on disk.
 //!
 //! Example (note at `notes/note-20260806-1432-a8f.yaml`):
 //! ```yaml
-//! version: 2
+//! version: 3
 //! type: note
 //! id: note-20260806-1432-a8f
 //! title: Hello
@@ -29,12 +33,35 @@ use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::models::{Metric, Note, Pulse};
+use crate::models::{Metric, Note, Pulse, SavedSearch};
 
 /// On-disk format version. Bumped on every breaking change to the YAML shape.
-pub const FORMAT_VERSION: u32 = 2;
+pub const FORMAT_VERSION: u32 = 3;
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Oldest on-disk version this build can still read. v2 is the first YAML
+/// format (1.x notes were markdown; see `migrate`).
+pub const MIN_FORMAT_VERSION: u32 = 2;
+
+/// Rewrites one raw YAML document from version `v` to `v + 1`, in place.
+/// Works on the untyped mapping since the old shape need not deserialize
+/// into today's models.
+pub type Upgrade = fn(&mut serde_yaml::Mapping) -> Result<()>;
+
+/// Per-version upgrade chain: `UPGRADES[i]` takes a document from version
+/// `MIN_FORMAT_VERSION + i` to the next one. Bumping `FORMAT_VERSION` means
+/// appending the step that gets the previous version there.
+const UPGRADES: &[Upgrade] = &[
+    v2_to_v3, // optional `deleted_at` (trash)
+];
+
+/// v3 marks trashed items with `deleted_at`. A v2 file was never trashed,
+/// so it reads as-is; the bump only keeps older builds from loading
+/// trashed v3 items as live ones.
+fn v2_to_v3(_: &mut serde_yaml::Mapping) -> Result<()> {
+    Ok(())
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum Item {
     Note(Note),
@@ -42,6 +69,36 @@ pub enum Item {
     Metric(Metric),
 }
 
+/// Subdirectory of the repo holding saved searches. They have no ID, DB
+/// row or trash, so [`read_all`] and [`upgrade_repo`] leave it alone.
+pub const SEARCHES_DIR: &str = "searches";
+
+/// On-disk format version of saved searches, bumped independently of
+/// `FORMAT_VERSION`: an item change need not touch searches, nor the
+/// other way around. Searches started at v2, the item format of the day.
+pub const SEARCH_FORMAT_VERSION: u32 = 2;
+
+/// Oldest saved-search version this build can still read.
+pub const MIN_SEARCH_FORMAT_VERSION: u32 = 2;
+
+/// Upgrade chain for saved searches, like `UPGRADES`: `SEARCH_UPGRADES[i]`
+/// takes a search from `MIN_SEARCH_FORMAT_VERSION + i` to the next version.
+const SEARCH_UPGRADES: &[Upgrade] = &[];
+
+/// On-disk shape of a saved search: `version`, `type: search`, fields.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum SearchDoc {
+    Search(SavedSearch),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct VersionedSearch {
+    version: u32,
+    #[serde(flatten)]
+    doc: SearchDoc,
+}
+
 /// Wrapper that injects/validates the `version` field on write/read.
 #[derive(Serialize, Deserialize, Debug)]
 struct Versioned {
@@ -74,19 +131,84 @@ pub fn serialize_metric(metric: &Metric) -> Result<String> {
     Ok(serde_yaml::to_string(&v)?)
 }
 
-/// Parse any versioned item from YAML text.
+pub fn serialize_search(search: &SavedSearch) -> Result<String> {
+    let v = VersionedSearch {
+        version: SEARCH_FORMAT_VERSION,
+        doc: SearchDoc::Search(search.clone()),
+    };
+    Ok(serde_yaml::to_string(&v)?)
+}
+
+/// Parse a saved search, upgrading older versions through
+/// `SEARCH_UPGRADES` like [`parse`] does for items.
+pub fn parse_search(text: &str) -> Result<SavedSearch> {
+    parse_search_with(text, SEARCH_UPGRADES)
+}
+
+fn parse_search_with(text: &str, upgrades: &[Upgrade]) -> Result<SavedSearch> {
+    let mut doc: serde_yaml::Value = serde_yaml::from_str(text).context("parsing saved search")?;
+    let from = doc_version(&doc)?;
+    if from != MIN_SEARCH_FORMAT_VERSION + upgrades.len() as u32 {
+        let map = doc
+            .as_mapping_mut()
+            .context("parsing saved search: not a mapping")?;
+        upgrade(map, from, MIN_SEARCH_FORMAT_VERSION, upgrades)?;
+    }
+    let v: VersionedSearch = serde_yaml::from_value(doc).context("parsing saved search")?;
+    let SearchDoc::Search(search) = v.doc;
+    Ok(search)
+}
+
+/// Parse any versioned item from YAML text, upgrading older versions.
 pub fn parse(text: &str) -> Result<Item> {
-    let v: Versioned = serde_yaml::from_str(text).context("parsing YAML item")?;
-    if v.version != FORMAT_VERSION {
-        anyhow::bail!(
-            "unsupported on-disk version {}: this build handles {}",
-            v.version,
-            FORMAT_VERSION
-        );
+    parse_with(text, UPGRADES)
+}
+
+fn parse_with(text: &str, upgrades: &[Upgrade]) -> Result<Item> {
+    let mut doc: serde_yaml::Value = serde_yaml::from_str(text).context("parsing YAML item")?;
+    let from = doc_version(&doc)?;
+    let current = MIN_FORMAT_VERSION + upgrades.len() as u32;
+    if from != current {
+        let map = doc
+            .as_mapping_mut()
+            .context("parsing YAML item: not a mapping")?;
+        upgrade(map, from, MIN_FORMAT_VERSION, upgrades)?;
     }
+    let v: Versioned = serde_yaml::from_value(doc).context("parsing YAML item")?;
     Ok(v.item)
 }
 
+fn doc_version(doc: &serde_yaml::Value) -> Result<u32> {
+    doc.get("version")
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u32::try_from(v).ok())
+        .context("parsing YAML item: missing or invalid `version`")
+}
+
+/// Walk `map` from version `from` up to the end of `upgrades`, a chain
+/// starting at version `min`, stamping the new version after each step.
+fn upgrade(map: &mut serde_yaml::Mapping, from: u32, min: u32, upgrades: &[Upgrade]) -> Result<()> {
+    let to = min + upgrades.len() as u32;
+    if from > to {
+        anyhow::bail!("unsupported on-disk version {from}: this build handles up to {to}; upgrade ron");
+    }
+    if from < min {
+        anyhow::bail!("unsupported on-disk version {from}: this build reads {min}..={to}");
+    }
+    for v in from..to {
+        let step = upgrades[(v - min) as usize];
+        step(map).with_context(|| format!("upgrading YAML v{v} -> v{}", v + 1))?;
+        map.insert("version".into(), (v + 1).into());
+    }
+    Ok(())
+}
+
+/// The `version` field of a YAML file's text, without parsing the item.
+pub fn file_version(text: &str) -> Result<u32> {
+    let doc: serde_yaml::Value = serde_yaml::from_str(text).context("parsing YAML item")?;
+    doc_version(&doc)
+}
+
 /// Subdirectory of the repo dir holding this item type.
 pub fn subdir(item: &Item) -> &'static str {
     match item {
@@ -109,13 +231,18 @@ pub fn subdir_for_id(id: &str) -> Option<&'static str> {
     }
 }
 
-/// Write an item to `<dir>/<subdir>/<id>.yaml`, creating the subdirectory.
-pub fn write_item(dir: &Path, item: &Item) -> Result<PathBuf> {
-    let (id, text) = match item {
+/// An item's id and its current-version YAML text.
+pub(crate) fn serialize_item(item: &Item) -> Result<(String, String)> {
+    Ok(match item {
         Item::Note(n) => (n.id.clone(), serialize(n)?),
         Item::Pulse(p) => (p.id.clone(), serialize_pulse(p)?),
         Item::Metric(m) => (m.id.clone(), serialize_metric(m)?),
-    };
+    })
+}
+
+/// Write an item to `<dir>/<subdir>/<id>.yaml`, creating the subdirectory.
+pub fn write_item(dir: &Path, item: &Item) -> Result<PathBuf> {
+    let (id, text) = serialize_item(item)?;
     let dir = dir.join(subdir(item));
     fs::create_dir_all(&dir).with_context(|| format!("mkdir {}", dir.display()))?;
     let path = dir.join(format!("{id}.yaml"));
@@ -134,34 +261,78 @@ pub fn read_item(path: &Path) -> Result<Item> {
     parse(&text)
 }
 
-/// Read all items in a repo dir. Walks the per-type subdirectories; for the
-/// legacy flat layout it also scans the repo root. Non-`.yaml` files and
-/// parse errors are skipped with a warning printed to stderr.
-pub fn read_all(dir: &Path) -> Result<Vec<Item>> {
-    let mut items = Vec::new();
-    let mut dirs: Vec<PathBuf> = [""]
-        .iter()
-        .map(|sub| dir.join(sub))
-        .collect();
-    // Walk the tree; picks up both the per-type subdirs (notes/, pulses/,
-    // metrics/) and the legacy flat layout (files directly in the root).
+/// Every item `.yaml` file under a repo dir (skipping `.git` and
+/// `searches/`), in no particular order.
+fn yaml_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
     while let Some(d) = dirs.pop() {
         for entry in fs::read_dir(&d)? {
             let path = entry?.path();
             if path.is_dir() {
-                if path.file_name().and_then(|s| s.to_str()) == Some(".git") {
+                if path.file_name().and_then(|s| s.to_str()) == Some(".git") || path == dir.join(SEARCHES_DIR) {
                     continue;
                 }
                 dirs.push(path);
-                continue;
+            } else if path.extension().and_then(|s| s.to_str()) == Some("yaml") {
+                out.push(path);
             }
-            if path.extension().and_then(|s| s.to_str()) != Some("yaml") {
+        }
+    }
+    Ok(out)
+}
+
+/// Outcome of `upgrade_repo`: files rewritten to `FORMAT_VERSION` (or that
+/// would be, on a dry run) and files that could not be upgraded.
+#[derive(Debug, Default)]
+pub struct RepoUpgrade {
+    pub upgraded: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Rewrite every YAML file older than `FORMAT_VERSION` in place at the
+/// current version. Files already current (or newer) are left alone. With
+/// `dry_run`, only report what would be rewritten.
+pub fn upgrade_repo(dir: &Path, dry_run: bool) -> Result<RepoUpgrade> {
+    let mut report = RepoUpgrade::default();
+    let mut files = yaml_files(dir)?;
+    files.sort();
+    for path in files {
+        let text = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let outdated = match file_version(&text) {
+            Ok(v) => v < FORMAT_VERSION,
+            Err(e) => {
+                report.failed.push((path, format!("{e:#}")));
                 continue;
             }
-            match read_item(&path) {
-                Ok(item) => items.push(item),
-                Err(e) => eprintln!("skip {}: {e:#}", path.display()),
+        };
+        if !outdated {
+            continue;
+        }
+        match parse(&text).and_then(|item| serialize_item(&item)) {
+            Ok((_, new_text)) => {
+                if !dry_run {
+                    fs::write(&path, new_text).with_context(|| format!("writing {}", path.display()))?;
+                }
+                report.upgraded.push(path);
             }
+            Err(e) => report.failed.push((path, format!("{e:#}"))),
+        }
+    }
+    Ok(report)
+}
+
+/// Read all items in a repo dir. Walks the per-type subdirectories; for the
+/// legacy flat layout it also scans the repo root. Non-`.yaml` files and
+/// parse errors are skipped with a warning printed to stderr.
+pub fn read_all(dir: &Path) -> Result<Vec<Item>> {
+    let mut items = Vec::new();
+    // Walk the tree; picks up both the per-type subdirs (notes/, pulses/,
+    // metrics/) and the legacy flat layout (files directly in the root).
+    for path in yaml_files(dir)? {
+        match read_item(&path) {
+            Ok(item) => items.push(item),
+            Err(e) => eprintln!("skip {}: {e:#}", path.display()),
         }
     }
     Ok(items)
@@ -188,7 +359,7 @@ mod tests {
     fn note_yaml_round_trip() {
         let original = note();
         let text = serialize(&original).unwrap();
-        assert!(text.contains("version: 2"));
+        assert!(text.contains("version: 3"));
         assert!(text.contains("type: note"));
         let parsed = parse(&text).unwrap();
         match parsed {
@@ -266,10 +437,61 @@ mod tests {
 
     #[test]
     fn version_mismatch_is_rejected() {
-        let text = serialize(&note()).unwrap().replace("version: 2", "version: 3");
+        let text = serialize(&note()).unwrap().replace("version: 3", "version: 4");
         assert!(parse(&text).is_err());
     }
 
+    /// A made-up v3 -> v4 step for exercising the chain; it tags the title
+    /// so the test can tell it ran.
+    fn fake_v3_to_v4(map: &mut serde_yaml::Mapping) -> Result<()> {
+        let title = map.get("title").and_then(|t| t.as_str()).unwrap_or_default();
+        let upgraded = format!("{title} (upgraded)");
+        map.insert("title".into(), upgraded.into());
+        Ok(())
+    }
+
+    #[test]
+    fn upgrade_chain_ends_at_format_version() {
+        assert_eq!(MIN_FORMAT_VERSION + UPGRADES.len() as u32, FORMAT_VERSION);
+    }
+
+    #[test]
+    fn older_versions_are_upgraded_through_the_chain() {
+        let v3 = serialize(&note()).unwrap();
+        let v2 = v3.replace("version: 3", "version: 2");
+        let steps: &[Upgrade] = &[v2_to_v3, fake_v3_to_v4];
+        for old in [&v2, &v3] {
+            match parse_with(old, steps).unwrap() {
+                Item::Note(n) => assert_eq!(n.title, "Hello (upgraded)"),
+                _ => panic!("expected Note"),
+            }
+        }
+        // a file already at the chain's head is not touched
+        let v4 = v3.replace("version: 3", "version: 4");
+        match parse_with(&v4, steps).unwrap() {
+            Item::Note(n) => assert_eq!(n.title, "Hello"),
+            _ => panic!("expected Note"),
+        }
+        // below the oldest supported version there is no path forward
+        let v1 = v3.replace("version: 3", "version: 1");
+        assert!(parse_with(&v1, steps).is_err());
+        assert_eq!(file_version(&v1).unwrap(), 1);
+    }
+
+    #[test]
+    fn upgrade_repo_rewrites_only_outdated_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = tmp.path();
+        let current = write_item(repo, &Item::Note(note())).unwrap();
+        let before = std::fs::read_to_string(&current).unwrap();
+        std::fs::write(repo.join("notes").join("broken.yaml"), "version: [").unwrap();
+
+        let report = upgrade_repo(repo, false).unwrap();
+        assert!(report.upgraded.is_empty());
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(std::fs::read_to_string(&current).unwrap(), before);
+    }
+
     #[test]
     fn write_uses_per_type_subdirs_and_read_all_finds_both_layouts() {
         let tmp = tempfile::tempdir().unwrap();
@@ -291,4 +513,56 @@ mod tests {
         ids.sort();
         assert_eq!(ids, vec!["note-20060521-1500-cad".to_string(), "note-20260806-1432-a8f".to_string()]);
     }
+
+    #[test]
+    fn saved_search_round_trips_and_stays_out_of_read_all() {
+        let tmp = tempfile::tempdir().unwrap();
+        let at = "2026-08-06T14:32:00".parse().unwrap();
+        let search = SavedSearch {
+            name: "todo".into(),
+            query: "todo -done".into(),
+            field: "content".into(),
+            ignore_case: true,
+            whole_word: false,
+            regex: false,
+            created: at,
+            updated: at,
+        };
+        let text = serialize_search(&search).unwrap();
+        assert!(text.contains("type: search"));
+        assert_eq!(parse_search(&text).unwrap(), search);
+
+        std::fs::create_dir_all(tmp.path().join(SEARCHES_DIR)).unwrap();
+        std::fs::write(tmp.path().join(SEARCHES_DIR).join("todo.yaml"), text).unwrap();
+        assert!(read_all(tmp.path()).unwrap().is_empty());
+        assert!(upgrade_repo(tmp.path(), true).unwrap().failed.is_empty());
+    }
+
+    #[test]
+    fn saved_searches_upgrade_through_their_own_chain() {
+        assert_eq!(MIN_SEARCH_FORMAT_VERSION + SEARCH_UPGRADES.len() as u32, SEARCH_FORMAT_VERSION);
+        let at = "2026-08-06T14:32:00".parse().unwrap();
+        let search = SavedSearch {
+            name: "todo".into(),
+            query: "todo".into(),
+            field: "content".into(),
+            ignore_case: true,
+            whole_word: false,
+            regex: false,
+            created: at,
+            updated: at,
+        };
+        let v2 = serialize_search(&search).unwrap();
+        assert!(v2.starts_with("version: 2\n"));
+        // A newer item format doesn't make a current search look outdated...
+        assert_eq!(parse_search(&v2).unwrap(), search);
+        // ...and a search step runs on its own chain.
+        let steps: &[Upgrade] = &[|map| {
+            map.insert("query".into(), "todo -done".into());
+            Ok(())
+        }];
+        assert_eq!(parse_search_with(&v2, steps).unwrap().query, "todo -done");
+        let v3 = v2.replace("version: 2", "version: 3");
+        assert!(parse_search(&v3).is_err());
+    }
 }